@@ -0,0 +1,71 @@
+//! Compares boxed `List`s with arena-allocated `ArenaList`s.
+//!
+//! Each workload is run on lists of `n` items: pushing every item onto an
+//! empty stack, popping every item off a full stack, summing every item,
+//! and "churning" a stack the way the VM's stack register is used, by
+//! repeatedly pushing two items and popping one. Run with `cargo bench`.
+
+extern crate seax_util;
+
+use seax_util::list::{List, ArenaList, Stack};
+
+use std::time::{Duration, Instant};
+
+/// Pushes `n` items onto an empty stack.
+fn push<S: Stack<u64>>(n: u64) -> S {
+    (0..n).fold(S::empty(), |stack, i| stack.push(i))
+}
+
+/// Pops every item off a stack, returning their sum.
+fn pop<S: Stack<u64>>(mut stack: S) -> u64 {
+    let mut sum = 0;
+    while let Some((item, rest)) = stack.pop() {
+        sum += item;
+        stack = rest;
+    }
+    sum
+}
+
+/// Pushes two items and pops one, `n` times.
+fn churn<S: Stack<u64>>(n: u64) -> S {
+    (0..n).fold(S::empty(), |stack, i| {
+        let (top, rest) = stack.push(i).push(i).pop().unwrap();
+        rest.push(top)
+    })
+}
+
+fn time<T, F: FnOnce() -> T>(f: F) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+fn main() {
+    println!("{:>8} {:>8} {:>10} {:>10} {:>10} {:>10}",
+        "n", "list", "push (us)", "pop (us)", "iter (us)", "churn (us)");
+    for &n in &[1000, 10000, 100000, 1000000] {
+        let expected = n * (n - 1) / 2;
+
+        let (list, push_time) = time(|| push::<List<u64>>(n));
+        let (sum, iter_time) = time(|| list.iter().sum::<u64>());
+        assert_eq!(sum, expected);
+        let (sum, pop_time) = time(|| pop(list));
+        assert_eq!(sum, expected);
+        let (list, churn_time) = time(|| churn::<List<u64>>(n));
+        assert_eq!(list.length() as u64, n * 2);
+        println!("{:>8} {:>8} {:>10} {:>10} {:>10} {:>10}",
+            n, "boxed", push_time.as_micros(), pop_time.as_micros(),
+            iter_time.as_micros(), churn_time.as_micros());
+
+        let (list, push_time) = time(|| push::<ArenaList<u64>>(n));
+        let (sum, iter_time) = time(|| list.iter().sum::<u64>());
+        assert_eq!(sum, expected);
+        let (sum, pop_time) = time(|| pop(list));
+        assert_eq!(sum, expected);
+        let (list, churn_time) = time(|| churn::<ArenaList<u64>>(n));
+        assert_eq!(list.length() as u64, n * 2);
+        println!("{:>8} {:>8} {:>10} {:>10} {:>10} {:>10}",
+            n, "arena", push_time.as_micros(), pop_time.as_micros(),
+            iter_time.as_micros(), churn_time.as_micros());
+    }
+}
@@ -0,0 +1,145 @@
+;; List utilities, sorting and a small arithmetic evaluator, exercising
+;; the shapes of code the compiler produces for typical Scheme programs.
+
+(define (length xs)
+  (if (null? xs) 0 (+ 1 (length (cdr xs)))))
+
+(define (append xs ys)
+  (if (null? xs) ys (cons (car xs) (append (cdr xs) ys))))
+
+(define (reverse-onto xs acc)
+  (if (null? xs) acc (reverse-onto (cdr xs) (cons (car xs) acc))))
+
+(define (reverse xs) (reverse-onto xs '()))
+
+(define (map f xs)
+  (if (null? xs) '() (cons (f (car xs)) (map f (cdr xs)))))
+
+(define (filter p xs)
+  (cond ((null? xs) '())
+        ((p (car xs)) (cons (car xs) (filter p (cdr xs))))
+        (else (filter p (cdr xs)))))
+
+(define (foldl f acc xs)
+  (if (null? xs) acc (foldl f (f acc (car xs)) (cdr xs))))
+
+(define (foldr f init xs)
+  (if (null? xs) init (f (car xs) (foldr f init (cdr xs)))))
+
+(define (range from to)
+  (if (> from to) '() (cons from (range (+ from 1) to))))
+
+(define (take xs n)
+  (if (or (= n 0) (null? xs)) '() (cons (car xs) (take (cdr xs) (- n 1)))))
+
+(define (drop xs n)
+  (if (or (= n 0) (null? xs)) xs (drop (cdr xs) (- n 1))))
+
+(define (sum xs) (foldl (lambda (a b) (+ a b)) 0 xs))
+
+(define (product xs) (foldl (lambda (a b) (* a b)) 1 xs))
+
+(define (insert x xs)
+  (cond ((null? xs) (list x))
+        ((<= x (car xs)) (cons x xs))
+        (else (cons (car xs) (insert x (cdr xs))))))
+
+(define (insertion-sort xs) (foldr insert '() xs))
+
+(define (quicksort xs)
+  (if (null? xs)
+      '()
+      (let ((pivot (car xs)) (rest (cdr xs)))
+        (append (quicksort (filter (lambda (x) (< x pivot)) rest))
+                (cons pivot
+                      (quicksort (filter (lambda (x) (>= x pivot)) rest)))))))
+
+(define (merge xs ys)
+  (cond ((null? xs) ys)
+        ((null? ys) xs)
+        ((<= (car xs) (car ys)) (cons (car xs) (merge (cdr xs) ys)))
+        (else (cons (car ys) (merge xs (cdr ys))))))
+
+(define (merge-sort xs)
+  (if (or (null? xs) (null? (cdr xs)))
+      xs
+      (let ((half (/ (length xs) 2)))
+        (merge (merge-sort (take xs half)) (merge-sort (drop xs half))))))
+
+(define (sorted? xs)
+  (or (null? xs)
+      (null? (cdr xs))
+      (and (<= (car xs) (car (cdr xs))) (sorted? (cdr xs)))))
+
+(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))
+
+(define (fib n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))
+
+(define (gcd a b) (if (= b 0) a (gcd b (% a b))))
+
+(define (expt base n)
+  (cond ((= n 0) 1)
+        ((= (% n 2) 0) (let ((half (expt base (/ n 2)))) (* half half)))
+        (else (* base (expt base (- n 1))))))
+
+(define (divides? d n) (= (% n d) 0))
+
+(define (prime? n)
+  (and (> n 1)
+       (null? (filter (lambda (d) (divides? d n)) (range 2 (- n 1))))))
+
+(define (primes-up-to n) (filter prime? (range 2 n)))
+
+(define (assoc key alist)
+  (cond ((null? alist) '())
+        ((= key (car (car alist))) (car alist))
+        (else (assoc key (cdr alist)))))
+
+(define (lookup key alist default)
+  (let ((entry (assoc key alist)))
+    (if (null? entry) default (cdr entry))))
+
+;; expressions are numbers, variables (characters), or lists of an
+;; operator character and two operands
+(define (evaluate expr env)
+  (cond ((atom? expr)
+         (if (char? expr) (lookup expr env 0) expr))
+        ((= (car expr) #\+)
+         (+ (evaluate (car (cdr expr)) env)
+            (evaluate (car (cdr (cdr expr))) env)))
+        ((= (car expr) #\-)
+         (- (evaluate (car (cdr expr)) env)
+            (evaluate (car (cdr (cdr expr))) env)))
+        ((= (car expr) #\*)
+         (* (evaluate (car (cdr expr)) env)
+            (evaluate (car (cdr (cdr expr))) env)))
+        (else 0)))
+
+(define (write-digits n)
+  (if (< n 10)
+      (write-char (integer->char (+ n 48)))
+      (begin (write-digits (/ n 10))
+             (write-char (integer->char (+ (% n 10) 48))))))
+
+(define (write-list xs)
+  (if (null? xs)
+      (write-char #\newline)
+      (begin (write-digits (car xs))
+             (write-char #\space)
+             (write-list (cdr xs)))))
+
+(define (main)
+  (let ((numbers '(31 4 15 9 26 5 35 8 97 93 23 84 62 64 33 83 27 95)))
+    (begin
+      (write-list (quicksort numbers))
+      (write-list (merge-sort numbers))
+      (write-list (insertion-sort (reverse numbers)))
+      (write-list (map fib (range 0 15)))
+      (write-list (primes-up-to 50))
+      (write-list (list (fact 10) (gcd 1071 462) (expt 3 13)
+                        (sum (range 1 100)) (product (range 1 12))))
+      (write-list (list (evaluate '(#\+ #\x (#\* 3 #\y))
+                                  (list (cons #\x 4) (cons #\y 7)))))
+      (if (and (sorted? (quicksort numbers)) (sorted? (merge-sort numbers)))
+          0
+          1))))
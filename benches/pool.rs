@@ -0,0 +1,135 @@
+//! Compares the size of programs encoded with and without a constant pool.
+//!
+//! Run with `cargo bench --bench pool -- FILE...` to re-encode programs
+//! compiled by the Seax Scheme compiler, which is the only meaningful
+//! measure of how much a pool saves.
+//!
+//! Without any files, the programs in `benches/data` are encoded instead.
+//! `lists.seax` is `lists.scm` compiled with the compiler's code
+//! generation scheme: top-level definitions in a `DUM`/`RAP` block,
+//! `LDF` closures, `SEL`/`JOIN` for conditionals, and argument lists built
+//! with `NIL` and `CONS`. They are followed by synthetic programs, which
+//! imitate the shape of compiler output for a module defining `n`
+//! mutually recursive list functions. These are more repetitive than most
+//! real programs, so they only show how the encoder scales, not the size
+//! of the pool's savings in practice.
+
+#[macro_use] extern crate seax_util;
+
+use seax_util::bytecode::{Encoder, Decoder, Program};
+use seax_util::cell::SVMCell::*;
+use seax_util::cell::Atom::*;
+use seax_util::cell::Inst::*;
+use seax_util::list::List;
+use seax_util::list::List::{Cons,Nil};
+use seax_util::SVMCell;
+
+use std::env;
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::time::Instant;
+
+/// Compiled programs measured when no files are given.
+const PROGRAMS: &[(&str, &[u8])] = &[
+    ("lists.seax", include_bytes!("data/lists.seax"))
+];
+
+/// Loads the variable at `(level . index)` in the environment.
+fn ld(level: u64, index: u64) -> List<SVMCell> {
+    list!(InstCell(LD), list_cell![ AtomCell(UInt(level)), AtomCell(UInt(index)) ])
+}
+
+/// Concatenates several instruction sequences.
+fn seq(parts: Vec<List<SVMCell>>) -> List<SVMCell> {
+    parts.iter().flat_map(|part| part.iter().cloned()).collect()
+}
+
+/// The body of a function `(lambda (xs acc) (if (null? xs) acc
+/// (fn_i (cdr xs) (op (car xs) acc))))`.
+fn function(i: u64, n: u64) -> SVMCell {
+    let op = [ADD, MUL, SUB, CONS][i as usize % 4];
+    let recur = seq(vec![
+        list!(InstCell(NIL)),
+        ld(1, 2), ld(1, 1), list!(InstCell(CAR), InstCell(op), InstCell(CONS)),
+        ld(1, 1), list!(InstCell(CDR), InstCell(CONS)),
+        ld(2, (i + 1) % n + 1), list!(InstCell(AP), InstCell(JOIN))
+    ]);
+    let body = seq(vec![
+        ld(1, 1), list!(InstCell(NULL), InstCell(SEL)),
+        list!(ListCell(Box::new(seq(vec![ld(1, 2), list!(InstCell(JOIN))])))),
+        list!(ListCell(Box::new(recur))),
+        list!(InstCell(RET))
+    ]);
+    ListCell(Box::new(body))
+}
+
+/// A module of `n` mutually recursive functions, applied to a list.
+fn module(n: u64) -> List<SVMCell> {
+    let mut code = list!(InstCell(DUM), InstCell(NIL));
+    for i in 0..n {
+        code.append(InstCell(LDF));
+        code.append(function(i, n));
+        code.append(InstCell(CONS));
+    }
+    code.append(InstCell(LDF));
+    code.append(list_cell![
+        InstCell(NIL), InstCell(LDC), AtomCell(SInt(0)), InstCell(CONS),
+        InstCell(LDC), list_cell![ AtomCell(SInt(1)), AtomCell(SInt(2)) ],
+        InstCell(CONS),
+        InstCell(LD), list_cell![ AtomCell(UInt(1)), AtomCell(UInt(1)) ],
+        InstCell(AP), InstCell(RET)
+    ]);
+    code.append(InstCell(RAP));
+    code.append(InstCell(STOP));
+    code
+}
+
+/// Encodes a program with and without a pool, and prints a row of the
+/// results.
+fn measure(name: &str, program: Program) {
+    let plain = Encoder::new().encode(&program).unwrap();
+
+    let mut encoder = Encoder::new();
+    encoder.set_constant_pool(true);
+    let start = Instant::now();
+    let pooled = encoder.encode(&program).unwrap();
+    let encode_time = start.elapsed();
+
+    let start = Instant::now();
+    let decoded = Decoder::new(&mut Cursor::new(&pooled[..])).read_program();
+    let decode_time = start.elapsed();
+    assert_eq!(decoded, Ok(program));
+
+    println!("{:>24} {:>10} {:>10} {:>6.1}% {:>12} {:>12}",
+        name, plain.len(), pooled.len(),
+        100.0 * pooled.len() as f64 / plain.len() as f64,
+        encode_time.as_micros(), decode_time.as_micros());
+}
+
+fn main() {
+    // cargo passes `--bench` along with any paths
+    let paths: Vec<String> = env::args().skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    println!("{:>24} {:>10} {:>10} {:>7} {:>12} {:>12}",
+        "program", "plain", "pooled", "ratio", "encode (us)", "decode (us)");
+    if paths.is_empty() {
+        for &(name, bytes) in PROGRAMS {
+            let program = Decoder::new(&mut Cursor::new(bytes)).read_program()
+                .unwrap_or_else(|why| panic!("couldn't decode {}: {}", name, why));
+            measure(name, program);
+        }
+        for &n in &[1, 4, 16, 64, 256] {
+            measure(&format!("synthetic, {} fns", n), Program::new(module(n)));
+        }
+    }
+    for path in paths {
+        let mut bytes = Vec::new();
+        File::open(&path)
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .unwrap_or_else(|why| panic!("couldn't read {}: {}", path, why));
+        let program = Decoder::new(&mut Cursor::new(bytes)).read_program()
+            .unwrap_or_else(|why| panic!("couldn't decode {}: {}", path, why));
+        measure(&path, program);
+    }
+}
@@ -0,0 +1,12 @@
+//! Decodes arbitrary bytes in every way `seax_util` can.
+//!
+//! Run with `cargo fuzz run decode`. Decoding may fail, but must never
+//! panic, overflow the stack, or allocate without bound.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seax_util::bytecode::decode_everything;
+
+fuzz_target!(|bytes: &[u8]| {
+    decode_everything(bytes);
+});
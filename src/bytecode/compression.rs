@@ -0,0 +1,46 @@
+//! DEFLATE compression of bytecode files.
+//!
+//! Compressing files requires the `compression` feature. Without it,
+//! compressed files are rejected with an error explaining why.
+
+#[cfg(feature = "compression")]
+use std::io::{Read, Write};
+
+#[cfg(feature = "compression")]
+use flate2::Compression;
+#[cfg(feature = "compression")]
+use flate2::read::DeflateDecoder;
+#[cfg(feature = "compression")]
+use flate2::write::DeflateEncoder;
+
+/// Compresses bytes as a raw DEFLATE stream.
+#[cfg(feature = "compression")]
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    // writing to a Vec can't fail
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Decompresses a raw DEFLATE stream.
+///
+/// Returns an error rather than decompressing more than `max_len` bytes.
+#[cfg(feature = "compression")]
+pub fn decompress(bytes: &[u8], max_len: usize) -> Result<Vec<u8>, String> {
+    let mut result = Vec::new();
+    DeflateDecoder::new(bytes)
+        .take(max_len as u64 + 1)
+        .read_to_end(&mut result)
+        .map_err(|why| format!("could not decompress file: {}", why))?;
+    if result.len() > max_len {
+        return Err(format!(
+            "could not decompress file: longer than {} bytes", max_len));
+    }
+    Ok(result)
+}
+
+#[cfg(not(feature = "compression"))]
+pub fn decompress(_: &[u8], _: usize) -> Result<Vec<u8>, String> {
+    Err(String::from(
+        "compressed files require the `compression` feature"))
+}
@@ -0,0 +1,43 @@
+//! CRC-32 checksums, as used to verify the integrity of bytecode files.
+//!
+//! This is the common CRC-32 (the one used by zlib, PNG, and Ethernet),
+//! with the reflected polynomial 0xEDB88320.
+
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+const TABLE: [u32; 256] = make_table();
+
+const fn make_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ POLYNOMIAL } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Incrementally computes the CRC-32 of a sequence of byte slices.
+#[derive(Clone,Copy,Debug)]
+pub struct Crc32 {
+    state: u32
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 { Crc32 { state: 0xFFFF_FFFF } }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            let idx = ((self.state ^ byte as u32) & 0xFF) as usize;
+            self.state = (self.state >> 8) ^ TABLE[idx];
+        }
+    }
+
+    pub fn finish(&self) -> u32 { !self.state }
+}
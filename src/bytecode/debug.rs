@@ -0,0 +1,196 @@
+//! Debugging information mapping cells to source locations.
+
+use std::io::Cursor;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use super::{write_len, Decoder};
+
+/// A span of source text, from which a cell was compiled.
+///
+/// Lines and columns are numbered starting at one. The end of the span is
+/// inclusive.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "debuginfo", issue = "94") )]
+pub struct Span {
+    /// The index of the source file in the `DebugInfo`'s file table.
+    pub file: u32,
+    /// The line on which the span starts.
+    pub line: u32,
+    /// The column at which the span starts.
+    pub column: u32,
+    /// The line on which the span ends.
+    pub end_line: u32,
+    /// The column at which the span ends.
+    pub end_column: u32
+}
+
+/// Debugging information for a program, mapping cell positions to the
+/// spans of source text they were compiled from.
+///
+/// The position of a cell is its index in a pre-order traversal of the
+/// program's code, starting at zero: a list cell comes immediately before
+/// the cells it contains, which come before the cell following the list.
+/// Positions do not depend on how the program is encoded, so they remain
+/// the same regardless of whether constant pools or compact atoms are
+/// used.
+///
+/// Compilers need not record a span for every cell. A cell without a span
+/// of its own is taken to have the span of the nearest cell before it that
+/// does.
+///
+/// # Examples
+/// ```
+/// # use seax_util::bytecode::{DebugInfo, Span};
+/// let mut debug = DebugInfo::new();
+/// let file = debug.add_file("fib.scm");
+/// debug.insert(0, Span { file, line: 1, column: 1, end_line: 1, end_column: 9 });
+/// debug.insert(4, Span { file, line: 2, column: 3, end_line: 2, end_column: 7 });
+///
+/// assert_eq!(debug.lookup(2).map(|span| span.line), Some(1));
+/// assert_eq!(debug.lookup(5).map(|span| span.line), Some(2));
+/// assert_eq!(debug.file_name(debug.lookup(5).unwrap()), Some("fib.scm"));
+/// ```
+#[derive(Clone,Debug,PartialEq,Default)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "debuginfo", issue = "94") )]
+pub struct DebugInfo {
+    files: Vec<String>,
+    /// Spans, sorted by position.
+    spans: Vec<(u32, Span)>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "debuginfo", issue = "94") )]
+impl DebugInfo {
+    /// Creates a new `DebugInfo` with no files or spans.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn new() -> DebugInfo {
+        DebugInfo::default()
+    }
+
+    /// Adds a source file to the file table, returning its index.
+    ///
+    /// If the file is already in the table, its existing index is
+    /// returned.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn add_file(&mut self, path: &str) -> u32 {
+        match self.files.iter().position(|file| file == path) {
+            Some(index) => index as u32,
+            None => {
+                self.files.push(String::from(path));
+                (self.files.len() - 1) as u32
+            }
+        }
+    }
+
+    /// Returns the source files in the file table.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Returns the name of the file containing a span.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn file_name(&self, span: &Span) -> Option<&str> {
+        self.files.get(span.file as usize).map(|file| file.as_ref())
+    }
+
+    /// Records the span of the cell at `position`, replacing any span
+    /// previously recorded for it.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn insert(&mut self, position: u32, span: Span) {
+        match self.spans.binary_search_by_key(&position, |&(pos, _)| pos) {
+            Ok(index) => self.spans[index].1 = span,
+            Err(index) => self.spans.insert(index, (position, span))
+        }
+    }
+
+    /// Looks up the span of the cell at `position`.
+    ///
+    /// Returns the span recorded for the nearest position at or before
+    /// `position`, or `None` if there is no such span.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn lookup(&self, position: u32) -> Option<&Span> {
+        match self.spans.binary_search_by_key(&position, |&(pos, _)| pos) {
+            Ok(index) => Some(&self.spans[index].1),
+            Err(0) => None,
+            Err(index) => Some(&self.spans[index - 1].1)
+        }
+    }
+
+    /// Returns the recorded spans and their positions, sorted by position.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn spans(&self) -> &[(u32, Span)] {
+        &self.spans
+    }
+}
+
+/// Encodes the contents of a debug section.
+pub fn emit(debug: &DebugInfo) -> Result<Vec<u8>, String> {
+    if debug.files.len() > u16::MAX as usize {
+        return Err(String::from("too many source files"));
+    }
+    let mut bytes = Vec::new();
+    bytes.write_u16::<BigEndian>(debug.files.len() as u16).unwrap();
+    for file in &debug.files {
+        if file.len() > u16::MAX as usize {
+            return Err(format!("source file name too long: {}", file));
+        }
+        bytes.write_u16::<BigEndian>(file.len() as u16).unwrap();
+        bytes.extend_from_slice(file.as_bytes());
+    }
+    write_len(&mut bytes, debug.spans.len(), || String::from("too many spans"))?;
+    for &(position, span) in &debug.spans {
+        if debug.files.len() <= span.file as usize {
+            return Err(format!("span at position {} refers to unknown file {}",
+                position, span.file));
+        }
+        for &n in &[ position, span.file, span.line, span.column
+                   , span.end_line, span.end_column ] {
+            bytes.write_u32::<BigEndian>(n).unwrap();
+        }
+    }
+    Ok(bytes)
+}
+
+/// Decodes the contents of a debug section.
+pub fn decode(bytes: &[u8]) -> Result<DebugInfo, String> {
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    let mut debug = DebugInfo::new();
+    for _ in 0..decoder.read_u16()? {
+        let len = decoder.read_u16()? as usize;
+        let file = decoder.read_vec(len, "source file name")?;
+        debug.files.push(String::from_utf8(file).map_err(|_|
+            String::from("invalid UTF-8 in debug section"))?);
+    }
+    for _ in 0..decoder.read_u32()? {
+        let position = decoder.read_u32()?;
+        let span = Span {
+            file: decoder.read_u32()?,
+            line: decoder.read_u32()?,
+            column: decoder.read_u32()?,
+            end_line: decoder.read_u32()?,
+            end_column: decoder.read_u32()?
+        };
+        if debug.files.len() <= span.file as usize {
+            return Err(format!("span at position {} refers to unknown file {}",
+                position, span.file));
+        }
+        match debug.spans.last() {
+            Some(&(last, _)) if last >= position =>
+                return Err(String::from("debug spans out of order")),
+            _ => debug.spans.push((position, span))
+        }
+    }
+    Ok(debug)
+}
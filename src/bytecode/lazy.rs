@@ -0,0 +1,600 @@
+//! Lazy decoding of bytecode held in memory.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use super::{decode_inst, decode_pool, varint, Decoder, DecodeError,
+            DEFAULT_MAX_CELLS, DEFAULT_MAX_DEPTH};
+use super::{BYTE_BYTES, BYTE_CONS, BYTE_NIL, BYTE_REF, BYTE_VARCHAR,
+            BYTE_VARUINT, BYTE_VARSINT, CONST_LEN, CONST_START, FLAG_CHECKSUM,
+            FLAG_COMPRESSED, FLAG_SIGNED, IDENT_BYTES, KNOWN_FLAGS,
+            RESERVED_LEN, RESERVED_START, REVISION_0, REVISION_1, SECTION_CODE,
+            SECTION_POOL};
+use super::crc32::Crc32;
+use super::signature;
+use ::List;
+use ::cell::{Atom, Inst, SVMCell};
+
+/// The maximum depth to which lists may be nested when they are
+/// materialized from views.
+///
+/// This is lower than `DEFAULT_MAX_DEPTH`, since materializing a list is
+/// recursive.
+const MAX_VIEW_DEPTH: usize = 1 << 8;
+
+/// Decodes a program lazily, from bytes already in memory.
+///
+/// Creating a `LazyDecoder` only reads the preamble and section table
+/// (and the constant pool, if there is one), so it is cheap even for very
+/// large programs. Cells are then decoded one at a time, as they are
+/// visited, into lightweight `CellView`s which borrow from the source
+/// bytes. Lists are not decoded until they are iterated over or
+/// materialized into a `List<SVMCell>`.
+///
+/// To step over a list without decoding it, its length in bytes must be
+/// known. The lengths of lists are found by scanning their bytes, which
+/// does not allocate, and are recorded so that each list is only scanned
+/// once.
+///
+/// This is intended for use with memory-mapped files. Compressed files
+/// can't be decoded lazily, and signatures are not verified; use a
+/// `Decoder` for those. If the file contains a checksum, it is verified.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::bytecode::{Encoder, Program, LazyDecoder, CellView};
+/// # use seax_util::cell::SVMCell::*;
+/// # use seax_util::cell::Atom::*;
+/// # use seax_util::cell::Inst::*;
+/// # use seax_util::list::List::{Cons,Nil};
+/// # fn main() {
+/// let body = list_cell![ InstCell(LDC), AtomCell(UInt(1)), InstCell(RET) ];
+/// let program = Program::new(list!(InstCell(NIL), InstCell(LDF), body.clone(),
+///                                  InstCell(AP)));
+/// let bytes = Encoder::new().encode(&program).unwrap();
+///
+/// let decoder = LazyDecoder::new(&bytes).unwrap();
+/// let cells: Vec<CellView> = decoder.cells().collect::<Result<_, _>>().unwrap();
+/// assert_eq!(cells[0], CellView::Inst(NIL));
+/// assert_eq!(cells[3], CellView::Inst(AP));
+/// match cells[2] {
+///     CellView::List(ref list) => assert_eq!(list.to_cell(), Ok(body)),
+///     ref other => panic!("expected a list, got {:?}", other)
+/// }
+/// assert_eq!(decoder.to_list(), Ok(program.code));
+/// # }
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+pub struct LazyDecoder<'a> {
+    code: &'a [u8],
+    pool: Vec<SVMCell>,
+    max_cells: usize,
+    /// The offsets of the ends of lists, keyed by their starting offsets.
+    ends: RefCell<HashMap<usize, usize>>
+}
+
+/// A view of a single cell in a `LazyDecoder`'s code.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+pub enum CellView<'a> {
+    /// An instruction.
+    Inst(Inst),
+    /// An atom.
+    Atom(Atom),
+    /// A byte string, borrowed from the source.
+    Bytes(&'a [u8]),
+    /// A list, which has not yet been decoded.
+    List(ListView<'a>),
+    /// A reference to an entry in the constant pool, which was decoded
+    /// along with the pool.
+    Shared(&'a SVMCell)
+}
+
+/// A view of a list which has not yet been decoded.
+#[derive(Clone,Copy)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+pub struct ListView<'a> {
+    context: Context<'a>,
+    offset: usize
+}
+
+/// An iterator over views of the cells in a list, or in a program's code.
+#[derive(Clone,Debug)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+pub struct CellViews<'a> {
+    context: Context<'a>,
+    offset: usize,
+    /// Whether the cells are elements of a list, rather than a sequence
+    /// of cells ending at the end of the code.
+    in_list: bool,
+    done: bool
+}
+
+/// Everything a view needs to decode cells.
+#[derive(Clone,Copy)]
+struct Context<'a> {
+    code: &'a [u8],
+    pool: &'a [SVMCell],
+    max_cells: usize,
+    ends: &'a RefCell<HashMap<usize, usize>>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+impl<'a> LazyDecoder<'a> {
+    /// Reads the preamble and section table of a program.
+    ///
+    /// Both Revision 0 and Revision 1 files are supported. Returns an error
+    /// if the file is malformed, compressed, or its checksum does not match.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn new(bytes: &'a [u8]) -> Result<LazyDecoder<'a>, DecodeError> {
+        LazyDecoder::with_max_cells(bytes, DEFAULT_MAX_CELLS)
+    }
+
+    /// Reads the preamble and section table of a program, decoding at most
+    /// `max_cells` cells from its constant pool, and materializing at most
+    /// `max_cells` cells at once.
+    ///
+    /// See `Decoder::set_max_cells()`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn with_max_cells(bytes: &'a [u8], max_cells: usize)
+                          -> Result<LazyDecoder<'a>, DecodeError> {
+        if bytes.len() < 4 {
+            return Err(DecodeError::Malformed(
+                String::from("EOF while decoding preamble")));
+        }
+        let ident = BigEndian::read_u16(&bytes[0..2]);
+        if ident != IDENT_BYTES {
+            return Err(DecodeError::Malformed(
+                format!("invalid identifying bytes {:#06x}", ident)));
+        }
+        let (code, pool) = match BigEndian::read_u16(&bytes[2..4]) {
+            REVISION_1 => read_sections(&bytes[4..], max_cells)?,
+            REVISION_0 => (&bytes[4..], Vec::new()),
+            version => {
+                warn!("mismatched version {}, expected {}; \
+                       attempting to decode as revision {}",
+                      version, REVISION_1, REVISION_0);
+                (&bytes[4..], Vec::new())
+            }
+        };
+        Ok(LazyDecoder {
+            code, pool, max_cells,
+            ends: RefCell::new(HashMap::new())
+        })
+    }
+
+    /// Returns an iterator over views of the cells in the program's code.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn cells<'b>(&'b self) -> CellViews<'b> {
+        CellViews {
+            context: self.context(),
+            offset: 0,
+            in_list: false,
+            done: false
+        }
+    }
+
+    /// Decodes the whole of the program's code.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn to_list(&self) -> Result<List<SVMCell>, String> {
+        let mut remaining = self.max_cells;
+        self.cells()
+            .map(|cell| cell.and_then(|cell| cell.to_cell_at(0, &mut remaining)))
+            .collect()
+    }
+
+    /// Returns the number of lists whose lengths have been recorded.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn num_recorded(&self) -> usize {
+        self.ends.borrow().len()
+    }
+
+    fn context<'b>(&'b self) -> Context<'b> {
+        Context {
+            code: self.code,
+            pool: &self.pool,
+            max_cells: self.max_cells,
+            ends: &self.ends
+        }
+    }
+}
+
+/// Finds the code section of a Revision 1 file, and decodes its pool.
+///
+/// `bytes` contains everything following the version bytes.
+fn read_sections(bytes: &[u8], max_cells: usize)
+                 -> Result<(&[u8], Vec<SVMCell>), DecodeError> {
+    let eof = || String::from("EOF while decoding section table");
+    if bytes.len() < 4 {
+        return Err(DecodeError::Malformed(eof()));
+    }
+    let flags = BigEndian::read_u16(&bytes[0..2]);
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(DecodeError::Malformed(
+            format!("unsupported flags {:#06x}", flags)));
+    }
+    if flags & FLAG_COMPRESSED != 0 {
+        return Err(DecodeError::Malformed(
+            String::from("compressed files can't be decoded lazily")));
+    }
+    let count = BigEndian::read_u16(&bytes[2..4]) as usize;
+    let header_len = 4 + count * 5;
+    let mut offset = header_len;
+    if flags & FLAG_CHECKSUM != 0 {
+        offset += 4;
+    }
+    if flags & FLAG_SIGNED != 0 {
+        offset += signature::BLOCK_LEN;
+    }
+    if bytes.len() < offset {
+        return Err(DecodeError::Malformed(eof()));
+    }
+    let mut crc = Crc32::new();
+    crc.update(&bytes[..header_len]);
+
+    let mut code = None;
+    let mut pool = None;
+    for entry in bytes[4..header_len].chunks(5) {
+        let len = BigEndian::read_u32(&entry[1..]) as usize;
+        let section = bytes.get(offset..offset + len).ok_or_else(||
+            String::from("EOF while decoding section"))?;
+        crc.update(section);
+        match entry[0] {
+            SECTION_CODE if code.is_some() =>
+                return Err(DecodeError::Malformed(
+                    String::from("duplicate code section"))),
+            SECTION_CODE => code = Some(section),
+            SECTION_POOL => pool = Some(section),
+            _ => {}
+        }
+        offset += len;
+    }
+    if flags & FLAG_CHECKSUM != 0 {
+        let expected = BigEndian::read_u32(&bytes[header_len..]);
+        let actual = crc.finish();
+        if expected != actual {
+            return Err(DecodeError::ChecksumMismatch { expected, actual });
+        }
+    }
+    let code = code.ok_or_else(|| String::from("missing code section"))?;
+    let pool = match pool {
+        Some(pool) => decode_pool(pool, max_cells, DEFAULT_MAX_DEPTH)?.entries,
+        None => Vec::new()
+    };
+    Ok((code, pool))
+}
+
+impl<'a> Context<'a> {
+    fn byte(&self, offset: usize) -> Result<u8, String> {
+        self.code.get(offset)
+            .cloned()
+            .ok_or_else(|| String::from("unexpected EOF"))
+    }
+
+    /// Returns the length of the cell at `offset` in bytes, if it is not a
+    /// list.
+    fn atom_len(&self, offset: usize) -> Result<usize, String> {
+        let len = match self.byte(offset)? {
+            b if b <= RESERVED_START + RESERVED_LEN => 1,
+            BYTE_BYTES => {
+                let len = self.code.get(offset + 1..offset + 5)
+                              .ok_or_else(|| String::from(
+                                  "EOF while decoding byte string"))?;
+                5 + BigEndian::read_u32(len) as usize
+            },
+            BYTE_REF => 5,
+            BYTE_VARUINT | BYTE_VARSINT | BYTE_VARCHAR => {
+                let (_, len) = varint::read(&mut &self.code[offset + 1..])?;
+                1 + len
+            },
+            0xC3 => 5,
+            0xC1 | 0xC2 | 0xC4 => 9,
+            b => return Err(format!("Unsupported byte {:#02x}", b))
+        };
+        if offset + len > self.code.len() {
+            return Err(String::from("unexpected EOF"));
+        }
+        Ok(len)
+    }
+
+    /// Returns the offset just past the end of the list at `start`.
+    ///
+    /// The ends of this list, and of any lists nested within it, are
+    /// recorded, so that they do not need to be scanned again.
+    fn end_of(&self, start: usize) -> Result<usize, String> {
+        if let Some(&end) = self.ends.borrow().get(&start) {
+            return Ok(end);
+        }
+        // the starts of the lists we are currently inside
+        let mut starts = vec![start];
+        let mut offset = start + 1;
+        loop {
+            // an element of the innermost list
+            if self.byte(offset)? == BYTE_CONS {
+                match self.ends.borrow().get(&offset) {
+                    Some(&end) => offset = end,
+                    None => {
+                        starts.push(offset);
+                        offset += 1;
+                        continue;
+                    }
+                }
+            } else {
+                offset += self.atom_len(offset)?;
+            }
+            // either another element follows, or the list ends with NIL,
+            // or with the final cdr of an improper list
+            loop {
+                match self.byte(offset)? {
+                    BYTE_CONS => { offset += 1; break; },
+                    BYTE_NIL  => offset += 1,
+                    _ => offset += self.atom_len(offset)?
+                }
+                let start = starts.pop().unwrap();
+                self.ends.borrow_mut().insert(start, offset);
+                if starts.is_empty() {
+                    return Ok(offset);
+                }
+            }
+        }
+    }
+
+    /// Decodes a view of the cell at `offset`, returning it and the offset
+    /// of the next cell.
+    fn view(&self, offset: usize) -> Result<(CellView<'a>, usize), String> {
+        let view = match self.byte(offset)? {
+            BYTE_CONS => return Ok((
+                CellView::List(ListView { context: *self, offset }),
+                self.end_of(offset)?
+            )),
+            b if b <= RESERVED_START + RESERVED_LEN =>
+                CellView::Inst(decode_inst(&b)?),
+            BYTE_BYTES =>
+                CellView::Bytes(&self.code[offset + 5..offset + self.atom_len(offset)?]),
+            BYTE_REF => {
+                let index = BigEndian::read_u32(
+                    &self.code[offset + 1..offset + self.atom_len(offset)?]);
+                CellView::Shared(self.pool.get(index as usize).ok_or_else(||
+                    format!("invalid constant pool reference {}", index))?)
+            },
+            b if (CONST_START..CONST_START + CONST_LEN).contains(&b) => {
+                let mut source = &self.code[offset..offset + self.atom_len(offset)?];
+                match Decoder::new(&mut source).next_cell()? {
+                    Some(SVMCell::AtomCell(atom)) => CellView::Atom(atom),
+                    _ => return Err(format!("Unsupported byte {:#02x}", b))
+                }
+            },
+            b => return Err(format!("Unsupported byte {:#02x}", b))
+        };
+        Ok((view, offset + self.atom_len(offset)?))
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+impl<'a> CellView<'a> {
+    /// Decodes the cell, including the contents of any lists.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn to_cell(&self) -> Result<SVMCell, String> {
+        match *self {
+            CellView::List(ref list) => list.to_cell(),
+            // anything else is a single cell, or a single pool entry
+            _ => {
+                let mut remaining = usize::MAX;
+                self.to_cell_at(0, &mut remaining)
+            }
+        }
+    }
+
+    /// Decodes the cell, which is nested inside `depth` lists, counting
+    /// the cells decoded against `remaining`.
+    fn to_cell_at(&self, depth: usize, remaining: &mut usize)
+                  -> Result<SVMCell, String> {
+        let size = match *self {
+            CellView::Shared(cell) => count_cells(cell),
+            _ => 1
+        };
+        if size > *remaining {
+            return Err(String::from("too many cells to materialize"));
+        }
+        *remaining -= size;
+        match *self {
+            CellView::Inst(inst) => Ok(SVMCell::InstCell(inst)),
+            CellView::Atom(atom) => Ok(SVMCell::AtomCell(atom)),
+            CellView::Bytes(bytes) => Ok(SVMCell::BytesCell(bytes.to_vec())),
+            CellView::List(ref list) => list.decode_at(depth, remaining),
+            CellView::Shared(cell) => Ok(cell.clone())
+        }
+    }
+}
+
+/// Counts the cells in `cell`, including the cells in any lists.
+///
+/// Pool entries are counted before they are copied, so that copying many
+/// references to a large entry fails quickly rather than exhausting memory.
+fn count_cells(cell: &SVMCell) -> usize {
+    let mut count = 1;
+    let mut stack = vec![cell];
+    while let Some(cell) = stack.pop() {
+        match *cell {
+            SVMCell::ListCell(ref list) => {
+                count += list.length();
+                stack.extend(list.iter());
+            },
+            SVMCell::DottedCell(ref dotted) => {
+                count += dotted.length() + 1;
+                stack.extend(dotted.items().iter());
+                stack.push(dotted.last_cdr());
+            },
+            _ => {}
+        }
+    }
+    count
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+impl<'a> ListView<'a> {
+    /// Returns an iterator over views of the list's elements.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn iter(&self) -> CellViews<'a> {
+        CellViews {
+            context: self.context,
+            offset: self.offset + 1,
+            in_list: true,
+            done: false
+        }
+    }
+
+    /// Decodes the list.
+    ///
+    /// Returns an error rather than materializing more than the decoder's
+    /// maximum number of cells, counting each reference to the constant
+    /// pool as the number of cells in the entry it refers to, or if the
+    /// list is an improper list.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn to_list(&self) -> Result<List<SVMCell>, String> {
+        match self.to_cell()? {
+            SVMCell::ListCell(list) => Ok(*list),
+            _ => Err(String::from("expected a proper list"))
+        }
+    }
+
+    /// Returns a view of the final cdr of the list, if it is an improper
+    /// list, or `None` if it ends with `NIL`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn last_cdr(&self) -> Result<Option<CellView<'a>>, String> {
+        let mut items = self.iter();
+        for item in items.by_ref() {
+            item?;
+        }
+        // the iterator stops at the end of the list
+        match self.context.byte(items.offset)? {
+            BYTE_NIL => Ok(None),
+            _ => self.context.view(items.offset).map(|(view, _)| Some(view))
+        }
+    }
+
+    /// Decodes the list, which is nested inside `depth` other lists, into
+    /// a list cell, or a dotted cell if it is an improper list.
+    ///
+    /// Materializing lists is recursive, so their depth is limited to
+    /// `MAX_VIEW_DEPTH`.
+    fn decode_at(&self, depth: usize, remaining: &mut usize)
+                  -> Result<SVMCell, String> {
+        if depth >= MAX_VIEW_DEPTH {
+            return Err(format!("lists nested too deeply: the limit is {}",
+                               MAX_VIEW_DEPTH));
+        }
+        let items = self.iter()
+            .map(|cell| cell.and_then(|cell|
+                cell.to_cell_at(depth + 1, remaining)))
+            .collect::<Result<List<SVMCell>, String>>()?;
+        match self.last_cdr()? {
+            Some(cdr) => cdr.to_cell_at(depth + 1, remaining)
+                .map(|cdr| SVMCell::dotted(items, cdr)),
+            None => Ok(SVMCell::ListCell(Box::new(items)))
+        }
+    }
+
+    /// Decodes the list, wrapped in a list cell, or in a dotted cell if it
+    /// is an improper list.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn to_cell(&self) -> Result<SVMCell, String> {
+        let mut remaining = self.context.max_cells;
+        self.decode_at(0, &mut remaining)
+    }
+
+    /// Returns the offset of the list within the code, in bytes.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the length of the encoded list, in bytes.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "lazy", issue = "94") )]
+    pub fn byte_len(&self) -> Result<usize, String> {
+        self.context.end_of(self.offset).map(|end| end - self.offset)
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+impl<'a> PartialEq for ListView<'a> {
+    /// Two views are equal if they view the same list in the same code.
+    fn eq(&self, other: &ListView<'a>) -> bool {
+        self.offset == other.offset &&
+            self.context.code.as_ptr() == other.context.code.as_ptr()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+impl<'a> ::std::fmt::Debug for ListView<'a> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "ListView at offset {}", self.offset)
+    }
+}
+
+impl<'a> ::std::fmt::Debug for Context<'a> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "{} bytes of code", self.code.len())
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "lazy", issue = "94") )]
+impl<'a> Iterator for CellViews<'a> {
+    type Item = Result<CellView<'a>, String>;
+
+    fn next(&mut self) -> Option<Result<CellView<'a>, String>> {
+        if self.done {
+            return None;
+        }
+        if !self.in_list && self.offset >= self.context.code.len() {
+            self.done = true;
+            return None;
+        }
+        let result = self.context.view(self.offset).and_then(|(view, next)| {
+            self.offset = next;
+            if self.in_list {
+                // skip the CONS tag before the next element, or finish at
+                // the NIL or final cdr which ends the list
+                match self.context.byte(next)? {
+                    BYTE_CONS => self.offset += 1,
+                    BYTE_NIL  => self.done = true,
+                    _ => {
+                        self.context.atom_len(next)?;
+                        self.done = true;
+                    }
+                }
+            }
+            Ok(view)
+        });
+        if result.is_err() {
+            self.done = true;
+        }
+        Some(result)
+    }
+}
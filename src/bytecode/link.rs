@@ -0,0 +1,406 @@
+//! Modules and linking.
+//!
+//! A module is a separately compiled unit of code, consisting of a number
+//! of named definitions, some of which are exported for use by other
+//! modules, and a list of names which it imports from other modules.
+//! `link()` combines several modules into a single executable program.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io::Cursor;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use super::{write_len, Decoder, Program};
+use ::List;
+use ::List::{Cons,Nil};
+use ::cell::{Atom, SVMCell};
+use ::cell::SVMCell::*;
+use ::cell::Inst::*;
+
+/// A definition in a module.
+///
+/// A definition's code is evaluated once, when the linked program starts,
+/// and must leave exactly one value on the stack; typically, this is a
+/// closure, created by `LDF`. It is evaluated between the `DUM` and `RAP`
+/// instructions which build the module environment, so the environment's
+/// top level is a dummy frame, which only holds the module environment
+/// once every definition has been evaluated. Instructions referring to
+/// the module environment may therefore only appear inside the bodies of
+/// closures created by `LDF`, whose environments extend it; in a closure
+/// created directly by the definition's code, it is at level 2.
+///
+/// The module environment contains the module's definitions, in order,
+/// followed by its imports, in order, numbered from one. For example,
+/// in a module with two definitions and one import, the import is at
+/// index 3. Since modules are compiled separately, these indices must be
+/// renumbered when modules are linked; every `LD` instruction referring
+/// to the module environment must have a relocation, giving the position
+/// of the `(level index)` list, or `(level . index)` pair, following it in
+/// the definition's code. Positions are numbered as described in
+/// `DebugInfo`, starting from zero at the first cell of the definition's
+/// code.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "link", issue = "94") )]
+pub struct Definition {
+    /// The definition's name.
+    pub name: String,
+    /// Whether other modules may import the definition.
+    pub exported: bool,
+    /// The code which evaluates to the definition's value.
+    pub code: List<SVMCell>,
+    /// The positions of the `(level index)` lists in `code` which refer
+    /// to the module environment.
+    pub relocations: Vec<u32>
+}
+
+/// A separately compiled unit of code.
+///
+/// Modules are encoded with `Encoder::encode_module()` and decoded with
+/// `Decoder::read_module()`.
+#[derive(Clone,Debug,PartialEq,Default)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "link", issue = "94") )]
+pub struct Module {
+    /// The module's name, used in error messages.
+    pub name: String,
+    /// The names this module imports from other modules.
+    pub imports: Vec<String>,
+    /// The module's definitions.
+    pub definitions: Vec<Definition>
+}
+
+/// Errors that may occur while linking modules.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "link", issue = "94") )]
+pub enum LinkError {
+    /// Names were imported which no module exports. Each entry contains
+    /// the importing module's name and the imported name.
+    Unresolved(Vec<(String, String)>),
+    /// A name was exported by more than one module. Contains the name and
+    /// the names of the first two modules exporting it.
+    DuplicateExport(String, String, String),
+    /// The entry point is not exported by any module.
+    MissingEntry(String),
+    /// A relocation does not refer to a valid `(level index)` list. Contains
+    /// the module's name, the definition's name, and the relocation's
+    /// position.
+    BadRelocation(String, String, u32)
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "link", issue = "94") )]
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LinkError::Unresolved(ref symbols) => {
+                write!(f, "unresolved symbols:")?;
+                for (module, symbol) in symbols {
+                    write!(f, " `{}` (imported by `{}`)", symbol, module)?;
+                }
+                Ok(())
+            },
+            LinkError::DuplicateExport(ref symbol, ref first, ref second) =>
+                write!(f, "`{}` is exported by both `{}` and `{}`",
+                    symbol, first, second),
+            LinkError::MissingEntry(ref entry) =>
+                write!(f, "entry point `{}` is not exported by any module",
+                    entry),
+            LinkError::BadRelocation(ref module, ref definition, position) =>
+                write!(f, "invalid relocation at position {} in `{}` in `{}`",
+                    position, definition, module)
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "link", issue = "94") )]
+impl Error for LinkError {}
+
+/// Links several modules into a single executable program.
+///
+/// Every import must be exported by exactly one module. The linked
+/// program evaluates every definition in every module, in order, in a
+/// single recursive environment, and then calls the exported definition
+/// named `entry` with no arguments.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::bytecode::{Module, Definition, LinkError, link};
+/// # use seax_util::cell::SVMCell::*;
+/// # use seax_util::cell::Atom::*;
+/// # use seax_util::cell::Inst::*;
+/// # use seax_util::list::List::{Cons,Nil};
+/// # fn main() {
+/// let lib = Module {
+///     name: String::from("lib"),
+///     imports: vec![],
+///     definitions: vec![ Definition {
+///         name: String::from("answer"),
+///         exported: true,
+///         code: list!(InstCell(LDF), list_cell![
+///             InstCell(LDC), AtomCell(UInt(42)), InstCell(RET)
+///         ]),
+///         relocations: vec![]
+///     }]
+/// };
+/// let main = Module {
+///     name: String::from("main"),
+///     imports: vec![ String::from("answer") ],
+///     definitions: vec![ Definition {
+///         name: String::from("main"),
+///         exported: true,
+///         // calls `answer`, which is the second entry in the module
+///         // environment, at level 2 inside the closure
+///         code: list!(InstCell(LDF), list_cell![
+///             InstCell(NIL),
+///             InstCell(LD), list_cell![ AtomCell(UInt(2)), AtomCell(UInt(2)) ],
+///             InstCell(AP), InstCell(RET)
+///         ]),
+///         relocations: vec![ 4 ]
+///     }]
+/// };
+///
+/// let program = link(&[lib.clone(), main.clone()], "main").unwrap();
+/// assert!(program.code.length() > 0);
+///
+/// assert_eq!(
+///     link(&[main], "main"),
+///     Err(LinkError::Unresolved(vec![
+///         (String::from("main"), String::from("answer"))
+///     ]))
+/// );
+/// # }
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "link", issue = "94") )]
+pub fn link(modules: &[Module], entry: &str) -> Result<Program, LinkError> {
+    // number every definition in the global environment
+    let mut bases = Vec::with_capacity(modules.len());
+    let mut exports: HashMap<&str, (u64, &str)> = HashMap::new();
+    let mut next = 1;
+    for module in modules {
+        bases.push(next);
+        for (i, definition) in module.definitions.iter().enumerate() {
+            if definition.exported {
+                let index = next + i as u64;
+                if let Some(&(_, first)) = exports.get(&definition.name[..]) {
+                    return Err(LinkError::DuplicateExport(
+                        definition.name.clone(), String::from(first),
+                        module.name.clone()));
+                }
+                exports.insert(&definition.name, (index, &module.name));
+            }
+        }
+        next += module.definitions.len() as u64;
+    }
+
+    // resolve each module's environment to global indices
+    let mut unresolved = Vec::new();
+    let mut environments = Vec::with_capacity(modules.len());
+    for (module, &base) in modules.iter().zip(&bases) {
+        let mut environment: Vec<u64> = (0..module.definitions.len() as u64)
+            .map(|i| base + i)
+            .collect();
+        for import in &module.imports {
+            match exports.get(&import[..]) {
+                Some(&(index, _)) => environment.push(index),
+                None => unresolved.push((module.name.clone(), import.clone()))
+            }
+        }
+        environments.push(environment);
+    }
+    if !unresolved.is_empty() {
+        return Err(LinkError::Unresolved(unresolved));
+    }
+    let entry = exports.get(entry)
+                       .map(|&(index, _)| index)
+                       .ok_or_else(|| LinkError::MissingEntry(String::from(entry)))?;
+
+    // build the list of definitions' values in reverse, so that the first
+    // definition ends up at the front
+    let mut cells = vec![ InstCell(DUM), InstCell(NIL) ];
+    for (module, environment) in modules.iter().zip(&environments).rev() {
+        for definition in module.definitions.iter().rev() {
+            let code = relocate(module, definition, environment)?;
+            cells.extend(code.iter().cloned());
+            cells.push(InstCell(CONS));
+        }
+    }
+    cells.push(InstCell(LDF));
+    cells.push(ListCell(Box::new(list!(
+        InstCell(NIL),
+        InstCell(LD), ListCell(Box::new(list!(
+            AtomCell(Atom::UInt(1)), AtomCell(Atom::UInt(entry))))),
+        InstCell(AP),
+        InstCell(RET)
+    ))));
+    cells.push(InstCell(RAP));
+    Ok(Program::new(cells.into_iter().collect()))
+}
+
+/// Rewrites a definition's references to its module's environment to
+/// refer to the global environment.
+fn relocate(module: &Module, definition: &Definition, environment: &[u64])
+           -> Result<List<SVMCell>, LinkError> {
+    let mut relocations: HashSet<u32> =
+        definition.relocations.iter().cloned().collect();
+    relocate_list(&definition.code, &mut relocations, environment, &mut 0)
+        .and_then(|code| match relocations.iter().min() {
+            // any relocations left over are past the end of the code
+            Some(&position) => Err(position),
+            None => Ok(code)
+        })
+        .map_err(|position| LinkError::BadRelocation(
+            module.name.clone(), definition.name.clone(), position))
+}
+
+/// Copies a list of cells, relocating the `(level index)` lists at the
+/// given positions, and removing them from `relocations`.
+///
+/// Returns the position of the first invalid relocation, if any.
+fn relocate_list(code: &List<SVMCell>, relocations: &mut HashSet<u32>,
+                 environment: &[u64], position: &mut u32)
+                 -> Result<List<SVMCell>, u32> {
+    let mut cells = Vec::new();
+    for cell in code.iter() {
+        let here = *position;
+        *position += 1;
+        let cell = if relocations.remove(&here) {
+            // the level and index are skipped along with the list
+            *position += 2;
+            relocate_index(cell, environment).ok_or(here)?
+        } else {
+            match *cell {
+                ListCell(ref list) => ListCell(Box::new(
+                    relocate_list(list, relocations, environment, position)?)),
+                DottedCell(ref dotted) => {
+                    let items = relocate_list(dotted.items(), relocations,
+                                              environment, position)?;
+                    // the final cdr is never a list, so it can't be relocated
+                    *position += 1;
+                    SVMCell::dotted(items, dotted.last_cdr().clone())
+                },
+                ref other => other.clone()
+            }
+        };
+        cells.push(cell);
+    }
+    Ok(cells.into_iter().collect())
+}
+
+/// Relocates a single `(level index)` list, or `(level . index)` pair.
+///
+/// Returns `None` if the cell isn't such a list, or if the index is not in
+/// the module environment.
+fn relocate_index(cell: &SVMCell, environment: &[u64]) -> Option<SVMCell> {
+    let (level, index) = match *cell {
+        ListCell(ref list) => {
+            let mut items = list.iter();
+            match (items.next(), items.next(), items.next()) {
+                (Some(&AtomCell(Atom::UInt(level))),
+                 Some(&AtomCell(Atom::UInt(index))), None) => (level, index),
+                _ => return None
+            }
+        },
+        DottedCell(ref dotted) => match (dotted.items(), dotted.last_cdr()) {
+            (&Cons(AtomCell(Atom::UInt(level)), ref rest),
+             &AtomCell(Atom::UInt(index))) if rest.is_empty() => (level, index),
+            _ => return None
+        },
+        _ => return None
+    };
+    if index < 1 {
+        return None;
+    }
+    let level = AtomCell(Atom::UInt(level));
+    let index = AtomCell(Atom::UInt(*environment.get(index as usize - 1)?));
+    Some(match *cell {
+        DottedCell(_) => SVMCell::cons(level, index),
+        _ => list_cell![level, index]
+    })
+}
+
+/// Splits a module into the code and contents of the module section
+/// used to encode it.
+pub fn emit_module(module: &Module) -> Result<(List<SVMCell>, Vec<u8>), String> {
+    fn write_name(bytes: &mut Vec<u8>, name: &str) -> Result<(), String> {
+        if name.len() > u16::MAX as usize {
+            return Err(format!("symbol too long: {}", name));
+        }
+        bytes.write_u16::<BigEndian>(name.len() as u16).unwrap();
+        bytes.extend_from_slice(name.as_bytes());
+        Ok(())
+    }
+    let mut bytes = Vec::new();
+    write_name(&mut bytes, &module.name)?;
+    write_len(&mut bytes, module.imports.len(), || String::from("too many imports"))?;
+    for import in &module.imports {
+        write_name(&mut bytes, import)?;
+    }
+    write_len(&mut bytes, module.definitions.len(),
+              || String::from("too many definitions"))?;
+    let mut code = Vec::with_capacity(module.definitions.len());
+    for definition in &module.definitions {
+        if definition.code == Nil {
+            return Err(format!("definition {} has no code", definition.name));
+        }
+        write_name(&mut bytes, &definition.name)?;
+        bytes.push(definition.exported as u8);
+        write_len(&mut bytes, definition.relocations.len(),
+                  || format!("definition {} has too many relocations",
+                             definition.name))?;
+        for &position in &definition.relocations {
+            bytes.write_u32::<BigEndian>(position).unwrap();
+        }
+        code.push(ListCell(Box::new(definition.code.clone())));
+    }
+    Ok((code.into_iter().collect(), bytes))
+}
+
+/// Reassembles a module from its code and the contents of its module
+/// section.
+pub fn decode_module(code: List<SVMCell>, bytes: &[u8]) -> Result<Module, String> {
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    let mut module = Module {
+        name: read_name(&mut decoder)?,
+        ..Module::default()
+    };
+    for _ in 0..decoder.read_u32()? {
+        module.imports.push(read_name(&mut decoder)?);
+    }
+    let count = decoder.read_u32()?;
+    if count as usize != code.length() {
+        return Err(format!("module has {} definitions, but {} code cells",
+            count, code.length()));
+    }
+    for cell in code.iter() {
+        let name = read_name(&mut decoder)?;
+        let exported = match decoder.read_vec(1, "module section")?[0] {
+            0 => false,
+            1 => true,
+            b => return Err(format!("invalid export flag {:#04x}", b))
+        };
+        let relocations = (0..decoder.read_u32()?)
+            .map(|_| decoder.read_u32())
+            .collect::<Result<Vec<u32>, String>>()?;
+        let code = match *cell {
+            ListCell(ref code) => (**code).clone(),
+            _ => return Err(format!("definition {} is not a list", name))
+        };
+        module.definitions.push(Definition { name, exported, code, relocations });
+    }
+    Ok(module)
+}
+
+fn read_name<R>(decoder: &mut Decoder<R>) -> Result<String, String>
+where R: ::std::io::Read {
+    let len = decoder.read_u16()? as usize;
+    String::from_utf8(decoder.read_vec(len, "symbol")?)
+        .map_err(|_| String::from("invalid UTF-8 in module section"))
+}
@@ -6,7 +6,7 @@
 //! Seax Bytecode Format
 //! ====================
 //!
-//! Seax Bytecode Standard Revision 0, June 11th, 2015
+//! Seax Bytecode Standard Revision 1
 //!
 //! I: The preamble
 //! ---------------
@@ -17,18 +17,21 @@
 //!    spell out the abbreviation SECD in hexadecimal, identify the file as a Seax bytecode file.
 //! 2. A 16-bit unsigned integer that represents the version of the Seax bytecode format that the
 //!    file was encoded with. This number is used to determine how the remainder of the file should
-//!    be decoded. This document is Revision 0 of the Seax Bytecode format, so the version
-//!    should be 0x0000.
+//!    be decoded. This document is Revision 1 of the Seax Bytecode format, so the version
+//!    should be 0x0001.
 //!
-//! Future revisions of this standard will allow additional metadata, such as the author's
-//! cryptographic signature, checksums for ensuring the executable's integrity, and directives to
-//! the virtual machine, to be placed in the preamble as well.
+//! In Revision 0 of this standard (June 11th, 2015), the preamble ends here and is followed
+//! directly by the program's instructions and constants, encoded as described in sections II and
+//! III. Decoders must continue to accept Revision 0 files.
+//!
+//! In Revision 1, the preamble continues with a section table, which allows additional metadata
+//! to be stored alongside the program. This is described in section IV.
 //!
 //! II: Instructions
 //! ----------------
 //!
 //! All Seax VM instructions are encoded using single byes. The Seax opcodes occupy the
-//! space 0x00 to 0x30, with the bytes 0x1D through 0x3F being reserved for future use.
+//! space 0x00 to 0x30, with the byte 0x30 being reserved for future use.
 //!
 //! The following table shows all of the currently available SVM opcodes.
 //!
@@ -66,10 +69,69 @@
 //!   0x1B  | CDR (a . b)   |
 //!   0x1C  | LDC           |
 //!   0x1D  | STOP          |
-//!   0x1E  | reserved      |
-//!         |     ...       |
+//!   0x1E  | AND a b       | Pushes the bitwise AND of the integers `a` and `b`.
+//!   0x1F  | OR a b        | Pushes the bitwise OR of the integers `a` and `b`.
+//!   0x20  | XOR a b       | Pushes the bitwise exclusive OR of the integers `a` and `b`.
+//!   0x21  | NOT a         | Pushes the bitwise complement of the integer `a`.
+//!   0x22  | SHL n a       | Pushes the integer `a` shifted left by `n` bits.
+//!   0x23  | SHR n a       | Pushes the integer `a` shifted right by `n` bits.
+//!   0x24  | TYPE a        | Pushes the type tag of `a` as a uint.
+//!   0x25  | ISUINT a      | Pushes true if `a` is a uint atom.
+//!   0x26  | ISSINT a      | Pushes true if `a` is a sint atom.
+//!   0x27  | ISFLOAT a     | Pushes true if `a` is a float atom.
+//!   0x28  | ISCHAR a      | Pushes true if `a` is a char atom.
+//!   0x29  | ISLIST a      | Pushes true if `a` is a list (including nil).
+//!   0x2A  | ISCLOS a      | Pushes true if `a` is a closure.
+//!   0x2B  | ISBYTES a     | Pushes true if `a` is a byte string.
+//!   0x2C  | ITOF a        | Converts the integer `a` to a float.
+//!   0x2D  | FTOI a        | Converts the float `a` to a sint, truncating towards zero.
+//!   0x2E  | CTOI a        | Converts the char `a` to a uint holding its code point.
+//!   0x2F  | ITOC a        | Converts the integer `a` to the char with that code point.
 //!   0x30  | reserved      |
 //!
+//! The bitwise instructions are defined only on integer atoms. When a uint and a sint are
+//! combined, the result is a sint. Shift amounts must lie between 0 and 63, inclusive; `SHR`
+//! performs a logical shift on uints and an arithmetic (sign-preserving) shift on sints. Their
+//! operational semantics are as follows, where the top of the stack is written first:
+//!
+//! + `AND`: `(a.b.s, e, AND.c, d) → ((b & a).s, e, c, d)`
+//! + `OR`:  `(a.b.s, e, OR.c, d) → ((b | a).s, e, c, d)`
+//! + `XOR`: `(a.b.s, e, XOR.c, d) → ((b ^ a).s, e, c, d)`
+//! + `NOT`: `(a.s, e, NOT.c, d) → ((!a).s, e, c, d)`
+//! + `SHL`: `(n.a.s, e, SHL.c, d) → ((a << n).s, e, c, d)`
+//! + `SHR`: `(n.a.s, e, SHR.c, d) → ((a >> n).s, e, c, d)`
+//!
+//! The `TYPE` instruction pushes one of the following type tags. The tags for atoms and byte
+//! strings are the same as the type tags used to identify constants of those types (see
+//! section III).
+//!
+//! | Tag   | Type
+//! +-------+-------------------------------
+//!   0x00  | list
+//!   0x01  | uint atom
+//!   0x02  | sint atom
+//!   0x03  | char atom
+//!   0x04  | float atom
+//!   0x05  | byte string
+//!   0x0E  | closure
+//!   0x0F  | instruction
+//!
+//! The type predicates `ISUINT` through `ISBYTES` pop one item and push a true or false value,
+//! exactly like `ATOM` and `NULL`:
+//!
+//! + `TYPE`: `(a.s, e, TYPE.c, d) → ((type a).s, e, c, d)`
+//! + `ISx`: `(a.s, e, ISx.c, d) → ((if a is x then true else false).s, e, c, d)`
+//!
+//! The conversion instructions pop one atom of the expected type and push the converted atom.
+//! It is an error to apply them to an atom of any other type, to apply `FTOI` to a float that
+//! is NaN or out of the range of a sint, or to apply `ITOC` to an integer that is not a valid
+//! Unicode scalar value.
+//!
+//! + `ITOF`: `(a.s, e, ITOF.c, d) → ((float a).s, e, c, d)`
+//! + `FTOI`: `(a.s, e, FTOI.c, d) → ((sint a).s, e, c, d)`
+//! + `CTOI`: `(a.s, e, CTOI.c, d) → ((uint a).s, e, c, d)`
+//! + `ITOC`: `(a.s, e, ITOC.c, d) → ((char a).s, e, c, d)`
+//!
 //! III: Constants
 //! --------------
 //!
@@ -99,11 +161,19 @@
 //!    be expected, while if the opcode is in the CDR part, a new instruction or constant will
 //!    be expected.)
 //!
+//!    A proper list is therefore a chain of CONS cells whose last CDR is NIL (0x00), such as
+//!    `0xC0 a 0xC0 b 0x00` for `(a b)`. If the last CDR is any other constant, the list is an
+//!    improper list, which decodes to a `DottedCell`; for example, `0xC0 a b` is the dotted pair
+//!    `(a . b)`. Since the NIL instruction's opcode is the same as NIL, a dotted pair may not
+//!    have the NIL instruction as its CDR.
+//!
 //! 2. Atom constants (0xC1 ... 0xCF)
 //!
 //!    Any constants that are not CONS cells are atom constants. Atom constants are identified by
 //!    bytes in the range between 0xC1 and 0xCF, inclusive. Currently, 0xC1, 0xC2, 0xC3, and 0xC4
-//!    identify extant atom types, while 0xC5 ... 0xCE are reserved for future use.
+//!    identify extant atom types, 0xC5 and 0xC6 identify byte strings and constant pool
+//!    references, 0xC7, 0xC8 and 0xC9 identify variable-length atoms, and 0xCA ... 0xCE are
+//!    reserved for future use.
 //!
 //!    Once an atom constant identifying byte is read, the bytes that follow it will be read as
 //!    that type of atom. The number of bytes read depends on the length of the atom type, which is
@@ -115,29 +185,172 @@
 //! + 0xC3: char atom (32-bit Unicode scalar value)
 //! + 0xC4: float atom (64-bit double-precision floating point number
 //!
-//!    If additional primitive data types are added to the Seax VM, the bytes 0xC5 to 0xCF will
+//!    If additional primitive data types are added to the Seax VM, the bytes 0xCA to 0xCF will
 //!    be used to identify those types.
 //!
 //!    Note that the type tag identifying a constant may be extracted by byte-masking the
 //!    identifying byte with the number 0x0F.
 //!
+//! 3. Byte strings (0xC5)
+//!
+//!    0xC5 identifies a byte string constant. It is followed by a 32-bit unsigned integer giving
+//!    the length of the byte string in bytes, and then by that many bytes of raw data. Unlike a
+//!    list of uint atoms, which takes nine bytes of bytecode per byte of data, a byte string
+//!    costs only one byte per byte of data, plus the five bytes of its tag and length.
+//!
+//! 4. Constant pool references (0xC6)
+//!
+//!    0xC6 identifies a reference to an entry in the file's constant pool (see section IV). It
+//!    is followed by a 32-bit unsigned integer giving the index of the entry, starting at zero.
+//!    A reference may appear anywhere a constant may appear, including as the CAR part of a
+//!    CONS cell, and is decoded exactly as though the referenced constant had been written in
+//!    its place. This allows constants which occur many times, such as a closure body shared
+//!    between several call sites, to be stored only once.
+//!
+//! 5. Variable-length atoms (0xC7 ... 0xC9)
+//!
+//!    Most integer constants in real programs, such as environment indices and loop counters,
+//!    are small, but the fixed-length atom encodings always take eight bytes to store them. The
+//!    following identifying bytes denote alternative encodings of the integer and character
+//!    atom types, which take fewer bytes for smaller values:
+//!
+//! + 0xC7: uint atom, as an unsigned LEB128 integer
+//! + 0xC8: sint atom, zigzag-encoded and then stored as an unsigned LEB128 integer
+//! + 0xC9: char atom, with its Unicode scalar value stored as an unsigned LEB128 integer
+//!
+//!    An unsigned LEB128 integer is stored seven bits at a time, starting with the least
+//!    significant seven bits. The high bit of each byte is set if another byte follows, and
+//!    clear in the last byte. Encodings longer than ten bytes, or which do not fit in 64 bits,
+//!    are invalid. The zigzag encoding maps the signed integers 0, -1, 1, -2, 2, ... to the
+//!    unsigned integers 0, 1, 2, 3, 4, ..., so that numbers with small absolute values have
+//!    short encodings.
+//!
+//!    A value stored using a variable-length encoding decodes to exactly the same atom as the
+//!    same value stored using the corresponding fixed-length encoding.
+//!
+//! IV: Sections
+//! ------------
+//!
+//! In Revision 1 files, the version bytes are followed by:
+//!
+//! 1. A 16-bit field of flags. Decoders must reject files with flags that they do not
+//!    understand. The following flags are defined:
+//!    + 0x0001: checksum. The file contains a checksum (see below).
+//!    + 0x0002: signed. The file contains a signature block (see below).
+//!    + 0x0004: compressed. Everything following the flags is compressed as a single raw
+//!      DEFLATE stream (as specified in RFC 1951). Once decompressed, it is laid out exactly as
+//!      it would be in an uncompressed file. The checksum and signature are computed over the
+//!      uncompressed bytes, so they do not depend on how the file was compressed.
+//! 2. A 16-bit unsigned integer giving the number of sections in the file.
+//! 3. The section table. For each section, this contains one byte identifying the section's
+//!    kind, followed by a 32-bit unsigned integer giving the length of the section in bytes.
+//! 4. If the checksum flag is set, a 32-bit CRC-32 checksum (using the polynomial 0x04C11DB7,
+//!    as in zlib and PNG). The checksum is computed over the flags, the section count, the
+//!    section table, and the contents of every section, in that order.
+//! 5. If the signed flag is set, a 96-byte signature block. This consists of the signer's 32-byte
+//!    Ed25519 public key, followed by a 64-byte Ed25519 signature (as specified in RFC 8032) by
+//!    that key. The signature is computed over the same bytes as the checksum.
+//!
+//! The contents of each section follow the section table, in the order in which they are
+//! listed in the table. The following section kinds are defined:
+//!
+//! | Value | Section       | Contents
+//! +-------+---------------+--------------------------------------------------------------------
+//!   0x01  | code          | The program, encoded as described in sections II and III. This is
+//!                           exactly what follows the preamble in a Revision 0 file.
+//!   0x02  | constants     | A 32-bit unsigned count, followed by that many constants encoded as
+//!                           described in section III.
+//!   0x03  | metadata      | A 16-bit unsigned count, followed by that many key-value pairs. Each
+//!                           key is a 16-bit length followed by that many bytes of UTF-8 text,
+//!                           and each value is a 32-bit length followed by that many bytes of
+//!                           UTF-8 text.
+//!   0x04  | debug         | Debugging information, mapping cells to the spans of source text
+//!                           they were compiled from. See below.
+//!   0x05  | pool          | The constant pool. A 32-bit unsigned count, followed by that many
+//!                           constants encoded as described in section III. A constant in the
+//!                           pool may contain references to entries before it, but not to
+//!                           itself or to any entry after it. The pool is not part of the
+//!                           decoded program; it only exists to be referenced by the other
+//!                           sections.
+//!   0x06  | symbols       | Names for functions and environment bindings. See below.
+//!   0x07  | module        | Marks the file as a module, which must be linked with other
+//!                           modules before it can be executed. See below.
+//!
+//! The debug section begins with the file table: a 16-bit unsigned count, followed by that many
+//! source file names, each of which is a 16-bit length followed by that many bytes of UTF-8
+//! text. This is followed by a 32-bit unsigned count of spans, and then that many spans. Each
+//! span consists of six 32-bit unsigned integers: the position of the cell it belongs to, the
+//! index of its source file in the file table, and the line and column of its start and end.
+//! Lines and columns are numbered from one, and spans must be sorted by position, with no two
+//! spans sharing a position.
+//!
+//! The position of a cell is its index in a pre-order traversal of the code section, starting
+//! from zero; that is, a CONS cell's position is one more than that of the cell before it, and
+//! the first cell it contains has the position following it. Cells without a span of their own
+//! are taken to have the span of the nearest cell before them that has one.
+//!
+//! The symbols section contains two tables: the first names functions, and the second names
+//! environment bindings. Each table is a 32-bit unsigned count, followed by that many entries.
+//! Each entry consists of a 32-bit unsigned cell position, followed by a name: a 16-bit length
+//! followed by that many bytes of UTF-8 text. A function's name is attached to the position of
+//! the CONS cell containing its code, and a binding's name is attached to the position of each
+//! LD instruction which loads it. No table may contain two entries with the same position.
+//!
+//! The module section begins with the module's name, as a 16-bit length followed by that many
+//! bytes of UTF-8 text. This is followed by a 32-bit unsigned count of imported names, and then
+//! the imported names, each stored in the same way as the module's name. Finally, there is a
+//! 32-bit unsigned count of definitions, followed by that many definitions. Each definition
+//! consists of its name, a byte which is 0x01 if the definition is exported and 0x00 if it is
+//! not, a 32-bit unsigned count of relocations, and then that many 32-bit unsigned positions.
+//! A module's code section contains exactly one CONS cell per definition, in the same order,
+//! containing the definition's code. The positions of a definition's relocations are numbered
+//! from zero at the first cell within that CONS cell.
+//!
+//! A file must contain at most one section of each kind, and must contain a code section.
+//! Decoders should skip sections of kinds that they do not recognize, so that future revisions
+//! may define new section kinds without breaking older decoders.
+//!
 
 extern crate byteorder;
 
 use self::byteorder::{ByteOrder, BigEndian, ReadBytesExt, WriteBytesExt};
 
 use std::error::Error;
-use std::io::Read;
+use std::io::{Read, Cursor};
 use std::fmt;
 use std::char;
+use std::iter;
 use std::mem::transmute;
+use std::option;
+
+use self::crc32::Crc32;
+use self::pool::Pool;
 
 use super::List;
-use super::List::*;
+use super::list::{ListIterator, ListBuilder, DottedList};
 use super::{SVMCell,Atom,Inst};
+use super::cell::{Interner, Interned};
 use super::SVMCell::*;
 use super::Atom::*;
 
+mod compression;
+mod crc32;
+mod debug;
+mod lazy;
+mod link;
+mod pool;
+mod signature;
+mod symbols;
+mod varint;
+
+pub use self::debug::{DebugInfo, Span};
+pub use self::lazy::{LazyDecoder, CellView, ListView, CellViews};
+pub use self::symbols::Symbols;
+pub use self::link::{Module, Definition, LinkError, link};
+
+#[cfg(feature = "signing")]
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
 #[cfg(test)]
 mod tests;
 
@@ -158,45 +371,437 @@ macro_rules! push_all {
 #[cfg_attr(feature = "unstable",
     stable(feature = "decode", since = "0.1.0") )]
 pub const IDENT_BYTES: u16 = 0x5ECD;
-/// Identifying bytes for this version of the Seax bytecode standard.
+/// Version bytes for Revision 0 of the Seax bytecode standard, the same as
+/// `REVISION_0`, kept for compatibility. Files are now written as
+/// `REVISION_1` by default.
 #[cfg_attr(feature = "unstable",
     stable(feature = "decode", since="0.3.0") )]
 pub const VERSION: u16     = 0x0000;
+/// Version bytes for Revision 0 of the Seax bytecode standard.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+pub const REVISION_0: u16  = 0x0000;
+/// Version bytes for Revision 1 of the Seax bytecode standard.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+pub const REVISION_1: u16  = 0x0001;
 
 /// block reserved for future opcodes
-const RESERVED_START: u8  = 0x1E;
-const RESERVED_LEN: u8    = 0x12;
+const RESERVED_START: u8  = 0x30;
+const RESERVED_LEN: u8    = 0x00;
 /// block reserved for typetags
 const CONST_START: u8     = 0xC1;
 const CONST_LEN: u8       = 0x0E;
 /// important bytecodes
 const BYTE_CONS: u8       = 0xC0;
 const BYTE_NIL: u8        = 0x00;
+const BYTE_BYTES: u8      = 0xC5;
+const BYTE_REF: u8        = 0xC6;
+const BYTE_VARUINT: u8    = 0xC7;
+const BYTE_VARSINT: u8    = 0xC8;
+const BYTE_VARCHAR: u8    = 0xC9;
+
+/// flag bits
+const FLAG_CHECKSUM: u16    = 0x0001;
+const FLAG_SIGNED: u16      = 0x0002;
+const FLAG_COMPRESSED: u16  = 0x0004;
+const KNOWN_FLAGS: u16      = FLAG_CHECKSUM | FLAG_SIGNED | FLAG_COMPRESSED;
+/// section kind bytes
+const SECTION_CODE: u8      = 0x01;
+const SECTION_CONSTANTS: u8 = 0x02;
+const SECTION_METADATA: u8  = 0x03;
+const SECTION_DEBUG: u8     = 0x04;
+const SECTION_POOL: u8      = 0x05;
+const SECTION_SYMBOLS: u8   = 0x06;
+const SECTION_MODULE: u8    = 0x07;
+
+/// The maximum number of cells a `Decoder` will add to a single section by
+/// expanding references to the constant pool, by default.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "decode", issue = "94") )]
+pub const DEFAULT_MAX_CELLS: usize = 1 << 24;
+/// The maximum length of a decompressed file, in bytes, by default.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "decode", issue = "94") )]
+pub const DEFAULT_MAX_DECOMPRESSED_LEN: usize = 1 << 28;
+/// The maximum depth to which a `Decoder` or an `Encoder` allows lists to
+/// be nested, by default.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "decode", issue = "94") )]
+pub const DEFAULT_MAX_DEPTH: usize = 1 << 12;
 
 /// Decode a whole program
 ///
 /// Decodes a whole program, including the identifying and version bytes.
+/// Both Revision 0 and Revision 1 files are accepted; for Revision 1 files,
+/// only the contents of the code section are returned. Use
+/// `Decoder::read_program()` to access the other sections.
 #[cfg_attr(feature = "unstable",
     unstable(feature = "decode", issue = "94") )]
 pub fn decode_program<R>(source: &mut R) -> Result<List<SVMCell>, String>
 where R: Read {
-    let mut decoder = Decoder::new(source);
-    decoder
-        .check_ident_bytes()
-        .map(|_| decoder.check_version()
-                        .map_err(|why| warn!("{}", why) )
-            )
-        .map(|_| // TODO: carry over errors from next_cell()
-                 // rather than upwrapping
-            decoder.collect::<List<SVMCell>>()
-            )
+    Decoder::new(source)
+        .read_program()
+        .map(|program| program.code)
+        .map_err(|why| why.to_string())
+}
+
+/// Decodes `bytes` in every way a `Decoder` or `LazyDecoder` can.
+///
+/// The results are discarded. This is used by the tests and the fuzzer to
+/// check that decoding arbitrary bytes fails gracefully rather than
+/// panicking, and is not part of the public API.
+#[doc(hidden)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "decode", issue = "94") )]
+pub fn decode_everything(bytes: &[u8]) {
+    const MAX_CELLS: usize = 1 << 16;
+    const MAX_DECOMPRESSED_LEN: usize = 1 << 20;
+
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_cells(MAX_CELLS);
+    decoder.set_max_decompressed_len(MAX_DECOMPRESSED_LEN);
+    let _ = decoder.read_program();
+
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_cells(MAX_CELLS);
+    decoder.set_max_decompressed_len(MAX_DECOMPRESSED_LEN);
+    if let Ok(module) = decoder.read_module() {
+        let _ = link(&[module], "main");
+    }
+
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_cells(MAX_CELLS);
+    let _ = decoder.count();
+
+    if let Ok(lazy) = LazyDecoder::with_max_cells(bytes, MAX_CELLS) {
+        for view in lazy.cells() {
+            if let Ok(CellView::List(list)) = view {
+                let _ = list.byte_len();
+                let _ = list.to_list();
+            }
+        }
+        let _ = lazy.to_list();
+    }
+}
+
+/// Errors that may occur while decoding a whole program.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+pub enum DecodeError {
+    /// The bytecode is malformed, or could not be read.
+    Malformed(String),
+    /// The file's checksum does not match its contents, indicating that
+    /// the file has been corrupted.
+    ChecksumMismatch {
+        /// The checksum recorded in the preamble.
+        expected: u32,
+        /// The checksum computed from the file's contents.
+        actual: u32
+    },
+    /// Signatures are being verified, but the file is not signed.
+    Unsigned,
+    /// The file is signed by a key that is not trusted. The key is
+    /// provided so that it may be reported to the user.
+    UntrustedKey([u8; 32]),
+    /// The file's signature is invalid, indicating that the file has
+    /// been tampered with or corrupted since it was signed.
+    BadSignature
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Malformed(ref why) => write!(f, "{}", why),
+            DecodeError::ChecksumMismatch { expected, actual } =>
+                write!(f, "checksum mismatch: expected {:#010x}, got {:#010x}",
+                    expected, actual),
+            DecodeError::Unsigned =>
+                write!(f, "file is not signed"),
+            DecodeError::UntrustedKey(ref key) => {
+                write!(f, "file is signed by untrusted key ")?;
+                for byte in key {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            },
+            DecodeError::BadSignature =>
+                write!(f, "invalid signature")
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+impl Error for DecodeError {}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+impl From<String> for DecodeError {
+    fn from(why: String) -> DecodeError { DecodeError::Malformed(why) }
+}
+
+/// Decodes every cell in a byte buffer, stopping at the first error.
+///
+/// Constant pool references are resolved against `pool`.
+fn decode_cells(bytes: &[u8], pool: DecodedPool, max_cells: usize,
+                max_depth: usize) -> Result<List<SVMCell>, String> {
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.pool = pool;
+    decoder.max_cells = max_cells;
+    decoder.max_depth = max_depth;
+    iter::from_fn(|| decoder.next_cell().transpose())
+        .collect()
+}
+
+/// The entries of a decoded constant pool.
+///
+/// The number of cells in each entry is recorded, so that references to
+/// the entry can be counted against a decoder's limit without traversing
+/// it.
+#[derive(Clone,Debug,Default)]
+struct DecodedPool {
+    entries: Vec<SVMCell>,
+    sizes: Vec<usize>
+}
+
+/// A decoded Seax program, along with the contents of any additional
+/// sections stored alongside it.
+///
+/// A `Program` can be encoded to either revision of the bytecode format
+/// using an `Encoder`, and is returned by `Decoder::read_program()`.
+/// Revision 0 files can only contain code, so all of the other fields of
+/// a `Program` decoded from a Revision 0 file will be empty.
+#[derive(Clone,Debug,PartialEq,Default)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+pub struct Program {
+    /// The program's instructions and constants.
+    pub code: List<SVMCell>,
+    /// The contents of the constants section.
+    pub constants: Vec<SVMCell>,
+    /// Key-value pairs from the metadata section, in the order in which
+    /// they were stored.
+    pub metadata: Vec<(String, String)>,
+    /// Debugging information from the debug section, if there is one.
+    pub debug: Option<DebugInfo>,
+    /// Names of functions and bindings from the symbols section, if there
+    /// is one.
+    pub symbols: Option<Symbols>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+impl Program {
+    /// Creates a new `Program` containing the given code and no other
+    /// sections.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn new(code: List<SVMCell>) -> Program {
+        Program { code, ..Default::default() }
+    }
+
+    /// Looks up a metadata value by key.
+    ///
+    /// If the key occurs more than once, the first value is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use seax_util::bytecode::Program;
+    /// # use seax_util::list::List;
+    /// let mut program = Program::new(List::new());
+    /// program.metadata.push((String::from("author"), String::from("hawk")));
+    /// assert_eq!(program.get_metadata("author"), Some("hawk"));
+    /// assert_eq!(program.get_metadata("license"), None);
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn get_metadata(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// Looks up the source span of the cell at `position`.
+    ///
+    /// Returns `None` if the program has no debugging information, or if
+    /// no span is recorded at or before `position`. See `DebugInfo` for
+    /// how cell positions are numbered.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "debuginfo", issue = "94") )]
+    pub fn span(&self, position: u32) -> Option<&Span> {
+        self.debug
+            .as_ref()
+            .and_then(|debug| debug.lookup(position))
+    }
+
+    /// Returns an iterator over every cell in the program's code, along
+    /// with its position, in pre-order.
+    ///
+    /// Positions are used to refer to cells by the debug and symbols
+    /// sections.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::bytecode::Program;
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Inst::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let program = Program::new(list!(
+    ///     InstCell(LDF), list_cell![ InstCell(NIL), InstCell(RET) ], InstCell(AP)
+    /// ));
+    /// let cells: Vec<_> = program.cells().collect();
+    /// assert_eq!(cells[2], (2, &InstCell(NIL)));
+    /// assert_eq!(cells[4], (4, &InstCell(AP)));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "symbols", issue = "94") )]
+    pub fn cells<'a>(&'a self) -> Cells<'a> {
+        Cells { stack: vec![self.code.iter().chain(None)], position: 0 }
+    }
+}
+
+/// An iterator over the cells in a program and their positions.
+///
+/// This is returned by `Program::cells()`.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "symbols", issue = "94") )]
+pub struct Cells<'a> {
+    // the cells of each list we are inside, followed by its final cdr if
+    // it is an improper list
+    stack: Vec<iter::Chain<ListIterator<'a, SVMCell>,
+                           option::IntoIter<&'a SVMCell>>>,
+    position: u32
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "symbols", issue = "94") )]
+impl<'a> Iterator for Cells<'a> {
+    type Item = (u32, &'a SVMCell);
+
+    fn next(&mut self) -> Option<(u32, &'a SVMCell)> {
+        loop {
+            let next = match self.stack.last_mut() {
+                Some(iter) => iter.next(),
+                None => return None
+            };
+            match next {
+                Some(cell) => {
+                    match *cell {
+                        ListCell(ref list) =>
+                            self.stack.push(list.iter().chain(None)),
+                        DottedCell(ref dotted) =>
+                            self.stack.push(dotted.items().iter()
+                                .chain(Some(dotted.last_cdr()))),
+                        _ => {}
+                    }
+                    let position = self.position;
+                    self.position += 1;
+                    return Some((position, cell));
+                },
+                None => { self.stack.pop(); }
+            }
+        }
+    }
 }
 
 #[cfg_attr(feature = "unstable",
     stable(feature = "decode", since="0.1.0"))]
 pub struct Decoder<'a, R: 'a> {
     source: &'a mut R,
-    num_read: usize
+    num_read: usize,
+    verify_checksum: bool,
+    signer: Option<[u8; 32]>,
+    pool: DecodedPool,
+    module: Option<Vec<u8>>,
+    /// The number of cells decoded so far, counting each reference to
+    /// the constant pool as the number of cells it refers to.
+    num_cells: usize,
+    /// The number of cells added so far by expanding references to the
+    /// constant pool, which is limited to `max_cells`.
+    num_expanded: usize,
+    max_cells: usize,
+    max_depth: usize,
+    max_decompressed_len: usize,
+    #[cfg(feature = "signing")]
+    trusted_keys: Option<Vec<VerifyingKey>>
+}
+
+/// Assembles cells from their parts as they are decoded.
+trait Assemble {
+    /// An assembled cell.
+    type Cell;
+    /// The items of a list which is being decoded.
+    type Items;
+
+    /// Assembles a cell which was decoded whole: an atom, an instruction,
+    /// a byte string, or a cell referred to in the constant pool.
+    fn cell(&mut self, cell: SVMCell) -> Self::Cell;
+
+    /// Starts a new list.
+    fn items(&mut self) -> Self::Items;
+
+    /// Adds an item to the end of a list.
+    fn push(&mut self, items: &mut Self::Items, item: Self::Cell);
+
+    /// Assembles a list of items, which is improper if `cdr` is present.
+    fn list(&mut self, items: Self::Items, cdr: Option<Self::Cell>) -> Self::Cell;
+}
+
+/// Assembles `SVMCell`s.
+struct Plain;
+
+impl Assemble for Plain {
+    type Cell = SVMCell;
+    type Items = ListBuilder<SVMCell>;
+
+    fn cell(&mut self, cell: SVMCell) -> SVMCell { cell }
+
+    fn items(&mut self) -> ListBuilder<SVMCell> { ListBuilder::new() }
+
+    fn push(&mut self, items: &mut ListBuilder<SVMCell>, item: SVMCell) {
+        items.push_back(item);
+    }
+
+    fn list(&mut self, items: ListBuilder<SVMCell>, cdr: Option<SVMCell>)
+           -> SVMCell {
+        match cdr {
+            Some(cdr) => SVMCell::dotted(items.build(), cdr),
+            None => SVMCell::ListCell(Box::new(items.build()))
+        }
+    }
+}
+
+/// Interns cells as they are assembled.
+impl Assemble for Interner {
+    type Cell = Interned;
+    type Items = Vec<Interned>;
+
+    fn cell(&mut self, cell: SVMCell) -> Interned { self.intern(&cell) }
+
+    fn items(&mut self) -> Vec<Interned> { Vec::new() }
+
+    fn push(&mut self, items: &mut Vec<Interned>, item: Interned) {
+        items.push(item);
+    }
+
+    fn list(&mut self, items: Vec<Interned>, cdr: Option<Interned>)
+           -> Interned {
+        let last = cdr.unwrap_or_else(|| self.nil());
+        items.into_iter().rev()
+             .fold(last, |cdr, car| self.cons(car, cdr))
+    }
 }
 
 /// Decode a Seax instruction from a byte
@@ -209,9 +814,7 @@ fn decode_inst(byte: &u8) -> Result<Inst, String> {
         b if b >= RESERVED_START &&
              b <= (RESERVED_START + RESERVED_LEN) =>
             Err(format!("Unimplemented: reserved byte {:#X}", b)),
-        b if b > (RESERVED_START + RESERVED_LEN) =>
-            Err(String::from("byte too high")),
-        _  => unreachable!() // Should require an act of God.
+        _  => Err(String::from("byte too high"))
     }
 }
 
@@ -233,7 +836,7 @@ where R: Read {
     pub fn check_ident_bytes(&mut self) -> Result<(), String> {
         self.source
             .read_u16::<BigEndian>()
-            .map_err(|why| String::from(why.description()))
+            .map_err(|why| why.to_string())
             .and_then(|ident| {
                 self.num_read += 2;
                 match ident {
@@ -247,40 +850,382 @@ where R: Read {
 
     /// Checks the version bytes
     ///
-    /// Checks the version bytes of a Seax bytecode file against the VERSION
-    /// of the bytecode standard used by this code. If the versions are not
-    /// the same, an error is returned.
+    /// Checks the version bytes of a Seax bytecode file against the
+    /// revisions of the bytecode standard supported by this code. If the
+    /// file's version is not supported, an error is returned.
     ///
     /// Consumes two bytes.
     #[cfg_attr(feature = "unstable",
         stable(feature = "decode", since="0.1.0") )]
     pub fn check_version(&mut self) -> Result<(), String> {
-        self.source
-            .read_u16::<BigEndian>()
-            .map_err(|why| String::from(why.description()))
-            .and_then(|version| {
-                self.num_read += 2;
-                match version {
-                    VERSION => Ok(()),
-                    bytes   => Err( // I expect this will generate a warning
-                                    // at the call site...
-                        format!("mismatched version {}, expected {}",
-                            bytes, version)
-                    )
-                }
+        self.read_version()
+            .and_then(|version| match version {
+                REVISION_0 | REVISION_1 => Ok(()),
+                bytes   => Err( // I expect this will generate a warning
+                                // at the call site...
+                    format!("mismatched version {}, expected {} or {}",
+                        bytes, REVISION_0, REVISION_1)
+                )
             })
     }
 
+    /// Reads the version bytes
+    ///
+    /// Returns the revision of the Seax bytecode standard that the file
+    /// claims to be encoded with, without checking whether that revision
+    /// is supported.
+    ///
+    /// Consumes two bytes.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn read_version(&mut self) -> Result<u16, String> {
+        let version = self.source
+                          .read_u16::<BigEndian>()
+                          .map_err(|why| why.to_string())?;
+        self.num_read += 2;
+        Ok(version)
+    }
+
+    /// Decodes a whole program, including the preamble.
+    ///
+    /// The version bytes determine how the rest of the file is decoded.
+    /// Revision 0 and Revision 1 files are supported. Files claiming any
+    /// other version are decoded as though they were Revision 0 files,
+    /// and a warning is logged.
+    ///
+    /// Consumes the remainder of the source.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn read_program(&mut self) -> Result<Program, DecodeError> {
+        self.check_ident_bytes()?;
+        self.signer = None;
+        self.module = None;
+        match self.read_version()? {
+            REVISION_1 => self.read_sections(),
+            _ if self.requires_signature() => Err(DecodeError::Unsigned),
+            REVISION_0 => Ok(self.read_code().map(Program::new)?),
+            version    => {
+                warn!("mismatched version {}, expected {}; \
+                       attempting to decode as revision {}",
+                      version, REVISION_1, REVISION_0);
+                Ok(self.read_code().map(Program::new)?)
+            }
+        }
+    }
+
+    /// Decodes a module, including the preamble.
+    ///
+    /// This works exactly like `read_program()`, except that the file must
+    /// contain a module section, and the result is a `Module` which may be
+    /// linked with other modules using `link()`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "link", issue = "94") )]
+    pub fn read_module(&mut self) -> Result<Module, DecodeError> {
+        let program = self.read_program()?;
+        let bytes = self.module.take().ok_or_else(||
+            String::from("missing module section"))?;
+        Ok(link::decode_module(program.code, &bytes)?)
+    }
+
+    #[cfg(feature = "signing")]
+    fn requires_signature(&self) -> bool { self.trusted_keys.is_some() }
+
+    #[cfg(not(feature = "signing"))]
+    fn requires_signature(&self) -> bool { false }
+
+    /// Decodes cells until the end of the source, stopping at the first
+    /// error.
+    fn read_code(&mut self) -> Result<List<SVMCell>, String> {
+        iter::from_fn(|| self.next_cell().transpose())
+            .collect()
+    }
+
+    /// Reads exactly `len` bytes from the source.
+    ///
+    /// The `what` argument describes what is being read, for error
+    /// messages.
+    fn read_vec(&mut self, len: usize, what: &str) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        (&mut *self.source).take(len as u64)
+            .read_to_end(&mut bytes)
+            .map_err(|why| why.to_string())?;
+        self.num_read += bytes.len();
+        if bytes.len() == len {
+            Ok(bytes)
+        } else {
+            Err(format!("EOF while decoding {}: expected {} bytes, got {}",
+                what, len, bytes.len()))
+        }
+    }
+
+    /// Decodes the section table and sections of a Revision 1 file.
+    ///
+    /// This is called after the identifying and version bytes have been
+    /// read.
+    fn read_sections(&mut self) -> Result<Program, DecodeError> {
+        let flags = self.read_u16()?;
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(DecodeError::Malformed(
+                format!("unsupported flags {:#06x}", flags)));
+        }
+        if flags & FLAG_COMPRESSED == 0 {
+            return self.read_section_table(flags);
+        }
+        let mut compressed = Vec::new();
+        self.num_read += self.source
+                             .read_to_end(&mut compressed)
+                             .map_err(|why| why.to_string())?;
+        let decompressed = compression::decompress(&compressed,
+                                                   self.max_decompressed_len)?;
+        let mut cursor = Cursor::new(decompressed);
+        let mut decoder = Decoder::new(&mut cursor);
+        decoder.verify_checksum = self.verify_checksum;
+        decoder.max_cells = self.max_cells;
+        decoder.max_depth = self.max_depth;
+        #[cfg(feature = "signing")]
+        { decoder.trusted_keys = self.trusted_keys.clone(); }
+        let program = decoder.read_section_table(flags);
+        self.signer = decoder.signer;
+        self.module = decoder.module;
+        program
+    }
+
+    /// Decodes everything following the flags in a Revision 1 file.
+    fn read_section_table(&mut self, flags: u16) -> Result<Program, DecodeError> {
+        let count = self.read_u16()?;
+        let mut header = Vec::new();
+        header.write_u16::<BigEndian>(flags).unwrap();
+        header.write_u16::<BigEndian>(count).unwrap();
+        let mut table = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let entry = self.read_vec(5, "section table")?;
+            header.extend_from_slice(&entry);
+            let len = (&entry[1..]).read_u32::<BigEndian>().unwrap();
+            table.push((entry[0], len));
+        }
+        let checksum = if flags & FLAG_CHECKSUM != 0 {
+            Some(self.read_u32()?)
+        } else {
+            None
+        };
+        let signature = if flags & FLAG_SIGNED != 0 {
+            let block = self.read_vec(signature::BLOCK_LEN, "signature")?;
+            self.signer = Some(signature::signer(&block));
+            Some(block)
+        } else if self.requires_signature() {
+            return Err(DecodeError::Unsigned);
+        } else {
+            None
+        };
+
+        let mut sections = Vec::with_capacity(table.len());
+        for (kind, len) in table {
+            let bytes = self.read_vec(len as usize, "section")?;
+            debug!("Read section {:#04x}, {} bytes", kind, len);
+            sections.push((kind, bytes));
+        }
+        match checksum {
+            Some(expected) if self.verify_checksum => {
+                let mut crc = Crc32::new();
+                crc.update(&header);
+                for (_, bytes) in &sections {
+                    crc.update(bytes);
+                }
+                let actual = crc.finish();
+                if expected != actual {
+                    return Err(DecodeError::ChecksumMismatch {
+                        expected, actual
+                    });
+                }
+            },
+            _ => {}
+        }
+        self.verify_signature(signature, header, &sections)?;
+
+        // the pool must be decoded first, as other sections refer to it
+        let pool = match sections.iter().find(|(kind, _)| *kind == SECTION_POOL) {
+            Some((_, bytes)) => decode_pool(bytes, self.max_cells,
+                                            self.max_depth)?,
+            None => DecodedPool::default()
+        };
+
+        let mut code = None;
+        let mut program = Program::default();
+        for (kind, bytes) in sections {
+            match kind {
+                SECTION_CODE if code.is_some() =>
+                    return Err(DecodeError::Malformed(
+                        String::from("duplicate code section"))),
+                SECTION_CODE =>
+                    code = Some(decode_cells(&bytes, pool.clone(),
+                                             self.max_cells, self.max_depth)?),
+                SECTION_CONSTANTS =>
+                    program.constants = decode_constants(&bytes, pool.clone(),
+                                                         self.max_cells,
+                                                         self.max_depth)?,
+                SECTION_POOL => {},
+                SECTION_METADATA =>
+                    program.metadata = decode_metadata(&bytes)?,
+                SECTION_DEBUG =>
+                    program.debug = Some(debug::decode(&bytes)?),
+                SECTION_SYMBOLS =>
+                    program.symbols = Some(symbols::decode(&bytes)?),
+                SECTION_MODULE =>
+                    self.module = Some(bytes),
+                other =>
+                    warn!("skipping unknown section {:#04x}", other)
+            }
+        }
+        program.code = code.ok_or(String::from("missing code section"))?;
+        Ok(program)
+    }
+
+    #[cfg(feature = "signing")]
+    fn verify_signature(&self, block: Option<Vec<u8>>, header: Vec<u8>,
+                        sections: &[(u8, Vec<u8>)])
+                        -> Result<(), DecodeError> {
+        match (block, self.trusted_keys.as_ref()) {
+            (Some(block), Some(trusted)) => {
+                let mut message = header;
+                for (_, bytes) in sections {
+                    message.extend_from_slice(bytes);
+                }
+                signature::verify(&block, &message, trusted)
+            },
+            _ => Ok(())
+        }
+    }
+
+    #[cfg(not(feature = "signing"))]
+    fn verify_signature(&self, _: Option<Vec<u8>>, _: Vec<u8>,
+                        _: &[(u8, Vec<u8>)])
+                        -> Result<(), DecodeError> {
+        Ok(())
+    }
+
+    fn read_varint(&mut self) -> Result<u64, String> {
+        let (n, len) = varint::read(self.source)?;
+        self.num_read += len;
+        Ok(n)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, String> {
+        let n = self.source
+                    .read_u16::<BigEndian>()
+                    .map_err(|why| why.to_string())?;
+        self.num_read += 2;
+        Ok(n)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let n = self.source
+                    .read_u32::<BigEndian>()
+                    .map_err(|why| why.to_string())?;
+        self.num_read += 4;
+        Ok(n)
+    }
+
     /// Creates a new decoder from a type implementing `std::io::Read`
     #[cfg_attr(feature = "unstable",
         stable(feature = "decode", since="0.1.0") )]
     pub fn new(src: &'a mut R) -> Decoder<'a, R> {
         Decoder {
             source: src,
-            num_read: 0
+            num_read: 0,
+            verify_checksum: true,
+            signer: None,
+            pool: DecodedPool::default(),
+            module: None,
+            num_cells: 0,
+            num_expanded: 0,
+            max_cells: DEFAULT_MAX_CELLS,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_decompressed_len: DEFAULT_MAX_DECOMPRESSED_LEN,
+            #[cfg(feature = "signing")]
+            trusted_keys: None
         }
     }
 
+    /// Requires files to be signed by one of the given keys.
+    ///
+    /// Once this has been called, `read_program()` will only accept
+    /// Revision 1 files with a valid signature by one of the `trusted`
+    /// keys, returning `DecodeError::Unsigned`, `DecodeError::UntrustedKey`
+    /// or `DecodeError::BadSignature` otherwise. The signature is checked
+    /// before any cells are decoded.
+    ///
+    /// Signatures are not verified unless this is called.
+    #[cfg(feature = "signing")]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "signing", issue = "94") )]
+    pub fn set_trusted_keys(&mut self, trusted: Vec<VerifyingKey>) {
+        self.trusted_keys = Some(trusted);
+    }
+
+    /// Returns the public key of the signer of the last program read.
+    ///
+    /// Returns `None` if the program was not signed. Note that unless
+    /// trusted keys have been set with `set_trusted_keys()`, the
+    /// signature is not verified, so this does not prove that the
+    /// program was actually signed by this key.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "signing", issue = "94") )]
+    pub fn signer(&self) -> Option<[u8; 32]> {
+        self.signer
+    }
+
+    /// Sets whether checksums should be verified.
+    ///
+    /// By default, if a file contains a checksum, `read_program()` checks
+    /// it against the file's contents before decoding any cells, and
+    /// returns `DecodeError::ChecksumMismatch` if they differ. Passing
+    /// `false` skips this check.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "checksum", issue = "94") )]
+    pub fn set_verify_checksum(&mut self, verify: bool) {
+        self.verify_checksum = verify;
+    }
+
+    /// Sets the maximum number of cells which references to the constant
+    /// pool may add to a single section.
+    ///
+    /// A small file can refer to the same constant pool entry many times,
+    /// and entries can refer to each other, so the number of cells in the
+    /// decoded program can grow exponentially with the length of the
+    /// file. Each reference adds the number of cells in the entry it
+    /// refers to, and decoding fails once references have added more than
+    /// `max` cells to a section. Cells which are not references are not
+    /// counted, as their number is limited by the length of the file. The
+    /// default is `DEFAULT_MAX_CELLS`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "decode", issue = "94") )]
+    pub fn set_max_cells(&mut self, max: usize) {
+        self.max_cells = max;
+    }
+
+    /// Sets the maximum depth to which lists may be nested.
+    ///
+    /// Decoding a list nested inside `max` other lists fails. Lists are
+    /// decoded iteratively, but dropping a deeply nested list is
+    /// recursive, so this protects against files which would overflow the
+    /// stack when their cells are dropped. The default is
+    /// `DEFAULT_MAX_DEPTH`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "decode", issue = "94") )]
+    pub fn set_max_depth(&mut self, max: usize) {
+        self.max_depth = max;
+    }
+
+    /// Sets the maximum length of a compressed file once decompressed.
+    ///
+    /// Decoding a compressed file fails if it decompresses to more than
+    /// `max` bytes. The default is `DEFAULT_MAX_DECOMPRESSED_LEN`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "decode", issue = "94") )]
+    pub fn set_max_decompressed_len(&mut self, max: usize) {
+        self.max_decompressed_len = max;
+    }
+
     /// Returns the number of bytes read by the decoder
     #[cfg_attr(feature = "unstable",
         stable(feature = "decode", since="0.1.0") )]
@@ -306,20 +1251,20 @@ where R: Read {
                 self.source
                     .read_u64::<BigEndian>()
                     .map(Atom::UInt)
-                    .map_err(|why| String::from(why.description()))
+                    .map_err(|why| why.to_string())
                 },
             2 => {
                 self.num_read += 8;
                 self.source
                     .read_i64::<BigEndian>()
                     .map(Atom::SInt)
-                    .map_err(|why| String::from(why.description()))
+                    .map_err(|why| why.to_string())
                 },
             3 => {
                 self.num_read += 4;
                 self.source
                     .read_u32::<BigEndian>()
-                    .map_err( |why | String::from(why.description()))
+                    .map_err( |why | why.to_string())
                     .and_then(|byte|
                         char::from_u32(byte)
                             .ok_or(String::from("Could not read character."))
@@ -331,76 +1276,249 @@ where R: Read {
                 self.source
                     .read_f64::<BigEndian>()
                     .map(Atom::Float)
-                    .map_err(|why| String::from(why.description()))
+                    .map_err(|why| why.to_string())
                 },
-            _ => unimplemented!()
+            7 => self.read_varint().map(Atom::UInt),
+            8 => self.read_varint()
+                     .map(varint::unzigzag)
+                     .map(Atom::SInt),
+            9 => self.read_varint()
+                     .and_then(|n| if n <= u32::MAX as u64 {
+                        char::from_u32(n as u32)
+                            .ok_or(String::from("Could not read character."))
+                     } else {
+                        Err(String::from("Could not read character."))
+                     })
+                     .map(Atom::Char),
+            _ => Err(format!("Unsupported constant type {:#02x}", byte))
         }
     }
-    // Decodes a CONS cell
-    //
-    // Consumes a varying number of bytes depending on the length of the list.
+    /// Decodes a byte string
+    ///
+    /// Reads the 32-bit length prefix following a byte string's
+    /// identifying byte, and then reads that many bytes of data.
+    ///
+    /// Consumes four bytes plus the length of the byte string.
     #[cfg_attr(feature = "unstable",
-        stable(feature = "decode", since="0.1.0") )]
-    fn decode_cons(&mut self) -> Result<Option<Box<List<SVMCell>>>, String> {
-        self.next_cell()
-            .and_then(|car|
-                car.ok_or(String::from("EOF while decoding CONS cell"))
-            )
-            .map(|car| {
-                debug!("Decoded {:?}, {} bytes read", car, self.num_read);
-                car
-            })
-            .and_then(|car| {
-                let mut buf = [0;1];
-                try!(self.source.read(&mut buf) // try to get next byte
-                         .map_err(|why| String::from(why.description())));
-                self.num_read += 1;
-                match buf[0] {
-                    BYTE_CONS =>
-                        self.decode_cons()
-                            .and_then(|cdr| cdr.ok_or(
-                                String::from("EOF while decoding CONS")) )
-                            .map( |cdr| (car, cdr) ),
-                    BYTE_NIL  => Ok((car, Box::new(Nil))),
-                    b         => Err(
-                        format!("Unexpected byte {:#02x} while decoding CONS", b)
-                    )
-                }
-            })
-            .map(|(car, cdr)| Some(Box::new( Cons(car, cdr)) ))
+        unstable(feature = "bytes", issue = "94") )]
+    fn decode_bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.read_u32()?;
+        self.read_vec(len as usize, "byte string")
     }
 
-    /// Decodes the next cell in the source
+    /// Decodes a constant pool reference
+    ///
+    /// Reads the 32-bit index following a reference's identifying byte,
+    /// and returns a copy of the referenced pool entry.
+    ///
+    /// Consumes four bytes.
     #[cfg_attr(feature = "unstable",
-        stable(feature = "decode", since="0.1.0") )]
-    pub fn next_cell(&mut self) -> Result<Option<SVMCell>,String> {
+        unstable(feature = "pool", issue = "94") )]
+    fn decode_ref(&mut self) -> Result<SVMCell, String> {
+        let index = self.read_u32()? as usize;
+        let size = self.pool.sizes.get(index).cloned().ok_or_else(||
+            format!("invalid constant pool reference {}", index))?;
+        // the reference itself has already been counted as one cell
+        self.count_cells(size - 1);
+        self.num_expanded = self.num_expanded.saturating_add(size - 1);
+        if self.num_expanded > self.max_cells {
+            return Err(format!("too many cells: the limit is {}",
+                               self.max_cells));
+        }
+        Ok(self.pool.entries[index].clone())
+    }
+
+    /// Counts `n` decoded cells.
+    fn count_cells(&mut self, n: usize) {
+        self.num_cells = self.num_cells.saturating_add(n);
+    }
+
+    /// Reads a single byte, returning `None` at EOF.
+    fn read_byte(&mut self) -> Result<Option<u8>, String> {
         let mut buf = [0;1];
         match self.source.read(&mut buf) {
-            Ok(1)   => { // a byte was read
+            Ok(0)    => Ok(None),
+            Ok(_)    => {
                 self.num_read += 1;
                 debug!("Read {:#X}, {} bytes read", buf[0], self.num_read);
-                match buf[0] {
-                    b if b < 0x30 => decode_inst(&b)
-                                        .map(SVMCell::InstCell)
-                                        .map(Some),
-                    b if b >= CONST_START &&
-                         b < (CONST_START + CONST_LEN) =>
-                                    self.decode_const(&b)
-                                        .map(SVMCell::AtomCell)
-                                        .map(Some),
-                    BYTE_CONS    => self.decode_cons()
-                                        .map(|cell|
-                                              cell.map(SVMCell::ListCell)
-                                        ),
-                    b            => Err(format!("Unsupported byte {:#02x}", b))
-                }
+                Ok(Some(buf[0]))
             },
-            Ok(0)    => Ok(None), //  we're out of bytes - EOF
-            Ok(_)    => unreachable!(), //
-            Err(why) => Err(String::from(why.description()))
+            Err(why) => Err(why.to_string())
         }
     }
 
+    /// Decodes a cell which is not a list, given its identifying byte
+    fn decode_atom(&mut self, byte: u8) -> Result<SVMCell, String> {
+        match byte {
+            b if b <= RESERVED_START + RESERVED_LEN =>
+                            decode_inst(&b).map(SVMCell::InstCell),
+            BYTE_BYTES   => self.decode_bytes().map(SVMCell::BytesCell),
+            BYTE_REF     => self.decode_ref(),
+            b if b >= CONST_START &&
+                 b < (CONST_START + CONST_LEN) =>
+                            self.decode_const(&b).map(SVMCell::AtomCell),
+            b            => Err(format!("Unsupported byte {:#02x}", b))
+        }
+    }
+
+    /// Decodes the next cell in the source
+    ///
+    /// Lists are decoded iteratively, so the depth to which they are nested
+    /// is not limited by the native stack. It is limited by
+    /// `set_max_depth()`, however, since dropping a deeply nested list is
+    /// recursive.
+    #[cfg_attr(feature = "unstable",
+        stable(feature = "decode", since="0.1.0") )]
+    pub fn next_cell(&mut self) -> Result<Option<SVMCell>,String> {
+        self.next_assembled(&mut Plain)
+    }
+
+    /// Decodes the next cell in the source, assembling it with `assemble`
+    /// as it is decoded.
+    fn next_assembled<A>(&mut self, assemble: &mut A)
+                        -> Result<Option<A::Cell>, String>
+    where A: Assemble {
+        // the elements decoded so far of each list we are inside,
+        // innermost last
+        let mut lists: Vec<A::Items> = Vec::new();
+        loop {
+            let byte = match self.read_byte()? {
+                Some(byte) => byte,
+                None if lists.is_empty() => return Ok(None), // EOF
+                None => return Err(String::from("EOF while decoding CONS cell"))
+            };
+            self.count_cells(1);
+            if byte == BYTE_CONS {
+                if lists.len() >= self.max_depth {
+                    return Err(format!("lists nested too deeply: the limit is {}",
+                                       self.max_depth));
+                }
+                lists.push(assemble.items());
+                continue;
+            }
+            let cell = self.decode_atom(byte)?;
+            debug!("Decoded {:?}, {} bytes read", cell, self.num_read);
+            let mut cell = assemble.cell(cell);
+            // add the cell to the innermost list, and close any lists
+            // which end after it
+            loop {
+                match lists.last_mut() {
+                    Some(items) => assemble.push(items, cell),
+                    None => return Ok(Some(cell))
+                };
+                match self.read_byte()? {
+                    Some(BYTE_CONS) => break,
+                    Some(BYTE_NIL)  => {
+                        let list = lists.pop().unwrap();
+                        cell = assemble.list(list, None);
+                    },
+                    // any other cell is the final cdr of an improper list
+                    Some(b) => {
+                        self.count_cells(1);
+                        let cdr = self.decode_atom(b)?;
+                        let cdr = assemble.cell(cdr);
+                        let list = lists.pop().unwrap();
+                        cell = assemble.list(list, Some(cdr));
+                    },
+                    None => return Err(String::from("EOF while decoding CONS"))
+                }
+            }
+        }
+    }
+
+    /// Decodes the next cell in the source, and interns it.
+    ///
+    /// Decoding several cells with the same `interner` shares any
+    /// structure they have in common, such as repeated constant lists.
+    /// Each list is interned as soon as it has been decoded, so a cell
+    /// which repeats structure already in the interner takes no more
+    /// memory than the nodes it adds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::bytecode::{Decoder, Encode};
+    /// # use seax_util::cell::Interner;
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Inst::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # use std::io::Cursor;
+    /// # fn main() {
+    /// let mut bytes = list_cell![InstCell(LDC), InstCell(NIL)].emit();
+    /// bytes.extend(list_cell![InstCell(LDF), InstCell(NIL)].emit());
+    ///
+    /// let mut interner = Interner::new();
+    /// let mut source = Cursor::new(bytes);
+    /// let mut decoder = Decoder::new(&mut source);
+    /// let a = decoder.next_interned(&mut interner).unwrap().unwrap();
+    /// let b = decoder.next_interned(&mut interner).unwrap().unwrap();
+    /// assert_eq!(a.cdr(), b.cdr());
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn next_interned(&mut self, interner: &mut Interner)
+                        -> Result<Option<Interned>, String> {
+        self.next_assembled(interner)
+    }
+
+}
+
+/// Decodes the contents of a constants section.
+fn decode_constants(bytes: &[u8], pool: DecodedPool, max_cells: usize,
+                    max_depth: usize) -> Result<Vec<SVMCell>, String> {
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.pool = pool;
+    decoder.max_cells = max_cells;
+    decoder.max_depth = max_depth;
+    let count = decoder.read_u32()?;
+    (0..count)
+        .map(|_| decoder.next_cell()
+                        .and_then(|cell| cell.ok_or(String::from(
+                            "EOF while decoding constants section"))) )
+        .collect()
+}
+
+/// Decodes the contents of a pool section.
+///
+/// Each entry is added to the pool as soon as it is decoded, so that
+/// later entries may refer to it.
+fn decode_pool(bytes: &[u8], max_cells: usize, max_depth: usize)
+              -> Result<DecodedPool, String> {
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.max_cells = max_cells;
+    decoder.max_depth = max_depth;
+    let count = decoder.read_u32()?;
+    for _ in 0..count {
+        let before = decoder.num_cells;
+        let entry = decoder.next_cell()?
+                           .ok_or(String::from("EOF while decoding pool section"))?;
+        decoder.pool.entries.push(entry);
+        decoder.pool.sizes.push(decoder.num_cells - before);
+    }
+    Ok(decoder.pool)
+}
+
+/// Decodes the contents of a metadata section.
+fn decode_metadata(bytes: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    let count = decoder.read_u16()?;
+    (0..count)
+        .map(|_| {
+            let key_len = decoder.read_u16()? as usize;
+            let key = decoder.read_vec(key_len, "metadata key")?;
+            let value_len = decoder.read_u32()? as usize;
+            let value = decoder.read_vec(value_len, "metadata value")?;
+            match (String::from_utf8(key), String::from_utf8(value)) {
+                (Ok(key), Ok(value)) => Ok((key, value)),
+                _ => Err(String::from("invalid UTF-8 in metadata section"))
+            }
+        })
+        .collect()
 }
 
 #[cfg_attr(feature = "unstable",
@@ -413,9 +1531,11 @@ where R: Read {
 
     #[cfg_attr(feature = "unstable",
         stable(feature = "decode", since="0.1.0") )]
+    /// Iteration stops at the end of the source, or at the first error.
+    /// Use `next_cell()` to find out which.
     fn next(&mut self) -> Option<SVMCell> {
         self.next_cell()
-            .unwrap()
+            .unwrap_or(None)
     }
 }
 #[cfg_attr(feature = "unstable",
@@ -433,6 +1553,517 @@ where R: fmt::Debug {
 
 }
 
+/// Encodes whole programs, including the preamble, to Seax bytecode.
+///
+/// An `Encoder` can emit files conforming to either Revision 0 or
+/// Revision 1 of the bytecode standard. Revision 0 files contain only
+/// code, so attempting to encode a `Program` with any other sections
+/// to Revision 0 is an error.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::bytecode::{Encoder, Program, REVISION_0, decode_program};
+/// # use seax_util::cell::SVMCell::*;
+/// # use seax_util::cell::Atom::*;
+/// # use seax_util::cell::Inst::*;
+/// # use seax_util::list::List::{Cons,Nil};
+/// # use std::io::Cursor;
+/// # fn main() {
+/// let code = list!(InstCell(LDC), AtomCell(UInt(1)));
+/// let mut program = Program::new(code.clone());
+/// program.metadata.push((String::from("name"), String::from("one")));
+///
+/// let bytes = Encoder::new().encode(&program).unwrap();
+/// assert_eq!(decode_program(&mut Cursor::new(bytes)), Ok(code.clone()));
+///
+/// // revision 0 files have nowhere to put the metadata
+/// assert!(Encoder::with_revision(REVISION_0).encode(&program).is_err());
+/// assert!(Encoder::with_revision(REVISION_0)
+///     .encode(&Program::new(code))
+///     .is_ok());
+/// # }
+/// ```
+#[derive(Clone,Debug)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+pub struct Encoder {
+    revision: u16,
+    checksum: bool,
+    #[cfg(feature = "compression")]
+    compress: bool,
+    pool: bool,
+    compact: bool,
+    max_depth: usize,
+    #[cfg(feature = "signing")]
+    signing_key: Option<SigningKey>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+impl Encoder {
+    /// Creates a new encoder for the current revision of the standard,
+    /// `REVISION_1`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn new() -> Encoder {
+        Encoder::with_revision(REVISION_1)
+    }
+
+    /// Creates a new encoder for the given revision of the standard.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn with_revision(revision: u16) -> Encoder {
+        Encoder {
+            revision,
+            checksum: false,
+            #[cfg(feature = "compression")]
+            compress: false,
+            pool: false,
+            compact: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            #[cfg(feature = "signing")]
+            signing_key: None
+        }
+    }
+
+    /// Sets the key used to sign encoded programs.
+    ///
+    /// Signatures are only supported by Revision 1 and later. Programs
+    /// are not signed unless a key is set.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::bytecode::{Encoder, Decoder, Program, SigningKey};
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Inst::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # use std::io::Cursor;
+    /// # fn main() {
+    /// let key = SigningKey::from_bytes(&[7; 32]);
+    /// let mut encoder = Encoder::new();
+    /// encoder.set_signing_key(key.clone());
+    /// let bytes = encoder.encode(&Program::new(list!(InstCell(NIL)))).unwrap();
+    ///
+    /// let mut cursor = Cursor::new(bytes);
+    /// let mut decoder = Decoder::new(&mut cursor);
+    /// decoder.set_trusted_keys(vec![key.verifying_key()]);
+    /// assert!(decoder.read_program().is_ok());
+    /// assert_eq!(decoder.signer(), Some(key.verifying_key().to_bytes()));
+    /// # }
+    /// ```
+    #[cfg(feature = "signing")]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "signing", issue = "94") )]
+    pub fn set_signing_key(&mut self, key: SigningKey) {
+        self.signing_key = Some(key);
+    }
+
+    #[cfg(feature = "signing")]
+    fn is_signing(&self) -> bool { self.signing_key.is_some() }
+
+    #[cfg(not(feature = "signing"))]
+    fn is_signing(&self) -> bool { false }
+
+    /// Computes the signature block for a file, if it is to be signed.
+    #[cfg(feature = "signing")]
+    fn signature(&self, header: &[u8], sections: &[(u8, Vec<u8>)])
+                -> Option<Vec<u8>> {
+        self.signing_key.as_ref().map(|key| {
+            let mut message = header.to_vec();
+            for (_, bytes) in sections {
+                message.extend_from_slice(bytes);
+            }
+            signature::sign(key, &message)
+        })
+    }
+
+    #[cfg(not(feature = "signing"))]
+    fn signature(&self, _: &[u8], _: &[(u8, Vec<u8>)]) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Sets whether to compress encoded programs.
+    ///
+    /// Compression is only supported by Revision 1 and later. Programs are
+    /// not compressed by default. Compressed programs are decompressed
+    /// transparently by `Decoder::read_program()` and `decode_program()`.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::bytecode::{Encoder, Program, decode_program};
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Inst::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # use std::io::Cursor;
+    /// # fn main() {
+    /// let program = Program::new(list!(
+    ///     InstCell(NIL), InstCell(NIL), InstCell(NIL), InstCell(NIL),
+    ///     InstCell(NIL), InstCell(NIL), InstCell(NIL), InstCell(NIL)
+    /// ));
+    /// let mut encoder = Encoder::new();
+    /// encoder.set_compression(true);
+    /// let bytes = encoder.encode(&program).unwrap();
+    /// assert_eq!(decode_program(&mut Cursor::new(bytes)), Ok(program.code));
+    /// # }
+    /// ```
+    #[cfg(feature = "compression")]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "compression", issue = "94") )]
+    pub fn set_compression(&mut self, compress: bool) {
+        self.compress = compress;
+    }
+
+    #[cfg(feature = "compression")]
+    fn is_compressing(&self) -> bool { self.compress }
+
+    #[cfg(not(feature = "compression"))]
+    fn is_compressing(&self) -> bool { false }
+
+    /// Compresses everything following the flags, if compression is on.
+    #[cfg(feature = "compression")]
+    fn compress(&self, body: Vec<u8>) -> Vec<u8> {
+        if self.compress { compression::compress(&body) } else { body }
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn compress(&self, body: Vec<u8>) -> Vec<u8> { body }
+
+    /// Sets whether to record a checksum in the preamble.
+    ///
+    /// Checksums allow decoders to detect corrupted files. They are only
+    /// supported by Revision 1 and later, and are not recorded by default.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "checksum", issue = "94") )]
+    pub fn set_checksum(&mut self, checksum: bool) {
+        self.checksum = checksum;
+    }
+
+    /// Sets whether to deduplicate constants using a constant pool.
+    ///
+    /// When enabled, atoms, byte strings and lists which occur several
+    /// times in a program's code are written once, to a pool section,
+    /// and referred to from everywhere else they occur. This never changes
+    /// the decoded program, but can make the encoded program considerably
+    /// smaller. Constant pools are only supported by Revision 1 and later,
+    /// and are not built by default.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::bytecode::{Encoder, Program, decode_program};
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Atom::*;
+    /// # use seax_util::cell::Inst::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # use std::io::Cursor;
+    /// # fn main() {
+    /// let body = list_cell![
+    ///     InstCell(LD), list_cell![ AtomCell(UInt(1)), AtomCell(UInt(1)) ],
+    ///     InstCell(LDC), AtomCell(UInt(1)), InstCell(ADD), InstCell(RET)
+    /// ];
+    /// let program = Program::new(list!(
+    ///     InstCell(LDF), body.clone(), InstCell(LDF), body.clone(),
+    ///     InstCell(LDF), body
+    /// ));
+    ///
+    /// let mut encoder = Encoder::new();
+    /// let plain = encoder.encode(&program).unwrap();
+    /// encoder.set_constant_pool(true);
+    /// let pooled = encoder.encode(&program).unwrap();
+    ///
+    /// assert!(pooled.len() < plain.len());
+    /// assert_eq!(decode_program(&mut Cursor::new(pooled)), Ok(program.code));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "pool", issue = "94") )]
+    pub fn set_constant_pool(&mut self, pool: bool) {
+        self.pool = pool;
+    }
+
+    /// Sets whether to use variable-length encodings for atoms.
+    ///
+    /// When enabled, integer and character atoms are encoded using the
+    /// variable-length encodings described in section III of the bytecode
+    /// standard, which take as little as two bytes for small values rather
+    /// than five or nine. Fixed-length encodings are used by default.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::bytecode::{Encoder, Program, decode_program};
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Atom::*;
+    /// # use seax_util::cell::Inst::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # use std::io::Cursor;
+    /// # fn main() {
+    /// let program = Program::new(list!(
+    ///     InstCell(LDC), AtomCell(UInt(1)), InstCell(LDC), AtomCell(SInt(-2)),
+    ///     InstCell(ADD)
+    /// ));
+    ///
+    /// let mut encoder = Encoder::new();
+    /// encoder.set_compact_atoms(true);
+    /// let bytes = encoder.encode(&program).unwrap();
+    /// assert_eq!(&bytes[13..], &[0x1C, 0xC7, 0x01, 0x1C, 0xC8, 0x03, 0x0A]);
+    /// assert_eq!(decode_program(&mut Cursor::new(bytes)), Ok(program.code));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "varint", issue = "94") )]
+    pub fn set_compact_atoms(&mut self, compact: bool) {
+        self.compact = compact;
+    }
+
+    /// Sets the maximum depth to which lists may be nested.
+    ///
+    /// Encoding a program containing lists nested more than `max` deep
+    /// fails, rather than producing a file which a `Decoder` with the same
+    /// limit would refuse to decode. The default is `DEFAULT_MAX_DEPTH`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "decode", issue = "94") )]
+    pub fn set_max_depth(&mut self, max: usize) {
+        self.max_depth = max;
+    }
+
+    /// Returns the revision of the standard this encoder emits.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn revision(&self) -> u16 {
+        self.revision
+    }
+
+    /// Encodes a program, including the preamble.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "sections", issue = "94") )]
+    pub fn encode(&self, program: &Program) -> Result<Vec<u8>, String> {
+        self.encode_with(program, None)
+    }
+
+    /// Encodes a module, including the preamble.
+    ///
+    /// Modules are only supported by Revision 1 and later.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "link", issue = "94") )]
+    pub fn encode_module(&self, module: &Module) -> Result<Vec<u8>, String> {
+        if self.revision == REVISION_0 {
+            return Err(String::from("revision 0 files may not contain modules"));
+        }
+        let (code, section) = link::emit_module(module)?;
+        self.encode_with(&Program::new(code), Some(section))
+    }
+
+    /// Encodes a program, and the contents of its module section if it is
+    /// a module.
+    fn encode_with(&self, program: &Program, module: Option<Vec<u8>>)
+                  -> Result<Vec<u8>, String> {
+        for cell in program.code.iter().chain(program.constants.iter()) {
+            check_cell(cell, self.max_depth)?;
+        }
+        let mut result = Vec::new();
+        result.write_u16::<BigEndian>(IDENT_BYTES).unwrap();
+        result.write_u16::<BigEndian>(self.revision).unwrap();
+        match self.revision {
+            REVISION_0 => {
+                if self.checksum {
+                    return Err(String::from(
+                        "revision 0 files may not contain a checksum"));
+                }
+                if self.is_signing() {
+                    return Err(String::from(
+                        "revision 0 files may not be signed"));
+                }
+                if self.pool {
+                    return Err(String::from(
+                        "revision 0 files may not contain a constant pool"));
+                }
+                if self.is_compressing() {
+                    return Err(String::from(
+                        "revision 0 files may not be compressed"));
+                }
+                if !program.constants.is_empty() ||
+                   !program.metadata.is_empty() ||
+                    program.debug.is_some() ||
+                    program.symbols.is_some() {
+                    return Err(String::from(
+                        "revision 0 files may only contain code"));
+                }
+                result.extend(emit_code(&program.code, self.compact));
+            },
+            REVISION_1 => {
+                let mut sections = self.emit_sections(program)?;
+                if let Some(bytes) = module {
+                    sections.push((SECTION_MODULE, bytes));
+                }
+                let mut flags = 0;
+                if self.checksum {
+                    flags |= FLAG_CHECKSUM;
+                }
+                if self.is_signing() {
+                    flags |= FLAG_SIGNED;
+                }
+                if self.is_compressing() {
+                    flags |= FLAG_COMPRESSED;
+                }
+                let mut header = Vec::new();
+                header.write_u16::<BigEndian>(flags).unwrap();
+                header.write_u16::<BigEndian>(sections.len() as u16)
+                      .unwrap();
+                for (kind, bytes) in &sections {
+                    header.push(*kind);
+                    write_len(&mut header, bytes.len(),
+                              || format!("section {:#04x} too long", kind))?;
+                }
+                // everything after the flags may be compressed
+                let mut body = header[2..].to_vec();
+                if self.checksum {
+                    let mut crc = Crc32::new();
+                    crc.update(&header);
+                    for (_, bytes) in &sections {
+                        crc.update(bytes);
+                    }
+                    body.write_u32::<BigEndian>(crc.finish()).unwrap();
+                }
+                if let Some(block) = self.signature(&header, &sections) {
+                    body.extend(block);
+                }
+                for (_, bytes) in sections {
+                    body.extend(bytes);
+                }
+                result.write_u16::<BigEndian>(flags).unwrap();
+                result.extend(self.compress(body));
+            },
+            other => return Err(format!("unsupported revision {}", other))
+        }
+        Ok(result)
+    }
+
+    /// Encodes each of a program's sections.
+    ///
+    /// Empty sections other than the code section are omitted.
+    fn emit_sections(&self, program: &Program)
+                    -> Result<Vec<(u8, Vec<u8>)>, String> {
+        let mut sections = Vec::new();
+        if self.pool {
+            let mut pool = Pool::new(&program.code, self.compact);
+            sections.push((SECTION_CODE, pool.emit_code(&program.code)));
+            if let Some(bytes) = pool.into_section()? {
+                sections.push((SECTION_POOL, bytes));
+            }
+        } else {
+            sections.push((SECTION_CODE,
+                           emit_code(&program.code, self.compact)));
+        }
+        if !program.constants.is_empty() {
+            let mut bytes = Vec::new();
+            write_len(&mut bytes, program.constants.len(),
+                      || String::from("too many constants"))?;
+            for constant in &program.constants {
+                bytes.extend(emit_cell(constant, self.compact));
+            }
+            sections.push((SECTION_CONSTANTS, bytes));
+        }
+        if !program.metadata.is_empty() {
+            if program.metadata.len() > u16::MAX as usize {
+                return Err(String::from("too many metadata entries"));
+            }
+            let mut bytes = Vec::new();
+            bytes.write_u16::<BigEndian>(program.metadata.len() as u16)
+                 .unwrap();
+            for (key, value) in &program.metadata {
+                if key.len() > u16::MAX as usize {
+                    return Err(format!("metadata key too long: {}", key));
+                }
+                bytes.write_u16::<BigEndian>(key.len() as u16).unwrap();
+                bytes.extend_from_slice(key.as_bytes());
+                write_len(&mut bytes, value.len(),
+                          || format!("metadata value too long: {}", key))?;
+                bytes.extend_from_slice(value.as_bytes());
+            }
+            sections.push((SECTION_METADATA, bytes));
+        }
+        if let Some(ref debug) = program.debug {
+            sections.push((SECTION_DEBUG, debug::emit(debug)?));
+        }
+        if let Some(ref symbols) = program.symbols {
+            sections.push((SECTION_SYMBOLS, symbols::emit(symbols)?));
+        }
+        Ok(sections)
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "sections", issue = "94") )]
+impl Default for Encoder {
+    fn default() -> Encoder { Encoder::new() }
+}
+
+/// Encodes each cell in a program's code in sequence.
+///
+/// This is the inverse of decoding cells until the end of the code.
+fn emit_code(code: &List<SVMCell>, compact: bool) -> Vec<u8> {
+    code.iter()
+        .flat_map(|cell| emit_cell(cell, compact))
+        .collect()
+}
+
+/// Checks that a cell can be encoded: that its lists are nested at most
+/// `max_depth` deep, its byte strings have 32-bit lengths, and its
+/// improper lists don't end in anything which would decode as the end of
+/// a proper list.
+///
+/// Empty lists are encoded as a single `NIL` byte, so they are not nested.
+fn check_cell(cell: &SVMCell, max_depth: usize) -> Result<(), String> {
+    let mut cells = vec![(cell, 0)];
+    while let Some((cell, depth)) = cells.pop() {
+        match *cell {
+            ListCell(ref list) if !list.is_empty() => {
+                cells.extend(list.iter().map(|cell| (cell, depth + 1)));
+            },
+            DottedCell(ref dotted) => {
+                match *dotted.last_cdr() {
+                    ListCell(_) | DottedCell(_) | InstCell(Inst::NIL) =>
+                        return Err(format!(
+                            "improper list {} ends in a list or NIL, which \
+                             would decode as a proper list", dotted)),
+                    _ => {}
+                }
+                cells.extend(dotted.items().iter().map(|cell| (cell, depth + 1)));
+                cells.push((dotted.last_cdr(), depth + 1));
+            },
+            BytesCell(ref bytes) if bytes.len() > u32::MAX as usize =>
+                return Err(format!("byte string too long: {} bytes, the limit is {}",
+                                   bytes.len(), u32::MAX)),
+            _ => continue
+        }
+        if depth + 1 > max_depth {
+            return Err(format!("lists nested too deeply: the limit is {}",
+                               max_depth));
+        }
+    }
+    Ok(())
+}
+
+/// Writes a 32-bit length or count, or returns the error made by `error`
+/// if `len` does not fit in 32 bits.
+fn write_len<F>(bytes: &mut Vec<u8>, len: usize, error: F) -> Result<(), String>
+where F: FnOnce() -> String {
+    if len > u32::MAX as usize {
+        return Err(error());
+    }
+    bytes.write_u32::<BigEndian>(len as u32).unwrap();
+    Ok(())
+}
+
+/// Encodes a cell, using variable-length atoms if `compact` is set.
+fn emit_cell(cell: &SVMCell, compact: bool) -> Vec<u8> {
+    if compact { cell.emit_compact() } else { cell.emit() }
+}
+
 /// Trait for an object that can be encoded to Seax bytecode.
 ///
 /// All types that can be encoded must implement this.
@@ -443,17 +2074,52 @@ pub trait Encode {
     #[cfg_attr(feature = "unstable",
         stable(feature = "encode", since="0.1.0") )]
     fn emit(&self) -> Vec<u8>;
+
+    /// Encodes this object to a list of bytes, using variable-length
+    /// encodings for any integer and character atoms it contains.
+    ///
+    /// By default, this is the same as `emit()`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "varint", issue = "94") )]
+    fn emit_compact(&self) -> Vec<u8> { self.emit() }
 }
 #[cfg_attr(feature = "unstable",
     stable(feature = "encode", since="0.1.0") )]
 impl Encode for SVMCell {
+    /// # Panics
+    ///
+    /// If the cell is a byte string of 4 GiB or more, since the lengths of
+    /// byte strings are encoded in 32 bits. `Encoder` checks for this, and
+    /// returns an error instead.
     #[cfg_attr(feature = "unstable",
         stable(feature = "encode", since="0.1.0") )]
     fn emit(&self) -> Vec<u8> {
         match *self {
             AtomCell(ref atom) => atom.emit(),
             InstCell(inst) => vec![inst as u8],
-            ListCell(ref list) => (*list).emit()
+            ListCell(ref list) => (*list).emit(),
+            BytesCell(ref bytes) => {
+                assert!(bytes.len() <= u32::MAX as usize,
+                    "byte string too long: {} bytes, the limit is {}",
+                    bytes.len(), u32::MAX);
+                let mut buf = vec![BYTE_BYTES];
+                buf.write_u32::<BigEndian>(bytes.len() as u32)
+                   .unwrap();
+                buf.extend_from_slice(bytes);
+                buf
+            },
+            DottedCell(ref dotted) => (*dotted).emit()
+        }
+    }
+
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "varint", issue = "94") )]
+    fn emit_compact(&self) -> Vec<u8> {
+        match *self {
+            AtomCell(ref atom) => atom.emit_compact(),
+            ListCell(ref list) => (*list).emit_compact(),
+            DottedCell(ref dotted) => (*dotted).emit_compact(),
+            _ => self.emit()
         }
     }
 }
@@ -491,6 +2157,20 @@ impl Encode for Atom {
             }
         }
     }
+
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "varint", issue = "94") )]
+    fn emit_compact(&self) -> Vec<u8> {
+        let (tag, value) = match *self {
+            UInt(value) => (BYTE_VARUINT, value),
+            SInt(value) => (BYTE_VARSINT, varint::zigzag(value)),
+            Char(value) => (BYTE_VARCHAR, value as u64),
+            Float(_)    => return self.emit()
+        };
+        let mut buf = vec![tag];
+        varint::write(&mut buf, value);
+        buf
+    }
 }
 
 // #[cfg_attr(feature = "unstable",
@@ -541,14 +2221,51 @@ where T: Encode {
     #[cfg_attr(feature = "unstable",
         stable(feature = "encode", since="0.1.0") )]
     fn emit(&self) -> Vec<u8> {
-        match *self {
-            Cons(ref it, ref tail) => {
-                let mut result = vec![BYTE_CONS];
-                push_all!(result, &it.emit());
-                push_all!(result, &(*tail.emit()));
-                result
-            },
-            Nil => vec![BYTE_NIL]
+        let mut result = Vec::new();
+        for it in self.iter() {
+            result.push(BYTE_CONS);
+            push_all!(result, &it.emit());
+        }
+        result.push(BYTE_NIL);
+        result
+    }
+
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "varint", issue = "94") )]
+    fn emit_compact(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        for it in self.iter() {
+            result.push(BYTE_CONS);
+            push_all!(result, &it.emit_compact());
+        }
+        result.push(BYTE_NIL);
+        result
+    }
+}
+
+/// Encodes an improper list like a `List`, except that its final cdr
+/// takes the place of the `NIL` byte.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "dotted", issue = "94") )]
+impl<T> Encode for DottedList<T>
+where T: Encode {
+    fn emit(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        for it in self.items().iter() {
+            result.push(BYTE_CONS);
+            push_all!(result, &it.emit());
+        }
+        push_all!(result, &self.last_cdr().emit());
+        result
+    }
+
+    fn emit_compact(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        for it in self.items().iter() {
+            result.push(BYTE_CONS);
+            push_all!(result, &it.emit_compact());
         }
+        push_all!(result, &self.last_cdr().emit_compact());
+        result
     }
 }
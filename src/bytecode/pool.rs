@@ -0,0 +1,246 @@
+//! Constant pool construction for deduplicated encoding.
+//!
+//! When an `Encoder` is asked to build a constant pool, every distinct
+//! atom, byte string, and list in the program is identified in a single
+//! bottom-up pass: atoms by their encoding, and lists by the identities of
+//! their items. Cells which occur often enough that sharing them saves
+//! space are written once, to the pool section, and every occurrence is
+//! replaced with a reference to the pool entry.
+
+use std::collections::HashMap;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use super::{emit_cell, write_len, BYTE_CONS, BYTE_NIL, BYTE_REF};
+use ::List;
+use ::cell::SVMCell;
+use ::cell::SVMCell::*;
+
+/// Length of a pool reference: the identifying byte and a 32-bit index.
+const REF_LEN: usize = 5;
+
+/// Identifies a distinct cell: an atom by its encoding, and a list by the
+/// ids of its items (and final cdr, if it is an improper list).
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    Atom(Vec<u8>),
+    List(Vec<usize>),
+    Dotted(Vec<usize>, usize)
+}
+
+/// A step in the traversal of a program's code.
+enum Visit<'a> {
+    /// Visits a cell, then everything within it.
+    Enter(&'a SVMCell),
+    /// Identifies a cell, once everything within it has been identified.
+    /// Holds its position in the traversal.
+    Exit(&'a SVMCell, usize)
+}
+
+/// A constant pool under construction.
+pub struct Pool {
+    /// The id of every cell in the code, in the order they are emitted if
+    /// nothing is shared.
+    ids: Vec<usize>,
+    /// The number of cells within each distinct cell, including itself,
+    /// by id.
+    sizes: Vec<usize>,
+    /// Whether each distinct cell is shared, by id.
+    shared: Vec<bool>,
+    /// Pool indices of the shared cells emitted so far, by id.
+    indices: HashMap<usize, u32>,
+    /// The position in `ids` of the next cell to be emitted.
+    next: usize,
+    /// Encoded pool entries, in index order.
+    entries: Vec<Vec<u8>>,
+    /// Whether atoms are encoded using variable-length encodings.
+    compact: bool
+}
+
+impl Pool {
+    /// Chooses which cells in a program's code should be shared.
+    ///
+    /// A cell is shared if the bytes saved by not repeating it outweigh
+    /// the cost of the references to it. Occurrences nested within other
+    /// shared cells are counted as well, so this is a heuristic: it never
+    /// affects the decoded program, but may occasionally share a cell that
+    /// ends up being emitted only once.
+    ///
+    /// Each cell is visited once, and its encoded length is computed from
+    /// those of its items, so this takes time linear in the size of the
+    /// code, however deeply it is nested.
+    pub fn new(code: &List<SVMCell>, compact: bool) -> Pool {
+        let mut keys = HashMap::new();
+        let mut ids = Vec::new();
+        let mut lens = Vec::new();
+        let mut sizes = Vec::new();
+        let mut counts = Vec::new();
+        // ids of the cells within the lists being visited
+        let mut items: Vec<usize> = Vec::new();
+        let mut visits = Vec::new();
+        enter(&mut visits, code.iter());
+        while let Some(visit) = visits.pop() {
+            match visit {
+                Visit::Enter(cell) => {
+                    visits.push(Visit::Exit(cell, ids.len()));
+                    ids.push(0);
+                    match *cell {
+                        ListCell(ref list) => enter(&mut visits, list.iter()),
+                        DottedCell(ref dotted) => {
+                            visits.push(Visit::Enter(dotted.last_cdr()));
+                            enter(&mut visits, dotted.items().iter());
+                        },
+                        _ => {}
+                    }
+                },
+                Visit::Exit(cell, position) => {
+                    let (key, len) = match *cell {
+                        ListCell(ref list) => {
+                            let start = items.len() - list.length();
+                            let list = items.split_off(start);
+                            let len = list_len(&list, &lens) + 1;
+                            (Key::List(list), len)
+                        },
+                        DottedCell(ref dotted) => {
+                            let cdr = items.pop().unwrap();
+                            let start = items.len() - dotted.length();
+                            let list = items.split_off(start);
+                            let len = list_len(&list, &lens) + lens[cdr];
+                            (Key::Dotted(list, cdr), len)
+                        },
+                        _ => {
+                            let bytes = emit_cell(cell, compact);
+                            let len = bytes.len();
+                            (Key::Atom(bytes), len)
+                        }
+                    };
+                    let size = ids.len() - position;
+                    let id = *keys.entry(key).or_insert_with(|| {
+                        lens.push(len);
+                        sizes.push(size);
+                        counts.push(0);
+                        lens.len() - 1
+                    });
+                    if let InstCell(_) = *cell {} else {
+                        counts[id] += 1;
+                    }
+                    ids[position] = id;
+                    items.push(id);
+                }
+            }
+        }
+        let shared = lens.iter().zip(counts)
+                         .map(|(&len, n)| worth_sharing(len, n))
+                         .collect();
+        Pool { ids, sizes, shared, indices: HashMap::new(), next: 0,
+               entries: Vec::new(), compact }
+    }
+
+    /// Encodes each cell in a program's code, replacing shared cells with
+    /// references into the pool.
+    ///
+    /// This must be given the same code the pool was built from.
+    pub fn emit_code(&mut self, code: &List<SVMCell>) -> Vec<u8> {
+        let mut result = Vec::new();
+        for cell in code.iter() {
+            result.extend(self.emit(cell));
+        }
+        debug_assert_eq!(self.next, self.ids.len());
+        result
+    }
+
+    /// Encodes the contents of the pool section.
+    ///
+    /// Returns `None` if no cells were shared, or an error if too many
+    /// were to be referred to by 32-bit indices.
+    pub fn into_section(self) -> Result<Option<Vec<u8>>, String> {
+        if self.entries.is_empty() {
+            return Ok(None);
+        }
+        let mut bytes = Vec::new();
+        write_len(&mut bytes, self.entries.len(),
+                  || String::from("too many constant pool entries"))?;
+        for entry in self.entries {
+            bytes.extend(entry);
+        }
+        Ok(Some(bytes))
+    }
+
+    /// Encodes a cell, or a reference to it if it is shared.
+    ///
+    /// A shared cell is added to the pool the first time it is emitted.
+    /// Since its children are emitted first, every entry in the pool only
+    /// refers to entries before it.
+    fn emit(&mut self, cell: &SVMCell) -> Vec<u8> {
+        let id = self.ids[self.next];
+        if !self.shared[id] {
+            self.next += 1;
+            return self.emit_inline(cell);
+        }
+        let index = match self.indices.get(&id) {
+            Some(&index) => {
+                // the cells within this one are not emitted again
+                self.next += self.sizes[id];
+                index
+            },
+            None => {
+                self.next += 1;
+                let entry = self.emit_inline(cell);
+                // this wraps if there are too many entries, in which case
+                // `into_section()` fails
+                let index = self.entries.len() as u32;
+                self.entries.push(entry);
+                self.indices.insert(id, index);
+                index
+            }
+        };
+        let mut result = vec![BYTE_REF];
+        result.write_u32::<BigEndian>(index).unwrap();
+        result
+    }
+
+    /// Encodes a cell itself, sharing its children where possible.
+    fn emit_inline(&mut self, cell: &SVMCell) -> Vec<u8> {
+        match *cell {
+            ListCell(ref list) => {
+                let mut result = Vec::new();
+                for item in list.iter() {
+                    result.push(BYTE_CONS);
+                    result.extend(self.emit(item));
+                }
+                result.push(BYTE_NIL);
+                result
+            },
+            DottedCell(ref dotted) => {
+                let mut result = Vec::new();
+                for item in dotted.items().iter() {
+                    result.push(BYTE_CONS);
+                    result.extend(self.emit(item));
+                }
+                result.extend(self.emit(dotted.last_cdr()));
+                result
+            },
+            _ => emit_cell(cell, self.compact)
+        }
+    }
+}
+
+/// Schedules visits to each of `cells`, so that they are visited in order.
+fn enter<'a, I>(visits: &mut Vec<Visit<'a>>, cells: I)
+where I: Iterator<Item = &'a SVMCell> {
+    let start = visits.len();
+    visits.extend(cells.map(Visit::Enter));
+    visits[start..].reverse();
+}
+
+/// Returns the encoded length of the items of a list, given their ids,
+/// not counting whatever ends the list.
+fn list_len(items: &[usize], lens: &[usize]) -> usize {
+    items.iter().map(|&id| 1 + lens[id]).sum()
+}
+
+/// Returns true if sharing a cell of `len` bytes which occurs `n` times
+/// makes the output smaller.
+fn worth_sharing(len: usize, n: usize) -> bool {
+    n > 1 && len * (n - 1) > REF_LEN * n
+}
@@ -0,0 +1,50 @@
+//! Ed25519 signature blocks for bytecode files.
+//!
+//! A signature block consists of the signer's 32-byte Ed25519 public key,
+//! followed by a 64-byte Ed25519 signature. Signing and verification are
+//! only available when the `signing` feature is enabled, but signed files
+//! may be decoded without it.
+
+#[cfg(feature = "signing")]
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+#[cfg(feature = "signing")]
+use super::DecodeError;
+
+/// Length of an Ed25519 public key, in bytes.
+pub const KEY_LEN: usize = 32;
+/// Length of a signature block, in bytes.
+pub const BLOCK_LEN: usize = KEY_LEN + 64;
+
+/// Signs a message, returning the signature block.
+#[cfg(feature = "signing")]
+pub fn sign(key: &SigningKey, message: &[u8]) -> Vec<u8> {
+    let mut block = Vec::with_capacity(BLOCK_LEN);
+    block.extend_from_slice(key.verifying_key().as_bytes());
+    block.extend_from_slice(&key.sign(message).to_bytes());
+    block
+}
+
+/// Verifies a signature block against a message.
+///
+/// The block's public key must be one of the `trusted` keys, and its
+/// signature must be a valid signature of `message` by that key.
+#[cfg(feature = "signing")]
+pub fn verify(block: &[u8], message: &[u8], trusted: &[VerifyingKey])
+             -> Result<(), DecodeError> {
+    let key = signer(block);
+    let trusted_key = trusted.iter()
+                             .find(|k| k.as_bytes() == &key)
+                             .ok_or(DecodeError::UntrustedKey(key))?;
+    let mut signature = [0; 64];
+    signature.copy_from_slice(&block[KEY_LEN..BLOCK_LEN]);
+    trusted_key.verify_strict(message, &Signature::from_bytes(&signature))
+               .map_err(|_| DecodeError::BadSignature)
+}
+
+/// Extracts the signer's public key from a signature block.
+pub fn signer(block: &[u8]) -> [u8; KEY_LEN] {
+    let mut key = [0; KEY_LEN];
+    key.copy_from_slice(&block[..KEY_LEN]);
+    key
+}
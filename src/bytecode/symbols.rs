@@ -0,0 +1,156 @@
+//! Names for the functions and environment bindings in a program.
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use super::{write_len, Decoder};
+use ::compiler_tools::{Index, Scope, SymTable};
+
+/// Names for the functions and environment bindings in a program, for use
+/// by disassemblers and debuggers.
+///
+/// Names are attached to cells by their position (see `DebugInfo` for how
+/// positions are numbered). A function's name is attached to the list cell
+/// containing its code, which follows an `LDF` instruction, and a binding's
+/// name is attached to each `LD` instruction that loads it. Since the same
+/// `(level . index)` pair may refer to different bindings in different
+/// functions, binding names are recorded for each `LD` rather than for each
+/// environment slot.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::bytecode::{Program, Symbols};
+/// # use seax_util::compiler_tools::{Scope, SymTable};
+/// # use seax_util::cell::SVMCell::*;
+/// # use seax_util::cell::Atom::*;
+/// # use seax_util::cell::Inst::*;
+/// # use seax_util::list::List::{Cons,Nil};
+/// # fn main() {
+/// let mut scope = SymTable::new();
+/// scope.bind("x", 1);
+///
+/// // compiling `(lambda (x) x)`
+/// let mut symbols = Symbols::new();
+/// let (lvl, idx) = symbols.lookup_binding(2, &scope, "x").unwrap();
+/// symbols.name_function(1, "id");
+/// let program = Program {
+///     symbols: Some(symbols),
+///     ..Program::new(list!(
+///         InstCell(LDF),
+///         list_cell![
+///             InstCell(LD), list_cell![ AtomCell(UInt(lvl)), AtomCell(UInt(idx)) ],
+///             InstCell(RET)
+///         ]
+///     ))
+/// };
+///
+/// let symbols = program.symbols.as_ref().unwrap();
+/// assert_eq!(symbols.function(1), Some("id"));
+/// assert_eq!(symbols.binding(2), Some("x"));
+/// # }
+/// ```
+#[derive(Clone,Debug,PartialEq,Default)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "symbols", issue = "94") )]
+pub struct Symbols {
+    functions: BTreeMap<u32, String>,
+    bindings: BTreeMap<u32, String>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "symbols", issue = "94") )]
+impl Symbols {
+    /// Creates a new `Symbols` with no names.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "symbols", issue = "94") )]
+    pub fn new() -> Symbols {
+        Symbols::default()
+    }
+
+    /// Names the function whose code is the list cell at `position`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "symbols", issue = "94") )]
+    pub fn name_function(&mut self, position: u32, name: &str) {
+        self.functions.insert(position, String::from(name));
+    }
+
+    /// Names the binding loaded by the `LD` instruction at `position`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "symbols", issue = "94") )]
+    pub fn name_binding(&mut self, position: u32, name: &str) {
+        self.bindings.insert(position, String::from(name));
+    }
+
+    /// Looks up a name in a symbol table, and if it is bound, names the
+    /// `LD` instruction at `position` after it.
+    ///
+    /// Returns the binding's index in the environment, or `None` if the
+    /// name is unbound, in which case nothing is recorded.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "symbols", issue = "94") )]
+    pub fn lookup_binding<'a>(&mut self, position: u32,
+                              table: &SymTable<'a>, name: &'a str)
+                              -> Option<Index> {
+        let index = table.lookup(&name);
+        if index.is_some() {
+            self.name_binding(position, name);
+        }
+        index
+    }
+
+    /// Returns the name of the function whose code is at `position`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "symbols", issue = "94") )]
+    pub fn function(&self, position: u32) -> Option<&str> {
+        self.functions.get(&position).map(|name| name.as_ref())
+    }
+
+    /// Returns the name of the binding loaded by the `LD` instruction at
+    /// `position`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "symbols", issue = "94") )]
+    pub fn binding(&self, position: u32) -> Option<&str> {
+        self.bindings.get(&position).map(|name| name.as_ref())
+    }
+}
+
+/// Encodes the contents of a symbols section.
+pub fn emit(symbols: &Symbols) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for table in &[&symbols.functions, &symbols.bindings] {
+        write_len(&mut bytes, table.len(), || String::from("too many symbols"))?;
+        for (&position, name) in table.iter() {
+            if name.len() > u16::MAX as usize {
+                return Err(format!("symbol too long: {}", name));
+            }
+            bytes.write_u32::<BigEndian>(position).unwrap();
+            bytes.write_u16::<BigEndian>(name.len() as u16).unwrap();
+            bytes.extend_from_slice(name.as_bytes());
+        }
+    }
+    Ok(bytes)
+}
+
+/// Decodes the contents of a symbols section.
+pub fn decode(bytes: &[u8]) -> Result<Symbols, String> {
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    let mut symbols = Symbols::new();
+    for table in &mut [&mut symbols.functions, &mut symbols.bindings] {
+        for _ in 0..decoder.read_u32()? {
+            let position = decoder.read_u32()?;
+            let len = decoder.read_u16()? as usize;
+            let name = decoder.read_vec(len, "symbol")?;
+            let name = String::from_utf8(name).map_err(|_|
+                String::from("invalid UTF-8 in symbols section"))?;
+            if table.insert(position, name).is_some() {
+                return Err(format!("duplicate symbol at position {}",
+                    position));
+            }
+        }
+    }
+    Ok(symbols)
+}
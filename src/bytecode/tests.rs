@@ -1,13 +1,19 @@
-use super::{Encode,Decoder};
-use ::cell::{Atom,Inst,SVMCell};
+use super::{Encode,Decoder,Encoder,Program,DecodeError,DebugInfo,Span,
+            Symbols,Module,Definition,LinkError,LazyDecoder,CellView,
+            REVISION_0,REVISION_1,decode_everything};
+use super::crc32::Crc32;
+use ::cell::{Atom,Inst,Interner,SVMCell};
 use ::cell::Atom::*;
 use ::cell::SVMCell::*;
 use ::Inst::*;
 use ::List::{Cons,Nil};
+use ::list::DottedList;
 
 use std::io::Cursor;
 
-use quickcheck::quickcheck;
+use quickcheck::{quickcheck, Arbitrary, Gen};
+
+use ::link;
 
 #[cfg(not(feature = "nightly"))]
 macro_rules! push_all {
@@ -41,7 +47,11 @@ fn prop_encode_float () {
         let cell = SVMCell::AtomCell(Atom::Float(x));
         let encoded = cell.emit();
         let decoded = Decoder::new(&mut Cursor::new(encoded)).next_cell();
-        decoded == Ok(Some(cell))
+        // compare bit patterns, since NaN != NaN
+        match decoded {
+            Ok(Some(AtomCell(Float(y)))) => x.to_bits() == y.to_bits(),
+            _ => false
+        }
     }
     quickcheck(prop as fn(f64) -> bool);
 }
@@ -78,6 +88,146 @@ fn prop_encode_char () {
     }
     quickcheck(prop as fn(char) -> bool);
 }
+#[test]
+fn prop_encode_bytes () {
+    fn prop (x: Vec<u8>) -> bool {
+        let cell = SVMCell::BytesCell(x);
+        let encoded = cell.emit();
+        let decoded = Decoder::new(&mut Cursor::new(encoded)).next_cell();
+        decoded == Ok(Some(cell))
+    }
+    quickcheck(prop as fn(Vec<u8>) -> bool);
+}
+
+fn decode_compact(cell: &SVMCell) -> Result<Option<SVMCell>, String> {
+    Decoder::new(&mut Cursor::new(cell.emit_compact())).next_cell()
+}
+
+#[test]
+fn prop_encode_compact_uint () {
+    fn prop (x: u64) -> bool {
+        let cell = SVMCell::AtomCell(Atom::UInt(x));
+        decode_compact(&cell) == Ok(Some(cell))
+    }
+    quickcheck(prop as fn(u64) -> bool);
+}
+
+#[test]
+fn prop_encode_compact_sint () {
+    fn prop (x: i64) -> bool {
+        let cell = SVMCell::AtomCell(Atom::SInt(x));
+        decode_compact(&cell) == Ok(Some(cell))
+    }
+    quickcheck(prop as fn(i64) -> bool);
+}
+
+#[test]
+fn prop_encode_compact_char () {
+    fn prop (x: char) -> bool {
+        let cell = SVMCell::AtomCell(Atom::Char(x));
+        decode_compact(&cell) == Ok(Some(cell))
+    }
+    quickcheck(prop as fn(char) -> bool);
+}
+
+#[test]
+fn prop_encode_compact_small_uint () {
+    // small values should always fit in a single byte after the tag
+    fn prop (x: u8) -> bool {
+        let cell = SVMCell::AtomCell(Atom::UInt((x & 0x7F) as u64));
+        cell.emit_compact().len() == 2
+    }
+    quickcheck(prop as fn(u8) -> bool);
+}
+
+#[test]
+fn test_encode_compact_boundaries () {
+    let cases = vec![
+        (UInt(0), vec![0xC7, 0x00]),
+        (UInt(127), vec![0xC7, 0x7F]),
+        (UInt(128), vec![0xC7, 0x80, 0x01]),
+        (UInt(16384), vec![0xC7, 0x80, 0x80, 0x01]),
+        (UInt(u64::MAX), vec![0xC7, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                                    0xFF, 0xFF, 0xFF, 0xFF, 0x01]),
+        (SInt(0), vec![0xC8, 0x00]),
+        (SInt(-1), vec![0xC8, 0x01]),
+        (SInt(1), vec![0xC8, 0x02]),
+        (SInt(-64), vec![0xC8, 0x7F]),
+        (SInt(64), vec![0xC8, 0x80, 0x01]),
+        (SInt(i64::MAX), vec![0xC8, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF,
+                                    0xFF, 0xFF, 0xFF, 0xFF, 0x01]),
+        (SInt(i64::MIN), vec![0xC8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                                    0xFF, 0xFF, 0xFF, 0xFF, 0x01]),
+        (Char('a'), vec![0xC9, 0x61]),
+        (Char('\u{10FFFF}'), vec![0xC9, 0xFF, 0xFF, 0x43]),
+        (Float(1.5), Float(1.5).emit())
+    ];
+    for (atom, bytes) in cases {
+        assert_eq!(bytes, atom.emit_compact());
+        let cell = AtomCell(atom);
+        assert_eq!(Ok(Some(cell.clone())), decode_compact(&cell));
+    }
+}
+
+#[test]
+fn test_decode_compact_invalid () {
+    fn decode(bytes: Vec<u8>) -> Result<Option<SVMCell>, String> {
+        Decoder::new(&mut Cursor::new(bytes)).next_cell()
+    }
+    assert_eq!(Err(String::from("EOF while decoding varint")),
+               decode(vec![0xC7, 0x80]));
+    assert_eq!(Err(String::from("varint too large")),
+               decode(vec![0xC7, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                                 0xFF, 0xFF, 0xFF, 0xFF, 0x02]));
+    assert_eq!(Err(String::from("varint too large")),
+               decode(vec![0xC7, 0x80, 0x80, 0x80, 0x80, 0x80,
+                                 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]));
+    // surrogates aren't Unicode scalar values
+    assert_eq!(Err(String::from("Could not read character.")),
+               decode(vec![0xC9, 0x80, 0xB0, 0x03]));
+}
+
+#[test]
+fn test_encode_compact_program () {
+    let program = Program::new(fib_program());
+    let mut encoder = Encoder::new();
+    encoder.set_compact_atoms(true);
+    let compact = encoder.encode(&program).unwrap();
+    assert!(compact.len() < Encoder::new().encode(&program).unwrap().len());
+    assert_eq!(
+        Ok(program.clone()),
+        Decoder::new(&mut Cursor::new(compact)).read_program()
+    );
+    // compact atoms can be combined with a constant pool
+    encoder.set_constant_pool(true);
+    let pooled = encoder.encode(&program).unwrap();
+    assert_eq!(
+        Ok(program),
+        Decoder::new(&mut Cursor::new(pooled)).read_program()
+    );
+}
+
+#[test]
+fn test_encode_bytes_is_compact () {
+    let bytes = vec![0xAB; 100];
+    let as_list = bytes.iter()
+                       .map(|&b| AtomCell(UInt(b as u64)))
+                       .collect::<::List<SVMCell>>();
+    assert_eq!(SVMCell::BytesCell(bytes).emit().len(), 105);
+    assert!(ListCell(Box::new(as_list)).emit().len() > 900);
+}
+
+#[test]
+fn test_decode_bytes_truncated () {
+    let encoded = vec![0xC5, 0x00, 0x00, 0x00, 0x04, 0xDE, 0xAD];
+    let decoded = Decoder::new(&mut Cursor::new(encoded)).next_cell();
+    assert_eq!(
+        Err(String::from(
+            "EOF while decoding byte string: expected 4 bytes, got 2")),
+        decoded
+    )
+}
+
 #[test]
 fn test_decode_program () {
     let cell = list_cell![
@@ -135,6 +285,1389 @@ fn test_decode_program_wrong_version () {
     )
 }
 
+#[test]
+fn test_version_constants () {
+    // `VERSION` predates revision 1, and still names revision 0
+    assert_eq!(super::VERSION, REVISION_0);
+    assert_eq!(Encoder::new().revision(), REVISION_1);
+    let mut source = Cursor::new(vec![0x00, 0x02]);
+    assert_eq!(Decoder::new(&mut source).check_version(),
+               Err(String::from("mismatched version 2, expected 0 or 1")));
+}
+
+fn fib_program() -> ::List<SVMCell> {
+    list!(
+        InstCell(NIL), InstCell(LDC), AtomCell(SInt(10)), InstCell(CONS),
+        InstCell(LDF),
+        list_cell![
+            InstCell(LD), list_cell![ AtomCell(UInt(1)), AtomCell(UInt(1)) ],
+            InstCell(LDC), AtomCell(SInt(2)),
+            InstCell(LT),
+            InstCell(SEL),
+            list_cell![ InstCell(LD),
+                        list_cell![ AtomCell(UInt(1)), AtomCell(UInt(1)) ],
+                        InstCell(JOIN) ],
+            list_cell![ InstCell(NIL), InstCell(JOIN) ],
+            InstCell(RET)
+        ],
+        InstCell(AP)
+    )
+}
+
+/// Debugging information for `fib_program()`, as though it had been
+/// compiled from `((lambda (n) (if (< n 2) n nil)) 10)`.
+fn fib_debug_info() -> DebugInfo {
+    fn span(file: u32, column: u32, end_column: u32) -> Span {
+        Span { file, line: 1, column, end_line: 1, end_column }
+    }
+    let mut debug = DebugInfo::new();
+    let file = debug.add_file("fib.scm");
+    debug.insert(0, span(file, 1, 44));   // NIL
+    debug.insert(1, span(file, 42, 43));  // LDC 10
+    debug.insert(4, span(file, 2, 40));   // LDF
+    debug.insert(6, span(file, 31, 31));  // LD n
+    debug.insert(10, span(file, 21, 27)); // LDC 2
+    debug.insert(12, span(file, 18, 39)); // LT
+    debug.insert(24, span(file, 1, 44));  // AP
+    debug
+}
+
+#[test]
+fn test_encode_revision_0 () {
+    let code = fib_program();
+    let encoded = Encoder::with_revision(REVISION_0)
+        .encode(&Program::new(code.clone()))
+        .unwrap();
+    let mut expected = vec![0x5e, 0xcd, 0x00, 0x00];
+    for cell in code.iter() {
+        push_all!(expected, &cell.emit());
+    }
+    assert_eq!(expected, encoded);
+}
+
+#[test]
+fn test_encode_revision_0_rejects_sections () {
+    let mut program = Program::new(fib_program());
+    program.debug = Some(DebugInfo::new());
+    assert_eq!(
+        Err(String::from("revision 0 files may only contain code")),
+        Encoder::with_revision(REVISION_0).encode(&program)
+    )
+}
+
+#[test]
+fn test_encode_revision_1_roundtrip () {
+    let mut program = Program::new(fib_program());
+    program.constants = vec![ AtomCell(Float(1.5)), BytesCell(vec![1, 2]) ];
+    program.metadata = vec![
+        (String::from("author"), String::from("Hawk Weisman")),
+        (String::from("source"), String::from("fib.scm"))
+    ];
+    program.debug = Some(fib_debug_info());
+    let encoded = Encoder::with_revision(REVISION_1)
+        .encode(&program)
+        .unwrap();
+    assert_eq!(&encoded[..4], &[0x5e, 0xcd, 0x00, 0x01]);
+    let decoded = Decoder::new(&mut Cursor::new(encoded)).read_program();
+    assert_eq!(Ok(program), decoded);
+}
+
+#[test]
+fn test_decode_program_revision_1 () {
+    let encoded = Encoder::new()
+        .encode(&Program::new(fib_program()))
+        .unwrap();
+    assert_eq!(
+        Ok(fib_program()),
+        super::decode_program(&mut Cursor::new(encoded))
+    )
+}
+
+#[test]
+fn test_decode_revision_1_skips_unknown_section () {
+    let code = list!(InstCell(LDC), AtomCell(UInt(1)));
+    let mut encoded = vec![
+        0x5e, 0xcd, 0x00, 0x01, // preamble
+        0x00, 0x00,             // flags
+        0x00, 0x02,             // two sections
+        0x7F, 0x00, 0x00, 0x00, 0x03,
+        0x01, 0x00, 0x00, 0x00, 0x0A
+    ];
+    push_all!(encoded, &[0xAA, 0xBB, 0xCC]);
+    push_all!(encoded, &[0x1C, 0xC1, 0, 0, 0, 0, 0, 0, 0, 0x01]);
+    assert_eq!(
+        Ok(code),
+        super::decode_program(&mut Cursor::new(encoded))
+    )
+}
+
+#[test]
+fn test_decode_revision_1_errors () {
+    fn decode(bytes: Vec<u8>) -> Result<::List<SVMCell>, String> {
+        super::decode_program(&mut Cursor::new(bytes))
+    }
+    assert_eq!(
+        Err(String::from("unsupported flags 0x8000")),
+        decode(vec![0x5e, 0xcd, 0x00, 0x01, 0x80, 0x00, 0x00, 0x00])
+    );
+    assert_eq!(
+        Err(String::from("missing code section")),
+        decode(vec![0x5e, 0xcd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00])
+    );
+    assert_eq!(
+        Err(String::from("EOF while decoding section: expected 16 bytes, got 1")),
+        decode(vec![0x5e, 0xcd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+                    0x01, 0x00, 0x00, 0x00, 0x10, 0x00])
+    );
+}
+
+#[test]
+fn test_decode_revision_0_propagates_errors () {
+    assert_eq!(
+        Err(String::from("Unsupported byte 0xff")),
+        super::decode_program(&mut Cursor::new(vec![0x5e, 0xcd, 0x00, 0x00, 0xFF]))
+    )
+}
+
+#[test]
+fn test_crc32_check_value () {
+    // the standard check value for CRC-32
+    let mut crc = Crc32::new();
+    crc.update(b"123456789");
+    assert_eq!(crc.finish(), 0xCBF43926);
+
+    let mut crc = Crc32::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finish(), 0xCBF43926);
+}
+
+fn encode_with_checksum(program: &Program) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    encoder.set_checksum(true);
+    encoder.encode(program).unwrap()
+}
+
+#[test]
+fn test_checksum_roundtrip () {
+    let mut program = Program::new(fib_program());
+    program.metadata.push((String::from("k"), String::from("v")));
+    let encoded = encode_with_checksum(&program);
+    assert_eq!(&encoded[4..6], &[0x00, 0x01]); // checksum flag
+    assert_eq!(
+        Ok(program),
+        Decoder::new(&mut Cursor::new(encoded)).read_program()
+    );
+}
+
+#[test]
+fn test_checksum_mismatch () {
+    let program = Program::new(list!(InstCell(LDC), AtomCell(UInt(7))));
+    let mut encoded = encode_with_checksum(&program);
+    let last = encoded.len() - 1;
+    encoded[last] = 8; // corrupt the constant
+    match Decoder::new(&mut Cursor::new(encoded.clone())).read_program() {
+        Err(DecodeError::ChecksumMismatch { expected, actual }) =>
+            assert!(expected != actual),
+        other => panic!("expected checksum mismatch, got {:?}", other)
+    };
+    assert!(super::decode_program(&mut Cursor::new(encoded.clone()))
+                .unwrap_err()
+                .starts_with("checksum mismatch"));
+
+    // if verification is skipped, the corrupted file decodes
+    let mut cursor = Cursor::new(encoded);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_verify_checksum(false);
+    assert_eq!(
+        Ok(list!(InstCell(LDC), AtomCell(UInt(8)))),
+        decoder.read_program().map(|p| p.code)
+    );
+}
+
+#[test]
+fn test_checksum_covers_section_table () {
+    let program = Program::new(list!(InstCell(NIL), InstCell(NIL)));
+    let mut encoded = encode_with_checksum(&program);
+    // change the kind of the only section from code to something unknown
+    encoded[8] = 0x7F;
+    match Decoder::new(&mut Cursor::new(encoded)).read_program() {
+        Err(DecodeError::ChecksumMismatch { .. }) => {},
+        other => panic!("expected checksum mismatch, got {:?}", other)
+    };
+}
+
+#[test]
+fn test_checksum_revision_0 () {
+    let mut encoder = Encoder::with_revision(REVISION_0);
+    encoder.set_checksum(true);
+    assert_eq!(
+        Err(String::from("revision 0 files may not contain a checksum")),
+        encoder.encode(&Program::new(fib_program()))
+    )
+}
+
+/// A program resembling compiler output, with `n` closures whose bodies
+/// share common subexpressions.
+fn closures_program(n: u64) -> ::List<SVMCell> {
+    let mut code = list!(InstCell(DUM), InstCell(NIL));
+    for i in 0..n {
+        code.append(InstCell(LDF));
+        code.append(list_cell![
+            InstCell(LD), list_cell![ AtomCell(UInt(1)), AtomCell(UInt(1)) ],
+            InstCell(NULL),
+            InstCell(SEL),
+            list_cell![ InstCell(NIL), InstCell(JOIN) ],
+            list_cell![ InstCell(LD), list_cell![ AtomCell(UInt(1)), AtomCell(UInt(1)) ],
+                        InstCell(CDR),
+                        InstCell(LDC), AtomCell(UInt(i)),
+                        InstCell(CONS), InstCell(JOIN) ],
+            InstCell(RET)
+        ]);
+        code.append(InstCell(CONS));
+    }
+    code.append(InstCell(RAP));
+    code
+}
+
+fn encode_with_pool(program: &Program) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    encoder.set_constant_pool(true);
+    encoder.encode(program).unwrap()
+}
+
+#[test]
+fn test_pool_roundtrip () {
+    for program in vec![ Program::new(fib_program())
+                       , Program::new(closures_program(10))
+                       , Program::new(list!(InstCell(NIL)))
+                       ] {
+        assert_eq!(
+            Ok(program.clone()),
+            Decoder::new(&mut Cursor::new(encode_with_pool(&program)))
+                .read_program()
+        );
+    }
+}
+
+#[test]
+fn test_pool_reduces_size () {
+    let program = Program::new(closures_program(50));
+    let plain = Encoder::new().encode(&program).unwrap();
+    let pooled = encode_with_pool(&program);
+    assert!(pooled.len() * 3 < plain.len() * 2,
+        "pooled size {} not less than 2/3 of {}", pooled.len(), plain.len());
+}
+
+#[test]
+fn test_pool_omitted_when_useless () {
+    let program = Program::new(list!(
+        InstCell(LDC), AtomCell(UInt(1)), InstCell(LDC), AtomCell(UInt(2)),
+        InstCell(LDF), list_cell![ InstCell(NIL), InstCell(RET) ],
+        InstCell(LDF), list_cell![ InstCell(NIL), InstCell(RET) ]
+    ));
+    assert_eq!(Encoder::new().encode(&program), Ok(encode_with_pool(&program)));
+}
+
+#[test]
+fn test_pool_shares_nested_entries () {
+    let pair = list_cell![ AtomCell(UInt(1)), AtomCell(UInt(2)) ];
+    let outer = list_cell![ pair.clone(), pair.clone(), pair.clone() ];
+    let program = Program::new(list!(outer.clone(), outer.clone(), outer));
+    let encoded = encode_with_pool(&program);
+    assert_eq!(&encoded[6..8], &[0x00, 0x02]); // code and pool sections
+    // the code is three references to the outer list, which comes after
+    // the two atoms and the pair in the pool
+    assert_eq!(&encoded[8..13], &[0x01, 0x00, 0x00, 0x00, 15]);
+    assert_eq!(&encoded[18..33], &[ 0xC6, 0x00, 0x00, 0x00, 0x03
+                                  , 0xC6, 0x00, 0x00, 0x00, 0x03
+                                  , 0xC6, 0x00, 0x00, 0x00, 0x03 ]);
+    assert_eq!(&encoded[33..37], &[0x00, 0x00, 0x00, 0x04]);
+    // the pair refers to the atoms
+    assert_eq!(&encoded[55..66], &[ 0xC0, 0xC6, 0x00, 0x00, 0x00, 0x00
+                                  , 0xC0, 0xC6, 0x00, 0x00, 0x00 ]);
+    assert_eq!(
+        Ok(program),
+        Decoder::new(&mut Cursor::new(encoded)).read_program()
+    );
+}
+
+#[test]
+fn test_pool_deeply_nested () {
+    let mut cell = list_cell![ AtomCell(UInt(1)), AtomCell(UInt(2)) ];
+    for _ in 1..1000 {
+        cell = list_cell![ InstCell(LDF), cell ];
+    }
+    let program = Program::new(list!(cell.clone(), cell));
+    let pooled = encode_with_pool(&program);
+    let decoded = Decoder::new(&mut Cursor::new(pooled)).read_program();
+    assert!(decoded == Ok(program));
+}
+
+#[test]
+fn test_pool_invalid_references () {
+    // a reference with no pool
+    let no_pool = vec![ 0x5E, 0xCD, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01
+                      , 0x01, 0x00, 0x00, 0x00, 0x05
+                      , 0xC6, 0x00, 0x00, 0x00, 0x00 ];
+    assert_eq!(
+        Err(String::from("invalid constant pool reference 0")),
+        super::decode_program(&mut Cursor::new(no_pool))
+    );
+    // a pool entry referring to itself
+    let cyclic = vec![ 0x5E, 0xCD, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02
+                     , 0x01, 0x00, 0x00, 0x00, 0x05
+                     , 0x05, 0x00, 0x00, 0x00, 0x0B
+                     , 0xC6, 0x00, 0x00, 0x00, 0x00
+                     , 0x00, 0x00, 0x00, 0x01
+                     , 0xC0, 0xC6, 0x00, 0x00, 0x00, 0x00, 0x00 ];
+    assert_eq!(
+        Err(String::from("invalid constant pool reference 0")),
+        super::decode_program(&mut Cursor::new(cyclic))
+    );
+}
+
+#[test]
+fn test_pool_revision_0 () {
+    let mut encoder = Encoder::with_revision(REVISION_0);
+    encoder.set_constant_pool(true);
+    assert_eq!(
+        Err(String::from("revision 0 files may not contain a constant pool")),
+        encoder.encode(&Program::new(fib_program()))
+    );
+}
+
+#[test]
+fn test_debug_info_lookup () {
+    let program = Program {
+        debug: Some(fib_debug_info()),
+        ..Program::new(fib_program())
+    };
+    assert_eq!(Some(1), program.span(0).map(|span| span.column));
+    // cells without a span inherit the one before them
+    assert_eq!(Some(42), program.span(3).map(|span| span.column));
+    assert_eq!(Some(31), program.span(6).map(|span| span.column));
+    assert_eq!(Some(31), program.span(9).map(|span| span.column));
+    assert_eq!(Some(18), program.span(23).map(|span| span.column));
+    assert_eq!(Some(1), program.span(24).map(|span| span.column));
+    let debug = program.debug.as_ref().unwrap();
+    assert_eq!(Some("fib.scm"), debug.file_name(program.span(24).unwrap()));
+    assert_eq!(None, Program::new(fib_program()).span(0));
+    assert_eq!(None, DebugInfo::new().lookup(0));
+}
+
+#[test]
+fn test_debug_info_insert () {
+    let mut debug = DebugInfo::new();
+    let a = debug.add_file("a.scm");
+    let b = debug.add_file("b.scm");
+    assert_eq!(a, debug.add_file("a.scm"));
+    let span = Span { file: a, line: 1, column: 1, end_line: 1, end_column: 1 };
+    debug.insert(5, span);
+    debug.insert(2, span);
+    debug.insert(5, Span { file: b, ..span });
+    assert_eq!(&[(2, span), (5, Span { file: b, ..span })], debug.spans());
+    assert_eq!(None, debug.lookup(1));
+    assert_eq!(Some(&span), debug.lookup(4));
+}
+
+#[test]
+fn test_debug_info_roundtrip () {
+    let program = Program {
+        debug: Some(fib_debug_info()),
+        ..Program::new(fib_program())
+    };
+    // positions don't depend on the encoding
+    let mut encoder = Encoder::new();
+    for &(pool, compact) in &[(false, false), (true, false), (true, true)] {
+        encoder.set_constant_pool(pool);
+        encoder.set_compact_atoms(compact);
+        let encoded = encoder.encode(&program).unwrap();
+        let decoded = Decoder::new(&mut Cursor::new(encoded)).read_program();
+        assert_eq!(Ok(program.clone()), decoded);
+    }
+}
+
+#[test]
+fn test_debug_info_errors () {
+    let mut debug = DebugInfo::new();
+    debug.insert(0, Span { file: 1, line: 1, column: 1, end_line: 1, end_column: 1 });
+    let program = Program { debug: Some(debug), ..Program::new(list!(InstCell(NIL))) };
+    assert_eq!(
+        Err(String::from("span at position 0 refers to unknown file 1")),
+        Encoder::new().encode(&program)
+    );
+
+    fn decode_debug(section: Vec<u8>) -> Result<::List<SVMCell>, String> {
+        let mut encoded = vec![ 0x5E, 0xCD, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02
+                              , 0x01, 0x00, 0x00, 0x00, 0x01
+                              , 0x04, 0x00, 0x00, 0x00, section.len() as u8
+                              , 0x00 ];
+        encoded.extend(section);
+        super::decode_program(&mut Cursor::new(encoded))
+    }
+    let span = |pos: u8, file: u8| vec![ 0, 0, 0, pos, 0, 0, 0, file
+                                       , 0, 0, 0, 1, 0, 0, 0, 1
+                                       , 0, 0, 0, 1, 0, 0, 0, 1 ];
+    let mut section = vec![0x00, 0x01, 0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x02];
+    section.extend(span(3, 0));
+    section.extend(span(1, 0));
+    assert_eq!(Err(String::from("debug spans out of order")),
+               decode_debug(section));
+    let mut section = vec![0x00, 0x01, 0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x01];
+    section.extend(span(0, 1));
+    assert_eq!(Err(String::from("span at position 0 refers to unknown file 1")),
+               decode_debug(section));
+    let mut section = vec![0x00, 0x01, 0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x01];
+    section.extend(span(0, 0));
+    assert_eq!(Ok(list!(InstCell(NIL))), decode_debug(section));
+}
+
+fn fib_symbols() -> Symbols {
+    let mut symbols = Symbols::new();
+    symbols.name_function(5, "fib");
+    symbols.name_binding(6, "n");
+    symbols.name_binding(15, "n");
+    symbols
+}
+
+/// Disassembles a program, one instruction per line, using names from
+/// the symbols section where there are any.
+fn disassemble(program: &Program) -> Vec<String> {
+    let symbols = program.symbols.clone().unwrap_or_default();
+    let cells: Vec<_> = program.cells().collect();
+    let mut lines = Vec::new();
+    for (i, &(position, cell)) in cells.iter().enumerate() {
+        if let InstCell(inst) = *cell {
+            let line = match (inst, cells.get(i + 1)) {
+                (LD, _) if symbols.binding(position).is_some() =>
+                    format!("LD {}", symbols.binding(position).unwrap()),
+                (LDF, Some(&(body, _))) if symbols.function(body).is_some() =>
+                    format!("LDF <{}>", symbols.function(body).unwrap()),
+                _ => format!("{:?}", inst)
+            };
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+#[test]
+fn test_program_cells () {
+    let program = Program::new(fib_program());
+    let cells: Vec<_> = program.cells().collect();
+    assert_eq!(25, cells.len());
+    for (i, &(position, _)) in cells.iter().enumerate() {
+        assert_eq!(i as u32, position);
+    }
+    assert_eq!(&InstCell(LDF), cells[4].1);
+    assert_eq!(&InstCell(LD), cells[6].1);
+    assert_eq!(&AtomCell(UInt(1)), cells[8].1);
+    assert_eq!(&InstCell(LD), cells[15].1);
+    assert_eq!(&InstCell(AP), cells[24].1);
+    assert_eq!(0, Program::new(::List::new()).cells().count());
+}
+
+#[test]
+fn test_symbols_disassembly () {
+    let program = Program {
+        symbols: Some(fib_symbols()),
+        ..Program::new(fib_program())
+    };
+    let lines = disassemble(&program);
+    assert_eq!("LDF <fib>", lines[3]);
+    assert_eq!("LD n", lines[4]);
+    assert_eq!("LD n", lines[8]);
+    assert_eq!(vec!["LDF", "LD", "LD"],
+        disassemble(&Program::new(fib_program()))
+            .into_iter()
+            .filter(|line| line.starts_with("LD") && !line.starts_with("LDC"))
+            .collect::<Vec<_>>());
+}
+
+#[test]
+fn test_symbols_roundtrip () {
+    let program = Program {
+        symbols: Some(fib_symbols()),
+        debug: Some(fib_debug_info()),
+        ..Program::new(fib_program())
+    };
+    let encoded = Encoder::new().encode(&program).unwrap();
+    let decoded = Decoder::new(&mut Cursor::new(encoded)).read_program();
+    assert_eq!(Ok(program.clone()), decoded);
+    assert_eq!(
+        Err(String::from("revision 0 files may only contain code")),
+        Encoder::with_revision(REVISION_0).encode(&program)
+    );
+}
+
+#[test]
+fn test_symbols_from_symtable () {
+    use ::compiler_tools::{Scope, SymTable};
+    let mut table = SymTable::new();
+    table.bind("n", 1);
+    let mut symbols = Symbols::new();
+    assert_eq!(table.lookup(&"n"), symbols.lookup_binding(6, &table, "n"));
+    assert_eq!(None, symbols.lookup_binding(7, &table, "m"));
+    assert_eq!(Some("n"), symbols.binding(6));
+    assert_eq!(None, symbols.binding(7));
+}
+
+#[test]
+fn test_symbols_duplicate () {
+    let encoded = vec![ 0x5E, 0xCD, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02
+                      , 0x01, 0x00, 0x00, 0x00, 0x01
+                      , 0x06, 0x00, 0x00, 0x00, 0x14
+                      , 0x00
+                      , 0x00, 0x00, 0x00, 0x02
+                      , 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, b'f'
+                      , 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, b'g'
+                      , 0x00, 0x00, 0x00, 0x00 ];
+    assert_eq!(
+        Err(String::from("duplicate symbol at position 1")),
+        super::decode_program(&mut Cursor::new(encoded))
+    );
+}
+
+fn ld(level: u64, index: u64) -> SVMCell {
+    list_cell![ AtomCell(UInt(level)), AtomCell(UInt(index)) ]
+}
+
+fn definition(name: &str, code: ::List<SVMCell>, relocations: Vec<u32>)
+              -> Definition {
+    Definition { name: String::from(name), exported: true, code, relocations }
+}
+
+/// A module defining `even?` and `odd?` in terms of each other and an
+/// imported `zero?`.
+fn parity_module() -> Module {
+    let body = |other: u64| list_cell![
+        InstCell(NIL), InstCell(LD), ld(1, 1), InstCell(CONS),
+        InstCell(LD), ld(2, 3), InstCell(AP),
+        InstCell(SEL),
+        list_cell![ InstCell(LDC), AtomCell(UInt(1)), InstCell(JOIN) ],
+        list_cell![ InstCell(NIL), InstCell(LDC), AtomCell(UInt(1)),
+                    InstCell(LD), ld(1, 1), InstCell(SUB), InstCell(CONS),
+                    InstCell(LD), ld(2, other), InstCell(AP), InstCell(JOIN) ],
+        InstCell(RET)
+    ];
+    Module {
+        name: String::from("parity"),
+        imports: vec![ String::from("zero?") ],
+        definitions: vec![
+            definition("even?", list!(InstCell(LDF), body(2)), vec![9, 29]),
+            definition("odd?", list!(InstCell(LDF), body(1)), vec![9, 29])
+        ]
+    }
+}
+
+fn zero_module() -> Module {
+    Module {
+        name: String::from("prelude"),
+        imports: vec![],
+        definitions: vec![
+            definition("zero?", list!(InstCell(LDF), list_cell![
+                InstCell(LDC), AtomCell(UInt(0)), InstCell(LD), ld(1, 1),
+                InstCell(EQ), InstCell(RET)
+            ]), vec![]),
+            Definition {
+                exported: false,
+                ..definition("helper", list!(InstCell(LDC), AtomCell(UInt(7))),
+                             vec![])
+            }
+        ]
+    }
+}
+
+fn main_module() -> Module {
+    Module {
+        name: String::from("main"),
+        imports: vec![ String::from("even?") ],
+        definitions: vec![
+            definition("main", list!(InstCell(LDF), list_cell![
+                InstCell(NIL), InstCell(LDC), AtomCell(UInt(10)), InstCell(CONS),
+                InstCell(LD), ld(2, 2), InstCell(AP), InstCell(RET)
+            ]), vec![7])
+        ]
+    }
+}
+
+#[test]
+fn test_link () {
+    let program = link(&[zero_module(), parity_module(), main_module()], "main")
+        .unwrap();
+    let cells: Vec<SVMCell> = program.code.iter().cloned().collect();
+    // the global environment is (zero? helper even? odd? main)
+    assert_eq!(&cells[..2], &[InstCell(DUM), InstCell(NIL)]);
+    let n = cells.len();
+    assert_eq!(&cells[n - 3..], &[
+        InstCell(LDF),
+        list_cell![ InstCell(NIL), InstCell(LD), ld(1, 5), InstCell(AP),
+                    InstCell(RET) ],
+        InstCell(RAP)
+    ]);
+    // main comes first, since the list is built in reverse
+    assert_eq!(cells[3], list_cell![
+        InstCell(NIL), InstCell(LDC), AtomCell(UInt(10)), InstCell(CONS),
+        InstCell(LD), ld(2, 3), InstCell(AP), InstCell(RET)
+    ]);
+    assert_eq!(cells[4], InstCell(CONS));
+    // odd? refers to zero? and even?
+    match cells[6] {
+        ListCell(ref body) => {
+            let body: Vec<SVMCell> = body.iter().cloned().collect();
+            assert_eq!(body[5], ld(2, 1));
+            assert_eq!(body[9], list_cell![
+                InstCell(NIL), InstCell(LDC), AtomCell(UInt(1)),
+                InstCell(LD), ld(1, 1), InstCell(SUB), InstCell(CONS),
+                InstCell(LD), ld(2, 3), InstCell(AP), InstCell(JOIN) ]);
+        },
+        ref other => panic!("expected odd? body, got {:?}", other)
+    }
+    // the private definition and zero?, which has no relocations, are
+    // unchanged
+    assert_eq!(20, n);
+    assert_eq!(&cells[11..14], &[
+        InstCell(LDC), AtomCell(UInt(7)), InstCell(CONS)
+    ]);
+    assert_eq!(cells[15], list_cell![
+        InstCell(LDC), AtomCell(UInt(0)), InstCell(LD), ld(1, 1),
+        InstCell(EQ), InstCell(RET)
+    ]);
+}
+
+#[test]
+fn test_link_errors () {
+    assert_eq!(
+        Err(LinkError::Unresolved(vec![
+            (String::from("parity"), String::from("zero?")),
+            (String::from("main"), String::from("list?"))
+        ])),
+        link(&[ parity_module(),
+                Module { imports: vec![ String::from("list?") ], ..main_module() }
+              ], "main")
+    );
+    assert_eq!(
+        Err(LinkError::DuplicateExport(String::from("zero?"),
+            String::from("prelude"), String::from("prelude"))),
+        link(&[zero_module(), zero_module()], "zero?")
+    );
+    assert_eq!(
+        Err(LinkError::MissingEntry(String::from("helper"))),
+        link(&[zero_module()], "helper")
+    );
+    assert_eq!(
+        "entry point `helper` is not exported by any module",
+        link(&[zero_module()], "helper").unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn test_link_bad_relocations () {
+    for &(position, ref code) in &[
+        (0, list!(InstCell(LD), ld(1, 1))),          // not a list
+        (1, list!(InstCell(LD), ld(1, 2))),          // not in the environment
+        (1, list!(InstCell(LD), ld(1, 0))),
+        (1, list!(InstCell(LD), list_cell![ AtomCell(UInt(1)) ])),
+        (1, list!(InstCell(LD), list_cell![ AtomCell(SInt(1)), AtomCell(UInt(1)) ])),
+        (2, list!(InstCell(LD), ld(1, 1))),          // inside the list
+        (4, list!(InstCell(LD), ld(1, 1)))           // past the end
+    ] {
+        let module = Module {
+            name: String::from("m"),
+            imports: vec![],
+            definitions: vec![ definition("f", code.clone(), vec![position]) ]
+        };
+        assert_eq!(
+            Err(LinkError::BadRelocation(String::from("m"), String::from("f"),
+                                         position)),
+            link(&[module], "f")
+        );
+    }
+}
+
+#[test]
+fn test_module_roundtrip () {
+    for module in vec![ parity_module(), zero_module(), main_module(),
+                        Module::default() ] {
+        let encoded = Encoder::new().encode_module(&module).unwrap();
+        assert_eq!(
+            Ok(module),
+            Decoder::new(&mut Cursor::new(encoded)).read_module()
+        );
+    }
+}
+
+#[test]
+fn test_module_errors () {
+    assert_eq!(
+        Err(String::from("revision 0 files may not contain modules")),
+        Encoder::with_revision(REVISION_0).encode_module(&zero_module())
+    );
+    let empty = Module {
+        definitions: vec![ definition("f", ::List::new(), vec![]) ],
+        ..Module::default()
+    };
+    assert_eq!(
+        Err(String::from("definition f has no code")),
+        Encoder::new().encode_module(&empty)
+    );
+    // a program isn't a module
+    let encoded = Encoder::new().encode(&Program::new(fib_program())).unwrap();
+    assert_eq!(
+        Err(DecodeError::Malformed(String::from("missing module section"))),
+        Decoder::new(&mut Cursor::new(encoded)).read_module()
+    );
+    // but a module can be decoded as a program
+    let encoded = Encoder::new().encode_module(&zero_module()).unwrap();
+    assert_eq!(2, super::decode_program(&mut Cursor::new(encoded))
+                      .unwrap()
+                      .length());
+}
+
+#[test]
+fn test_decode_signed_without_verification () {
+    let program = Program::new(list!(InstCell(NIL)));
+    let mut encoded = Encoder::new().encode(&program).unwrap();
+    // set the signed flag and splice a signature block in after the
+    // section table; it is not checked unless trusted keys are set.
+    encoded[5] = 0x02;
+    let block: Vec<u8> = (0..96).collect();
+    let tail = encoded.split_off(13);
+    encoded.extend(block);
+    encoded.extend(tail);
+
+    let mut cursor = Cursor::new(encoded);
+    let mut decoder = Decoder::new(&mut cursor);
+    assert_eq!(Ok(program), decoder.read_program());
+    let key: Vec<u8> = (0..32).collect();
+    assert_eq!(Some(&key[..]), decoder.signer().as_ref().map(|k| &k[..]));
+}
+
+#[test]
+fn test_lazy_roundtrip () {
+    let programs = vec![ Program::new(fib_program())
+                       , Program::new(closures_program(10))
+                       , Program::new(list!(InstCell(NIL)))
+                       ];
+    let mut compact = Encoder::new();
+    compact.set_compact_atoms(true);
+    for program in programs {
+        for encoded in vec![ Encoder::new().encode(&program).unwrap()
+                           , Encoder::with_revision(REVISION_0)
+                                .encode(&program).unwrap()
+                           , encode_with_pool(&program)
+                           , encode_with_checksum(&program)
+                           , compact.encode(&program).unwrap()
+                           ] {
+            let decoder = LazyDecoder::new(&encoded).unwrap();
+            assert_eq!(Ok(program.code.clone()), decoder.to_list());
+        }
+    }
+}
+
+#[test]
+fn test_lazy_views () {
+    let encoded = Encoder::new()
+        .encode(&Program::new(fib_program()))
+        .unwrap();
+    let decoder = LazyDecoder::new(&encoded).unwrap();
+    let views: Vec<CellView> = decoder.cells()
+                                      .collect::<Result<_, _>>()
+                                      .unwrap();
+    assert_eq!(fib_program().length(), views.len());
+    assert_eq!(CellView::Inst(NIL), views[0]);
+    assert_eq!(CellView::Atom(SInt(10)), views[2]);
+    for (view, cell) in views.iter().zip(fib_program().iter()) {
+        assert_eq!(Ok(cell.clone()), view.to_cell());
+        if let CellView::List(ref list) = *view {
+            assert_eq!(cell.emit().len(), list.byte_len().unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_lazy_skips_nested_lists () {
+    // the nested list contains an invalid char, which is only noticed
+    // when the list is materialized.
+    let mut encoded = vec![0x5E, 0xCD, 0x00, 0x00];
+    encoded.extend(InstCell(LDF).emit());
+    encoded.extend(vec![0xC0, 0xC3, 0x00, 0x00, 0xD8, 0x00, 0x00]);
+    encoded.extend(InstCell(AP).emit());
+
+    let decoder = LazyDecoder::new(&encoded).unwrap();
+    let views: Vec<CellView> = decoder.cells()
+                                      .collect::<Result<_, _>>()
+                                      .unwrap();
+    assert_eq!(3, views.len());
+    assert_eq!(CellView::Inst(AP), views[2]);
+    assert_eq!(1, decoder.num_recorded());
+    match views[1] {
+        CellView::List(ref list) => {
+            assert_eq!(Ok(7), list.byte_len());
+            assert!(list.to_list().is_err());
+        },
+        ref other => panic!("expected a list, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_lazy_records_list_ends () {
+    let code = list!(
+        InstCell(LDF),
+        list_cell![ list_cell![ list_cell![ InstCell(NIL) ] ] ],
+        InstCell(AP)
+    );
+    let encoded = Encoder::new().encode(&Program::new(code)).unwrap();
+    let decoder = LazyDecoder::new(&encoded).unwrap();
+    assert_eq!(3, decoder.cells().count());
+    // scanning the outer list records the ends of the nested lists too
+    assert_eq!(3, decoder.num_recorded());
+}
+
+#[test]
+fn test_lazy_errors () {
+    let program = Program::new(fib_program());
+    let mut encoded = encode_with_checksum(&program);
+    let last = encoded.len() - 1;
+    encoded[last] ^= 0xFF;
+    match LazyDecoder::new(&encoded) {
+        Err(DecodeError::ChecksumMismatch { .. }) => {},
+        other => panic!("expected a checksum mismatch, got {:?}", other)
+    }
+    assert_eq!(
+        Err(DecodeError::Malformed(
+            String::from("EOF while decoding preamble"))),
+        LazyDecoder::new(&[0x5E, 0xCD]).map(|_| ())
+    );
+    // truncated code is reported when it is reached, and never panics
+    let encoded = Encoder::with_revision(REVISION_0)
+        .encode(&program)
+        .unwrap();
+    for len in 4..encoded.len() {
+        let decoder = LazyDecoder::new(&encoded[..len]).unwrap();
+        if let Ok(code) = decoder.to_list() {
+            assert!(code.length() < program.code.length());
+        }
+    }
+    let decoder = LazyDecoder::new(&encoded[..encoded.len() - 2]).unwrap();
+    assert!(decoder.to_list().is_err());
+}
+
+/// A randomly generated program, for fuzzing the decoder.
+#[derive(Clone,Debug)]
+struct RandomProgram(Program);
+
+impl Arbitrary for RandomProgram {
+    fn arbitrary(g: &mut Gen) -> RandomProgram {
+        let mut program = Program::new(::List::arbitrary(g));
+        program.constants = Vec::arbitrary(&mut Gen::new(4));
+        if bool::arbitrary(g) {
+            program.metadata.push((String::arbitrary(g), String::arbitrary(g)));
+        }
+        RandomProgram(program)
+    }
+}
+
+/// Encodes a program, choosing the encoder's options from `options`.
+fn encode_with_options(program: &Program, options: u8) -> Vec<u8> {
+    let mut encoder = if options & 0x08 != 0 {
+        Encoder::with_revision(REVISION_0)
+    } else {
+        Encoder::new()
+    };
+    encoder.set_checksum(options & 0x01 != 0);
+    encoder.set_constant_pool(options & 0x02 != 0);
+    encoder.set_compact_atoms(options & 0x04 != 0);
+    if options & 0x08 != 0 {
+        encoder.set_checksum(false);
+        encoder.set_constant_pool(false);
+        encoder.encode(&Program::new(program.code.clone())).unwrap()
+    } else {
+        encoder.encode(program).unwrap()
+    }
+}
+
+#[test]
+fn prop_roundtrip_arbitrary_cells () {
+    fn prop (cell: SVMCell) -> bool {
+        let decoded = Decoder::new(&mut Cursor::new(cell.emit())).next_cell();
+        decoded == Ok(Some(cell.clone())) && decode_compact(&cell) == Ok(Some(cell))
+    }
+    quickcheck(prop as fn(SVMCell) -> bool);
+}
+
+#[test]
+fn prop_roundtrip_arbitrary_programs () {
+    fn prop (code: ::List<SVMCell>) -> bool {
+        let program = Program::new(code);
+        let encoded = Encoder::new().encode(&program).unwrap();
+        super::decode_program(&mut Cursor::new(encoded)) == Ok(program.code)
+    }
+    quickcheck(prop as fn(::List<SVMCell>) -> bool);
+}
+
+#[test]
+fn prop_decode_random_bytes () {
+    fn prop (bytes: Vec<u8>) -> bool {
+        decode_everything(&bytes);
+        for preamble in &[ &[0x5E, 0xCD, 0x00, 0x00][..]
+                         , &[0x5E, 0xCD, 0x00, 0x01][..]
+                         , &[0x5E, 0xCD, 0x00, 0x01, 0x00, 0x00][..]
+                         , &[0x5E, 0xCD, 0x00, 0x01, 0x00, 0x04][..]
+                         ] {
+            let mut file = preamble.to_vec();
+            file.extend_from_slice(&bytes);
+            decode_everything(&file);
+        }
+        true
+    }
+    quickcheck(prop as fn(Vec<u8>) -> bool);
+}
+
+#[test]
+fn prop_decode_random_programs () {
+    fn prop (program: RandomProgram, options: u8) -> bool {
+        let RandomProgram(program) = program;
+        let encoded = encode_with_options(&program, options);
+        let decoded = Decoder::new(&mut Cursor::new(&encoded)).read_program();
+        let expected = if options & 0x08 != 0 {
+            Program::new(program.code.clone())
+        } else {
+            program
+        };
+        decoded == Ok(expected)
+    }
+    quickcheck(prop as fn(RandomProgram, u8) -> bool);
+}
+
+#[test]
+fn prop_decode_mutated_programs () {
+    fn prop (program: RandomProgram, options: u8, index: usize, byte: u8,
+             truncate: bool) -> bool {
+        let RandomProgram(program) = program;
+        let mut encoded = encode_with_options(&program, options);
+        let index = index % encoded.len();
+        if truncate {
+            encoded.truncate(index);
+        } else {
+            encoded[index] = byte;
+        }
+        decode_everything(&encoded);
+        true
+    }
+    quickcheck(prop as fn(RandomProgram, u8, usize, u8, bool) -> bool);
+}
+
+#[test]
+fn test_decode_reserved_constant_types () {
+    for tag in 0xCA..0xCF {
+        assert_eq!(
+            Err(format!("Unsupported constant type {:#02x}", tag)),
+            Decoder::new(&mut Cursor::new(vec![tag, 0x00])).next_cell()
+        );
+    }
+}
+
+#[test]
+fn test_decode_interned () {
+    let pair = SVMCell::dotted(list!(AtomCell(UInt(1))), AtomCell(UInt(2)));
+    let cells = vec![
+        list_cell![InstCell(LD), pair.clone(), InstCell(RET)],
+        list_cell![InstCell(LDF), list_cell![InstCell(LD), pair, InstCell(RET)]],
+        BytesCell(vec![1, 2])
+    ];
+    let mut bytes = Vec::new();
+    for cell in &cells {
+        bytes.extend(cell.emit());
+    }
+    let mut interner = Interner::new();
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    let mut decoded = Vec::new();
+    while let Some(cell) = decoder.next_interned(&mut interner).unwrap() {
+        decoded.push(cell);
+    }
+    assert_eq!(decoded.iter().map(|cell| cell.to_cell()).collect::<Vec<_>>(),
+               cells);
+    // the body of the closure is the first cell
+    let body = decoded[1].cdr().unwrap().car().unwrap();
+    assert_eq!(body, &decoded[0]);
+    let mut fresh = Interner::new();
+    for cell in &cells {
+        fresh.intern(cell);
+    }
+    assert_eq!(interner.len(), fresh.len());
+}
+
+#[test]
+fn test_decoder_iterator_stops_at_errors () {
+    let bytes = vec![0x00, 0xFF, 0x00];
+    let mut cursor = Cursor::new(bytes);
+    let cells: Vec<SVMCell> = Decoder::new(&mut cursor).collect();
+    assert_eq!(vec![InstCell(NIL)], cells);
+}
+
+#[test]
+fn test_decode_nesting_limit () {
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut bytes = vec![0xC0; depth];
+        bytes.push(0x00);
+        bytes.extend(vec![0x00; depth]);
+        bytes
+    }
+    let max = super::DEFAULT_MAX_DEPTH;
+    let error = Err(format!("lists nested too deeply: the limit is {}", max));
+    let decoded = Decoder::new(&mut Cursor::new(nested(max))).next_cell();
+    assert!(decoded.unwrap().is_some());
+    assert_eq!(error,
+        Decoder::new(&mut Cursor::new(nested(max + 1))).next_cell());
+
+    let mut cursor = Cursor::new(nested(4));
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_depth(3);
+    assert_eq!(Err(String::from("lists nested too deeply: the limit is 3")),
+        decoder.next_cell());
+    let mut cursor = Cursor::new(nested(max + 1));
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_depth(max + 1);
+    assert!(decoder.next_cell().unwrap().is_some());
+
+    // a lazy decoder can step over the list, but not materialize it
+    let mut file = vec![0x5E, 0xCD, 0x00, 0x00];
+    file.extend(nested(max * 4));
+    let decoder = LazyDecoder::new(&file).unwrap();
+    assert_eq!(1, decoder.cells().count());
+    assert_eq!(Err(String::from("lists nested too deeply: the limit is 256")),
+        decoder.to_list());
+}
+
+#[test]
+fn test_encode_nesting_limit () {
+    fn nested(depth: usize) -> SVMCell {
+        let mut cell = InstCell(NIL);
+        for _ in 0..depth {
+            cell = list_cell![cell];
+        }
+        cell
+    }
+    let mut encoder = Encoder::new();
+    encoder.set_max_depth(3);
+    assert!(encoder.encode(&Program::new(list!(nested(3)))).is_ok());
+    // empty lists are encoded as `NIL`, so they are not nested
+    let empty = list_cell![list_cell![list_cell![ListCell(Box::new(Nil))]]];
+    assert!(encoder.encode(&Program::new(list!(empty))).is_ok());
+    let error = Err(String::from("lists nested too deeply: the limit is 3"));
+    assert_eq!(error, encoder.encode(&Program::new(list!(nested(4)))));
+    let mut program = Program::new(list!(InstCell(NIL)));
+    program.constants.push(nested(4));
+    assert_eq!(error, encoder.encode(&program));
+    let dotted = SVMCell::dotted(list!(nested(3)), AtomCell(UInt(1)));
+    assert_eq!(error, encoder.encode(&Program::new(list!(dotted))));
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn test_lengths_limited_to_32_bits () {
+    let mut bytes = Vec::new();
+    assert_eq!(Ok(()), super::write_len(&mut bytes, u32::MAX as usize,
+                                        || String::from("too long")));
+    assert_eq!(bytes, vec![0xFF; 4]);
+    assert_eq!(Err(String::from("too long")),
+        super::write_len(&mut bytes, u32::MAX as usize + 1,
+                         || String::from("too long")));
+    assert_eq!(bytes.len(), 4);
+}
+
+#[test]
+fn test_max_cells_only_limits_pool_references () {
+    // a Revision 0 stream has no pool, so any number of cells may be read
+    let bytes = list_cell![InstCell(LDC), AtomCell(UInt(1)), InstCell(ADD)]
+        .emit()
+        .repeat(100);
+    let mut cursor = Cursor::new(bytes);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_cells(10);
+    assert_eq!(100, decoder.by_ref().count());
+    assert_eq!(Ok(None), decoder.next_cell());
+}
+
+/// Builds a Revision 1 file without checksums or signatures.
+fn rev1_file(sections: Vec<(u8, Vec<u8>)>) -> Vec<u8> {
+    let mut file = vec![0x5E, 0xCD, 0x00, 0x01, 0x00, 0x00,
+                        0x00, sections.len() as u8];
+    for &(kind, ref bytes) in &sections {
+        file.push(kind);
+        file.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    }
+    for (_, bytes) in sections {
+        file.extend(bytes);
+    }
+    file
+}
+
+#[test]
+fn test_decode_pool_reference_limit () {
+    // each pool entry refers to the previous one twice, so entry n
+    // contains 2^n cells
+    let mut pool = vec![0x00, 0x00, 0x00, 40, 0xC1, 0, 0, 0, 0, 0, 0, 0, 0];
+    for i in 0..39u8 {
+        pool.extend(vec![0xC0, 0xC6, 0x00, 0x00, 0x00, i,
+                         0xC0, 0xC6, 0x00, 0x00, 0x00, i, 0x00]);
+    }
+    let code = vec![0xC6, 0x00, 0x00, 0x00, 39];
+    let file = rev1_file(vec![(0x01, code), (0x05, pool)]);
+
+    let mut cursor = Cursor::new(&file);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_cells(1 << 12);
+    assert_eq!(
+        Err(DecodeError::Malformed(
+            String::from("too many cells: the limit is 4096"))),
+        decoder.read_program()
+    );
+    assert_eq!(
+        Err(DecodeError::Malformed(
+            String::from("too many cells: the limit is 4096"))),
+        LazyDecoder::with_max_cells(&file, 1 << 12).map(|_| ())
+    );
+
+    // many references to an entry of 101 cells
+    let mut pool = vec![0x00, 0x00, 0x00, 0x01];
+    pool.extend([0xC0, 0x00].repeat(100));
+    pool.push(0x00);
+    let mut code = [0xC0, 0xC6, 0x00, 0x00, 0x00, 0x00].repeat(100);
+    code.push(0x00);
+    let file = rev1_file(vec![(0x01, code), (0x05, pool)]);
+
+    let mut cursor = Cursor::new(&file);
+    let mut decoder = Decoder::new(&mut cursor);
+    decoder.set_max_cells(1 << 12);
+    assert_eq!(
+        Err(DecodeError::Malformed(
+            String::from("too many cells: the limit is 4096"))),
+        decoder.read_program()
+    );
+    let lazy = LazyDecoder::with_max_cells(&file, 1 << 12).unwrap();
+    assert_eq!(
+        Err(String::from("too many cells to materialize")),
+        lazy.to_list()
+    );
+    let lazy = LazyDecoder::with_max_cells(&file, 1 << 14).unwrap();
+    assert_eq!(Ok(1), lazy.to_list().map(|code| code.length()));
+}
+
+#[cfg(feature = "signing")]
+mod signing {
+    use super::super::{Decoder,Encoder,Program,DecodeError,REVISION_0,
+                       SigningKey};
+    use ::cell::Atom::*;
+    use ::cell::SVMCell::*;
+    use ::Inst::*;
+    use ::List::{Cons,Nil};
+
+    use std::io::Cursor;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn encode_signed(program: &Program, checksum: bool) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        encoder.set_checksum(checksum);
+        encoder.set_signing_key(key(1));
+        encoder.encode(program).unwrap()
+    }
+
+    fn decode_trusting(bytes: Vec<u8>, trusted: &[u8])
+                       -> Result<Program, DecodeError> {
+        let mut cursor = Cursor::new(bytes);
+        let mut decoder = Decoder::new(&mut cursor);
+        decoder.set_trusted_keys(
+            trusted.iter().map(|&seed| key(seed).verifying_key()).collect());
+        decoder.read_program()
+    }
+
+    #[test]
+    fn test_signed_roundtrip () {
+        let mut program = Program::new(super::fib_program());
+        program.metadata.push((String::from("author"), String::from("eliza")));
+        for &checksum in &[false, true] {
+            let encoded = encode_signed(&program, checksum);
+            assert_eq!(encoded[5] & 0x02, 0x02); // signed flag
+            assert_eq!(Ok(program.clone()), decode_trusting(encoded, &[2, 1]));
+        }
+    }
+
+    #[test]
+    fn test_signed_untrusted_key () {
+        let program = Program::new(list!(InstCell(NIL)));
+        assert_eq!(
+            Err(DecodeError::UntrustedKey(key(1).verifying_key().to_bytes())),
+            decode_trusting(encode_signed(&program, false), &[2, 3])
+        );
+    }
+
+    #[test]
+    fn test_signed_tampered () {
+        let program = Program::new(list!(InstCell(LDC), AtomCell(UInt(7))));
+        let mut encoded = encode_signed(&program, false);
+        let last = encoded.len() - 1;
+        encoded[last] = 8;
+        assert_eq!(
+            Err(DecodeError::BadSignature),
+            decode_trusting(encoded.clone(), &[1])
+        );
+        // without trusted keys, the signature isn't checked
+        assert_eq!(
+            Ok(list!(InstCell(LDC), AtomCell(UInt(8)))),
+            Decoder::new(&mut Cursor::new(encoded)).read_program()
+                                                   .map(|p| p.code)
+        );
+    }
+
+    #[test]
+    fn test_signed_requires_signature () {
+        let program = Program::new(list!(InstCell(NIL)));
+        let unsigned = Encoder::new().encode(&program).unwrap();
+        assert_eq!(Err(DecodeError::Unsigned), decode_trusting(unsigned, &[1]));
+        let rev0 = Encoder::with_revision(REVISION_0).encode(&program).unwrap();
+        assert_eq!(Err(DecodeError::Unsigned), decode_trusting(rev0, &[1]));
+    }
+
+    #[test]
+    fn test_signed_revision_0 () {
+        let mut encoder = Encoder::with_revision(REVISION_0);
+        encoder.set_signing_key(key(1));
+        assert_eq!(
+            Err(String::from("revision 0 files may not be signed")),
+            encoder.encode(&Program::new(list!(InstCell(NIL))))
+        );
+    }
+}
+
+#[test]
+#[cfg(not(feature = "compression"))]
+fn test_decode_compressed_without_feature () {
+    let encoded = vec![0x5E, 0xCD, 0x00, 0x01, 0x00, 0x04, 0x63, 0x00, 0x00];
+    assert_eq!(
+        Err(String::from("compressed files require the `compression` feature")),
+        super::decode_program(&mut Cursor::new(encoded))
+    );
+}
+
+#[cfg(feature = "compression")]
+mod compression {
+    use super::super::{Decoder,Encoder,Program,DecodeError,REVISION_0};
+    use ::cell::SVMCell::*;
+    use ::Inst::*;
+    use ::List::{Cons,Nil};
+
+    use std::io::Cursor;
+
+    fn encode_compressed(program: &Program) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        encoder.set_compression(true);
+        encoder.encode(program).unwrap()
+    }
+
+    #[test]
+    fn test_compressed_roundtrip () {
+        let mut program = Program::new(super::closures_program(20));
+        program.metadata.push((String::from("name"), String::from("prelude")));
+        let encoded = encode_compressed(&program);
+        assert_eq!(&encoded[4..6], &[0x00, 0x04]); // compressed flag
+        assert_eq!(
+            Ok(program.clone()),
+            Decoder::new(&mut Cursor::new(encoded)).read_program()
+        );
+        let encoded = encode_compressed(&program);
+        assert_eq!(
+            Ok(program.code),
+            super::super::decode_program(&mut Cursor::new(encoded))
+        );
+    }
+
+    #[test]
+    fn test_decompressed_length_limit () {
+        let program = Program::new(super::closures_program(50));
+        let encoded = encode_compressed(&program);
+        let mut cursor = Cursor::new(encoded);
+        let mut decoder = Decoder::new(&mut cursor);
+        decoder.set_max_decompressed_len(100);
+        assert_eq!(
+            Err(DecodeError::Malformed(String::from(
+                "could not decompress file: longer than 100 bytes"))),
+            decoder.read_program()
+        );
+    }
+
+    #[test]
+    fn test_compressed_is_smaller () {
+        let program = Program::new(super::closures_program(50));
+        let plain = Encoder::new().encode(&program).unwrap();
+        let compressed = encode_compressed(&program);
+        assert!(compressed.len() * 4 < plain.len(),
+            "compressed size {} not less than a quarter of {}",
+            compressed.len(), plain.len());
+    }
+
+    #[test]
+    fn test_compressed_with_checksum_and_pool () {
+        let program = Program::new(super::closures_program(5));
+        let mut encoder = Encoder::new();
+        encoder.set_compression(true);
+        encoder.set_checksum(true);
+        encoder.set_constant_pool(true);
+        encoder.set_compact_atoms(true);
+        let encoded = encoder.encode(&program).unwrap();
+        assert_eq!(&encoded[4..6], &[0x00, 0x05]);
+        assert_eq!(
+            Ok(program),
+            Decoder::new(&mut Cursor::new(encoded)).read_program()
+        );
+    }
+
+    #[test]
+    fn test_compressed_corrupt () {
+        let program = Program::new(super::fib_program());
+        let mut encoded = encode_compressed(&program);
+        encoded.truncate(encoded.len() / 2);
+        match Decoder::new(&mut Cursor::new(encoded)).read_program() {
+            Err(DecodeError::Malformed(_)) => {},
+            other => panic!("expected malformed file, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_compressed_revision_0 () {
+        let mut encoder = Encoder::with_revision(REVISION_0);
+        encoder.set_compression(true);
+        assert_eq!(
+            Err(String::from("revision 0 files may not be compressed")),
+            encoder.encode(&Program::new(list!(InstCell(NIL))))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "signing")]
+    fn test_compressed_signed () {
+        use super::super::SigningKey;
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let program = Program::new(super::fib_program());
+        let mut encoder = Encoder::new();
+        encoder.set_compression(true);
+        encoder.set_signing_key(key.clone());
+        let encoded = encoder.encode(&program).unwrap();
+
+        let mut cursor = Cursor::new(encoded);
+        let mut decoder = Decoder::new(&mut cursor);
+        decoder.set_trusted_keys(vec![key.verifying_key()]);
+        assert_eq!(Ok(program), decoder.read_program());
+        assert_eq!(Some(key.verifying_key().to_bytes()), decoder.signer());
+    }
+}
+
 impl_encode_test!(
     test_encode_uint_zero,
     SVMCell::AtomCell(Atom::UInt(0))
@@ -251,6 +1784,89 @@ impl_encode_test!(
     test_encode_inst_stop,
     SVMCell::InstCell(Inst::STOP)
 );
+impl_encode_test!(
+    test_encode_inst_and,
+    SVMCell::InstCell(Inst::AND)
+);
+impl_encode_test!(
+    test_encode_inst_or,
+    SVMCell::InstCell(Inst::OR)
+);
+impl_encode_test!(
+    test_encode_inst_xor,
+    SVMCell::InstCell(Inst::XOR)
+);
+impl_encode_test!(
+    test_encode_inst_not,
+    SVMCell::InstCell(Inst::NOT)
+);
+impl_encode_test!(
+    test_encode_inst_shl,
+    SVMCell::InstCell(Inst::SHL)
+);
+impl_encode_test!(
+    test_encode_inst_shr,
+    SVMCell::InstCell(Inst::SHR)
+);
+
+impl_encode_test!(
+    test_encode_inst_type,
+    SVMCell::InstCell(Inst::TYPE)
+);
+impl_encode_test!(
+    test_encode_inst_isuint,
+    SVMCell::InstCell(Inst::ISUINT)
+);
+impl_encode_test!(
+    test_encode_inst_issint,
+    SVMCell::InstCell(Inst::ISSINT)
+);
+impl_encode_test!(
+    test_encode_inst_isfloat,
+    SVMCell::InstCell(Inst::ISFLOAT)
+);
+impl_encode_test!(
+    test_encode_inst_ischar,
+    SVMCell::InstCell(Inst::ISCHAR)
+);
+impl_encode_test!(
+    test_encode_inst_islist,
+    SVMCell::InstCell(Inst::ISLIST)
+);
+impl_encode_test!(
+    test_encode_inst_isclos,
+    SVMCell::InstCell(Inst::ISCLOS)
+);
+impl_encode_test!(
+    test_encode_inst_isbytes,
+    SVMCell::InstCell(Inst::ISBYTES)
+);
+impl_encode_test!(
+    test_encode_inst_itof,
+    SVMCell::InstCell(Inst::ITOF)
+);
+impl_encode_test!(
+    test_encode_inst_ftoi,
+    SVMCell::InstCell(Inst::FTOI)
+);
+impl_encode_test!(
+    test_encode_inst_ctoi,
+    SVMCell::InstCell(Inst::CTOI)
+);
+impl_encode_test!(
+    test_encode_inst_itoc,
+    SVMCell::InstCell(Inst::ITOC)
+);
+
+#[test]
+fn test_decode_reserved_opcode () {
+    let decoded = Decoder::new(&mut Cursor::new(vec![0x30])).next_cell();
+    assert_eq!(
+        Err(String::from("Unimplemented: reserved byte 0x30")),
+        decoded
+    )
+}
+
 impl_encode_test!(
     test_encode_simple_program,
     list_cell![
@@ -346,3 +1962,157 @@ impl_encode_test!(
         InstCell(ADD)
     ]
 );
+
+impl_encode_test!(
+    test_encode_bytes_empty,
+    SVMCell::BytesCell(vec![])
+);
+
+impl_encode_test!(
+    test_encode_program_with_bytes,
+    list_cell![
+        InstCell(LDC), BytesCell(vec![0x5E, 0xCD, 0x00, 0x00]),
+        InstCell(LDC), AtomCell(SInt(1)),
+        InstCell(CONS)
+    ]
+);
+
+#[test]
+fn test_encode_long_list () {
+    let program: ::List<SVMCell> = (0..1000000u64)
+        .map(|i| AtomCell(UInt(i)))
+        .collect();
+    let cell = ListCell(Box::new(program));
+    let decoded = Decoder::new(&mut Cursor::new(cell.emit())).next_cell();
+    assert_eq!(Ok(Some(cell)), decoded);
+}
+
+fn pair(car: u64, cdr: u64) -> SVMCell {
+    SVMCell::cons(AtomCell(UInt(car)), AtomCell(UInt(cdr)))
+}
+
+fn improper_program() -> ::List<SVMCell> {
+    list!(
+        InstCell(LD), pair(1, 3),
+        InstCell(LDC), SVMCell::dotted(
+            list!(AtomCell(UInt(1)), ld(2, 2)), BytesCell(vec![0xFF])),
+        InstCell(LDC), list_cell![ pair(4, 5), AtomCell(Char('a')) ],
+        InstCell(LDC), SVMCell::cons(AtomCell(SInt(-1)), InstCell(AP))
+    )
+}
+
+#[test]
+fn test_encode_dotted_pair () {
+    let cell = pair(1, 3);
+    let mut expected = vec![0xC0];
+    expected.extend(AtomCell(UInt(1)).emit());
+    expected.extend(AtomCell(UInt(3)).emit());
+    assert_eq!(expected, cell.emit());
+    for encoded in vec![ cell.emit(), cell.emit_compact() ] {
+        assert_eq!(Ok(Some(cell.clone())),
+                   Decoder::new(&mut Cursor::new(encoded)).next_cell());
+    }
+}
+
+#[test]
+fn test_encode_dotted_nil () {
+    // `(1 . NIL)` is the list `(1)`, since `NIL` also ends a list
+    let cell = SVMCell::cons(AtomCell(UInt(1)), InstCell(NIL));
+    assert_eq!(list_cell![AtomCell(UInt(1))], cell);
+    let program = Program::new(list!(cell));
+    let encoded = Encoder::new().encode(&program).unwrap();
+    assert_eq!(Ok(program),
+               Decoder::new(&mut Cursor::new(encoded)).read_program());
+    // dotted cells built directly with such a cdr can't be encoded
+    for cdr in vec![ InstCell(NIL)
+                   , list_cell![AtomCell(UInt(2))]
+                   , pair(2, 3)
+                   ] {
+        let dotted = DottedCell(Box::new(
+            DottedList::pair(AtomCell(UInt(1)), cdr)));
+        assert!(Encoder::new().encode(&Program::new(list!(dotted))).is_err());
+    }
+}
+
+#[test]
+fn test_decode_improper_lists () {
+    let program = Program::new(improper_program());
+    let mut compact = Encoder::new();
+    compact.set_compact_atoms(true);
+    for encoded in vec![ Encoder::new().encode(&program).unwrap()
+                       , Encoder::with_revision(REVISION_0)
+                            .encode(&program).unwrap()
+                       , encode_with_pool(&program)
+                       , compact.encode(&program).unwrap()
+                       ] {
+        assert_eq!(Ok(program.clone()),
+                   Decoder::new(&mut Cursor::new(encoded.clone())).read_program());
+        let decoder = LazyDecoder::new(&encoded).unwrap();
+        assert_eq!(Ok(program.code.clone()), decoder.to_list());
+    }
+}
+
+#[test]
+fn test_decode_dotted_cdr_from_pool () {
+    // a pool reference to a list in the cdr position continues the list
+    let mut bytes = vec![0xC0];
+    bytes.extend(AtomCell(UInt(1)).emit());
+    bytes.extend(vec![0xC6, 0x00, 0x00, 0x00, 0x00]);
+    let code = super::decode_cells(&bytes, super::DecodedPool {
+        entries: vec![ list_cell![ AtomCell(UInt(2)) ] ],
+        sizes: vec![ 2 ]
+    }, 16, 16);
+    assert_eq!(Ok(list!(list_cell![ AtomCell(UInt(1)), AtomCell(UInt(2)) ])),
+               code);
+}
+
+#[test]
+fn test_lazy_improper_lists () {
+    let encoded = Encoder::new()
+        .encode(&Program::new(improper_program()))
+        .unwrap();
+    let decoder = LazyDecoder::new(&encoded).unwrap();
+    let views: Vec<CellView> = decoder.cells()
+                                      .collect::<Result<_, _>>()
+                                      .unwrap();
+    assert_eq!(8, views.len());
+    let list = match views[3] {
+        CellView::List(list) => list,
+        ref other => panic!("expected a list view, got {:?}", other)
+    };
+    assert_eq!(2, list.iter().count());
+    let cdr = list.last_cdr().unwrap().unwrap();
+    assert_eq!(Ok(BytesCell(vec![0xFF])), cdr.to_cell());
+    assert!(list.to_list().is_err());
+    assert_eq!(Ok(improper_program()[3u64].clone()), list.to_cell());
+    match views[5] {
+        CellView::List(list) => assert_eq!(Ok(None), list.last_cdr()),
+        ref other => panic!("expected a list view, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_improper_list_positions () {
+    let program = Program::new(list!(InstCell(LD), pair(1, 3), InstCell(AP)));
+    let cells: Vec<_> = program.cells().collect();
+    assert_eq!(5, cells.len());
+    assert_eq!((3, &AtomCell(UInt(3))), cells[3]);
+    assert_eq!((4, &InstCell(AP)), cells[4]);
+}
+
+#[test]
+fn test_link_dotted_pairs () {
+    let module = Module {
+        name: String::from("m"),
+        imports: vec![],
+        definitions: vec![
+            definition("f", list!(InstCell(LDF), list_cell![
+                InstCell(LD), pair(1, 1), InstCell(RET)
+            ]), vec![3])
+        ]
+    };
+    let program = link(&[zero_module(), module], "f").unwrap();
+    // f is the third definition in the global environment
+    assert_eq!(program.code[3u64],
+               list_cell![ InstCell(LD), pair(1, 3), InstCell(RET) ]);
+}
@@ -0,0 +1,55 @@
+//! Variable-length integer encoding.
+//!
+//! Integers are encoded as unsigned LEB128: seven bits at a time, least
+//! significant group first, with the high bit of each byte set if more
+//! bytes follow. Signed integers are first zigzag-encoded, so that small
+//! negative numbers are also encoded in few bytes.
+
+use std::io::Read;
+
+/// Maximum length of an encoded 64-bit integer.
+pub const MAX_LEN: usize = 10;
+
+/// Appends the LEB128 encoding of `n` to `buf`.
+pub fn write(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+/// Reads a LEB128-encoded integer.
+///
+/// Returns the integer and the number of bytes read.
+pub fn read<R: Read>(source: &mut R) -> Result<(u64, usize), String> {
+    let mut n = 0;
+    for i in 0..MAX_LEN {
+        let mut buf = [0; 1];
+        if source.read(&mut buf).map_err(|why| why.to_string())? == 0 {
+            return Err(String::from("EOF while decoding varint"));
+        }
+        let group = (buf[0] & 0x7F) as u64;
+        // the tenth byte may only contribute the top bit of the integer
+        if i == MAX_LEN - 1 && group > 1 {
+            return Err(String::from("varint too large"));
+        }
+        n |= group << (7 * i);
+        if buf[0] & 0x80 == 0 {
+            return Ok((n, i + 1));
+        }
+    }
+    Err(String::from("varint too large"))
+}
+
+/// Maps signed integers to unsigned integers so that numbers with a small
+/// absolute value have a small encoding: 0, -1, 1, -2, ... map to 0, 1, 2,
+/// 3, ...
+pub fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+/// The inverse of `zigzag()`.
+pub fn unzigzag(n: u64) -> i64 {
+    ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
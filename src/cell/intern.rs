@@ -0,0 +1,359 @@
+//! Hash-consing of cells.
+//!
+//! Compiled programs contain many structurally equal lists, such as the
+//! `(NIL)` at the end of many instruction sequences, or constant lists
+//! which are loaded in several places. An `Interner` maps every
+//! structurally equal cell to a single shared `Interned` node, including
+//! the sublists of lists, so that each distinct structure is stored only
+//! once. Since equal cells interned by the same interner are the same
+//! node, comparing and hashing `Interned` cells is O(1).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem::ManuallyDrop;
+use std::rc::Rc;
+
+use super::{Atom, Inst, SVMCell};
+use super::SVMCell::*;
+use ::list::{List, ListBuilder};
+use ::list::List::Nil;
+
+/// A cell which has been interned by an `Interner`.
+///
+/// Cloning an `Interned` cell only increments a reference count.
+/// Interned cells are compared and hashed by identity, so two cells are
+/// equal only if they were interned by the same `Interner`, and are
+/// structurally equal.
+#[derive(Clone)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+pub struct Interned(ManuallyDrop<Rc<InternedNode>>);
+
+/// The contents of an `Interned` cell.
+///
+/// Lists are chains of `Cons` nodes ending in `Nil`, or, for improper
+/// lists, in any other node.
+#[derive(Debug)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+pub enum InternedNode {
+    /// An atom.
+    Atom(Atom),
+    /// An instruction.
+    Inst(Inst),
+    /// A byte string.
+    Bytes(Vec<u8>),
+    /// The empty list.
+    Nil,
+    /// A `cons` cell, with its car and cdr.
+    Cons(Interned, Interned)
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl Interned {
+
+    /// Borrows this cell's contents.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn node(&self) -> &InternedNode { &self.0 }
+
+    /// Returns the car of this cell, if it is a `cons` cell.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn car(&self) -> Option<&Interned> {
+        match *self.node() {
+            InternedNode::Cons(ref car, _) => Some(car),
+            _ => None
+        }
+    }
+
+    /// Returns the cdr of this cell, if it is a `cons` cell.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn cdr(&self) -> Option<&Interned> {
+        match *self.node() {
+            InternedNode::Cons(_, ref cdr) => Some(cdr),
+            _ => None
+        }
+    }
+
+    /// Copies this cell out of the interner's shared structure.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn to_cell(&self) -> SVMCell {
+        let mut items = ListBuilder::new();
+        let mut rest = self;
+        while let InternedNode::Cons(ref car, ref cdr) = *rest.node() {
+            items.push_back(car.to_cell());
+            rest = cdr;
+        }
+        let last = match *rest.node() {
+            InternedNode::Atom(atom) => AtomCell(atom),
+            InternedNode::Inst(inst) => InstCell(inst),
+            InternedNode::Bytes(ref bytes) => BytesCell(bytes.clone()),
+            InternedNode::Nil => ListCell(Box::new(Nil)),
+            InternedNode::Cons(..) => unreachable!()
+        };
+        SVMCell::dotted(items.build(), last)
+    }
+
+    /// Copies this cell out of the interner's shared structure, as a list.
+    ///
+    /// Returns `None` if this cell is not a proper list.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn to_list(&self) -> Option<List<SVMCell>> {
+        match self.to_cell() {
+            ListCell(list) => Some(*list),
+            _ => None
+        }
+    }
+
+    /// Returns the address of this cell's node, which identifies it.
+    #[inline]
+    fn addr(&self) -> usize { &**self.0 as *const InternedNode as usize }
+
+    /// Takes this cell's reference to its node.
+    #[inline]
+    fn into_rc(self) -> Rc<InternedNode> {
+        let mut cell = ManuallyDrop::new(self);
+        unsafe { ManuallyDrop::take(&mut cell.0) }
+    }
+}
+
+/// Drops the cdrs of a chain of `cons` cells iteratively, so that dropping
+/// the last reference to a long list can't overflow the stack.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl Drop for Interned {
+    fn drop(&mut self) {
+        let mut next = unsafe { ManuallyDrop::take(&mut self.0) };
+        while let Ok(InternedNode::Cons(car, cdr)) = Rc::try_unwrap(next) {
+            drop(car);
+            next = cdr.into_rc();
+        }
+    }
+}
+
+/// Interned cells are equal if they are the same node.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl PartialEq for Interned {
+    #[inline]
+    fn eq(&self, other: &Interned) -> bool { Rc::ptr_eq(&self.0, &other.0) }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl Eq for Interned {}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl Hash for Interned {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) { self.addr().hash(state) }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_cell())
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_cell())
+    }
+}
+
+/// Identifies a node by its contents, and its children by their
+/// addresses, so that looking a node up is O(1) in its size.
+///
+/// Floats are identified by their bits, so `0.0` and `-0.0` are interned
+/// separately, while each NaN is interned once.
+#[derive(PartialEq,Eq,Hash)]
+enum Key {
+    UInt(u64),
+    SInt(i64),
+    Float(u64),
+    Char(char),
+    Inst(u8),
+    Bytes(Vec<u8>),
+    Nil,
+    Cons(usize, usize)
+}
+
+/// Maps structurally equal cells to a single shared `Interned` node.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::cell::Interner;
+/// # use seax_util::cell::SVMCell::*;
+/// # use seax_util::cell::Atom::*;
+/// # use seax_util::cell::Inst::*;
+/// # use seax_util::list::List::{Cons,Nil};
+/// # fn main() {
+/// let mut interner = Interner::new();
+/// let a = interner.intern(&list_cell![InstCell(LDC), AtomCell(SInt(1)), InstCell(NIL)]);
+/// let b = interner.intern(&list_cell![InstCell(LDF), InstCell(NIL)]);
+/// // the `(NIL)` tail is shared
+/// assert_eq!(a.cdr().unwrap().cdr(), b.cdr());
+///
+/// let c = interner.intern(&list_cell![InstCell(LDF), InstCell(NIL)]);
+/// assert_eq!(b, c);
+/// assert_eq!(c.to_cell(), list_cell![InstCell(LDF), InstCell(NIL)]);
+/// # }
+/// ```
+#[derive(Default)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+pub struct Interner {
+    nodes: HashMap<Key, Interned>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl Interner {
+
+    /// Creates an empty interner.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn new() -> Interner { Interner::default() }
+
+    /// Returns the shared node with the given key, creating it if needed.
+    fn node<F>(&mut self, key: Key, make: F) -> Interned
+    where F: FnOnce() -> InternedNode {
+        self.nodes.entry(key)
+            .or_insert_with(|| Interned(ManuallyDrop::new(Rc::new(make()))))
+            .clone()
+    }
+
+    /// Interns an atom.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn atom(&mut self, atom: Atom) -> Interned {
+        let key = match atom {
+            Atom::UInt(n) => Key::UInt(n),
+            Atom::SInt(n) => Key::SInt(n),
+            Atom::Float(n) => Key::Float(n.to_bits()),
+            Atom::Char(c) => Key::Char(c)
+        };
+        self.node(key, || InternedNode::Atom(atom))
+    }
+
+    /// Interns an instruction.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn inst(&mut self, inst: Inst) -> Interned {
+        self.node(Key::Inst(inst as u8), || InternedNode::Inst(inst))
+    }
+
+    /// Interns a byte string.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn bytes(&mut self, bytes: &[u8]) -> Interned {
+        self.node(Key::Bytes(bytes.to_vec()), || InternedNode::Bytes(bytes.to_vec()))
+    }
+
+    /// Interns the empty list.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn nil(&mut self) -> Interned {
+        self.node(Key::Nil, || InternedNode::Nil)
+    }
+
+    /// Interns a `cons` cell of two interned cells.
+    ///
+    /// This is O(1), since `car` and `cdr` are already interned.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn cons(&mut self, car: Interned, cdr: Interned) -> Interned {
+        let key = Key::Cons(car.addr(), cdr.addr());
+        self.node(key, || InternedNode::Cons(car, cdr))
+    }
+
+    /// Interns a cell.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn intern(&mut self, cell: &SVMCell) -> Interned {
+        match *cell {
+            AtomCell(atom) => self.atom(atom),
+            InstCell(inst) => self.inst(inst),
+            BytesCell(ref bytes) => self.bytes(bytes),
+            ListCell(ref list) => self.intern_list(list),
+            DottedCell(ref list) => {
+                let last = self.intern(list.last_cdr());
+                self.intern_items(list.items(), last)
+            }
+        }
+    }
+
+    /// Interns a list.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn intern_list(&mut self, list: &List<SVMCell>) -> Interned {
+        let nil = self.nil();
+        self.intern_items(list, nil)
+    }
+
+    /// Interns a chain of `cons` cells whose cars are the items of `items`,
+    /// and whose final cdr is `last`.
+    fn intern_items(&mut self, items: &List<SVMCell>, last: Interned) -> Interned {
+        let items: Vec<Interned> = items.iter()
+            .map(|item| self.intern(item))
+            .collect();
+        items.into_iter().rev()
+            .fold(last, |cdr, car| self.cons(car, cdr))
+    }
+
+    /// Returns the number of distinct nodes interned.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn len(&self) -> usize { self.nodes.len() }
+
+    /// Returns true if nothing has been interned.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn is_empty(&self) -> bool { self.nodes.is_empty() }
+
+    /// Forgets every node which is no longer used outside the interner,
+    /// returning the number of nodes forgotten.
+    ///
+    /// Cells interned afterwards which are equal to a forgotten node get
+    /// a new node.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "intern", issue = "94") )]
+    pub fn purge(&mut self) -> usize {
+        let before = self.nodes.len();
+        loop {
+            // forgetting a node may leave its children unused as well
+            let len = self.nodes.len();
+            self.nodes.retain(|_, node| Rc::strong_count(&node.0) > 1);
+            if self.nodes.len() == len {
+                return before - len;
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "intern", issue = "94") )]
+impl fmt::Debug for Interner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Interner of {} nodes", self.nodes.len())
+    }
+}
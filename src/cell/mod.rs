@@ -2,11 +2,12 @@ pub use self::SVMCell::*;
 pub use self::Atom::*;
 
 use super::List;
+use super::list::{DottedList, Stack};
 
-use std::{fmt,ops};
+use std::{fmt,ops,char};
 
-#[cfg(test)]
-mod tests;
+#[cfg(any(test, feature = "arbitrary"))]
+use quickcheck::{Arbitrary, Gen};
 
 #[macro_export]
 #[cfg_attr(feature = "unstable",
@@ -22,6 +23,13 @@ macro_rules! list_cell {
 
 }
 
+#[cfg(test)]
+mod tests;
+
+mod intern;
+
+pub use self::intern::{Interner, Interned, InternedNode};
+
 #[derive(PartialEq,Clone)]
 #[cfg_attr(feature = "unstable",
     stable(feature="vm_core", since="0.1.0") )]
@@ -34,14 +42,277 @@ pub enum SVMCell {
     ListCell(Box<List<SVMCell>>),
     #[cfg_attr(feature = "unstable",
         stable(feature="vm_core", since="0.1.0") )]
-    InstCell(Inst)
+    InstCell(Inst),
+    /// Byte string cell.
+    ///
+    /// Holds a buffer of raw bytes, such as the contents of a file,
+    /// without the overhead of a list of `UInt` atoms.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="bytes", issue = "94") )]
+    BytesCell(Vec<u8>),
+    /// Improper list cell, such as the dotted pair `(a . b)`.
+    ///
+    /// The final cdr of a dotted cell is never a list, nor the `NIL`
+    /// instruction, which is encoded the same way as the end of a list;
+    /// use `SVMCell::cons()` or `SVMCell::dotted()` to build cells with
+    /// arbitrary cdrs, which keeps them that way. Encoding a dotted cell
+    /// with such a cdr fails.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="dotted", issue = "94") )]
+    DottedCell(Box<DottedList<SVMCell>>)
+}
+
+impl SVMCell {
+    /// Makes a `cons` cell whose car is `car` and whose cdr is `cdr`.
+    ///
+    /// If `cdr` is a list or the `NIL` instruction, the result is a list
+    /// with `car` prepended; otherwise, it is a dotted pair, or a longer
+    /// improper list if `cdr` is one.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::cell::SVMCell;
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Atom::*;
+    /// # use seax_util::cell::Inst::NIL;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let pair = SVMCell::cons(AtomCell(UInt(1)), AtomCell(UInt(3)));
+    /// assert_eq!(pair.to_string(), "(1 . 3)");
+    ///
+    /// let list = SVMCell::cons(AtomCell(UInt(1)), list_cell![AtomCell(UInt(3))]);
+    /// assert_eq!(list, list_cell![AtomCell(UInt(1)), AtomCell(UInt(3))]);
+    ///
+    /// let list = SVMCell::cons(AtomCell(UInt(1)), InstCell(NIL));
+    /// assert_eq!(list, list_cell![AtomCell(UInt(1))]);
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="dotted", issue = "94") )]
+    pub fn cons(car: SVMCell, cdr: SVMCell) -> SVMCell {
+        SVMCell::dotted(List::new().push(car), cdr)
+    }
+
+    /// Makes a chain of `cons` cells whose cars are the items of `items`,
+    /// and whose final cdr is `cdr`.
+    ///
+    /// If `cdr` is a list, the result is a list of the items of both. The
+    /// `NIL` instruction is encoded the same way as the end of a list, so
+    /// it is treated as the empty list. If `items` is empty, the result is
+    /// `cdr` itself.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="dotted", issue = "94") )]
+    pub fn dotted(mut items: List<SVMCell>, cdr: SVMCell) -> SVMCell {
+        let mut cdr = cdr;
+        loop {
+            match cdr {
+                ListCell(list) => {
+                    items.append_list(*list);
+                    return ListCell(Box::new(items));
+                },
+                InstCell(Inst::NIL) if !items.is_empty() =>
+                    return ListCell(Box::new(items)),
+                // a dotted cell built directly may itself end in a list
+                DottedCell(dotted) => {
+                    let (rest, last) = dotted.into_parts();
+                    items.append_list(rest);
+                    cdr = last;
+                },
+                last => return match DottedList::new(items, last) {
+                    Ok(dotted) => DottedCell(Box::new(dotted)),
+                    Err(last) => last
+                }
+            }
+        }
+    }
+
+    /// Takes apart a `cons` cell, returning its car and its cdr.
+    ///
+    /// Returns `None` if this cell is not a non-empty list or an improper
+    /// list.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::cell::SVMCell;
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Atom::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let pair = SVMCell::cons(AtomCell(UInt(1)), AtomCell(UInt(3)));
+    /// assert_eq!(pair.uncons(), Some((AtomCell(UInt(1)), AtomCell(UInt(3)))));
+    ///
+    /// let list = list_cell![AtomCell(UInt(1))];
+    /// assert_eq!(list.uncons(), Some((AtomCell(UInt(1)), list_cell![])));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="dotted", issue = "94") )]
+    pub fn uncons(self) -> Option<(SVMCell, SVMCell)> {
+        match self {
+            ListCell(list) => list.pop()
+                .map(|(car, cdr)| (car, ListCell(Box::new(cdr)))),
+            DottedCell(dotted) => {
+                let (items, last) = dotted.into_parts();
+                items.pop().map(|(car, rest)| match DottedList::new(rest, last) {
+                    Ok(cdr) => (car, DottedCell(Box::new(cdr))),
+                    Err(last) => (car, last)
+                })
+            },
+            _ => None
+        }
+    }
+
+    /// Borrows the contents of a byte string cell.
+    ///
+    /// Returns `None` if this cell is not a `BytesCell`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use seax_util::cell::SVMCell;
+    /// let cell = SVMCell::from(vec![0xDE, 0xAD]);
+    /// assert_eq!(cell.as_bytes(), Some(&[0xDE, 0xAD][..]));
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="bytes", issue = "94") )]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            BytesCell(ref bytes) => Some(bytes),
+            _ => None
+        }
+    }
+
+    /// Converts this cell into a `Vec<u8>`.
+    ///
+    /// Byte string cells are unwrapped directly. A list cell is also
+    /// accepted if every item in it is a `UInt` atom that fits in a
+    /// byte, which is how byte data was represented prior to the
+    /// introduction of `BytesCell`. Any other cell is handed back
+    /// unchanged in the `Err` case.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::cell::SVMCell;
+    /// # use seax_util::cell::SVMCell::*;
+    /// # use seax_util::cell::Atom::*;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let cell = SVMCell::from(vec![1, 2, 3]);
+    /// assert_eq!(cell.into_bytes(), Ok(vec![1, 2, 3]));
+    ///
+    /// let cell = list_cell![AtomCell(UInt(1)), AtomCell(UInt(2))];
+    /// assert_eq!(cell.into_bytes(), Ok(vec![1, 2]));
+    ///
+    /// let cell = AtomCell(SInt(-1));
+    /// assert_eq!(cell.clone().into_bytes(), Err(cell));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="bytes", issue = "94") )]
+    pub fn into_bytes(self) -> Result<Vec<u8>, SVMCell> {
+        match self {
+            BytesCell(bytes) => Ok(bytes),
+            ListCell(list) => {
+                let bytes = list.iter()
+                    .map(|cell| match *cell {
+                        AtomCell(UInt(b)) if b <= 0xFF => Some(b as u8),
+                        _ => None
+                    })
+                    .collect::<Option<Vec<u8>>>();
+                bytes.ok_or(ListCell(list))
+            },
+            other => Err(other)
+        }
+    }
+}
+
+/// Type tags for SVM cells.
+///
+/// These are the values pushed onto the stack by the `TYPE` instruction.
+/// The tags for atoms and byte strings match the type tags used to
+/// identify those constants in Seax bytecode.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature="typeinfo", issue = "94") )]
+pub enum CellType {
+    /// A list (`cons` cell or `nil`)
+    List = 0x00,
+    /// An unsigned integer atom
+    UInt = 0x01,
+    /// A signed integer atom
+    SInt = 0x02,
+    /// A character atom
+    Char = 0x03,
+    /// A floating-point atom
+    Float = 0x04,
+    /// A byte string
+    Bytes = 0x05,
+    /// A function closure.
+    ///
+    /// Closures are constructed by the virtual machine at run time, so
+    /// no cell defined in this crate has this type; it is reserved for
+    /// the VM's closure representation.
+    Closure = 0x0E,
+    /// An instruction
+    Inst = 0x0F
+}
+
+impl SVMCell {
+    /// Returns the type of this cell.
+    ///
+    /// # Examples
+    /// ```
+    /// # use seax_util::cell::{CellType, SVMCell};
+    /// # use seax_util::cell::Atom::*;
+    /// assert_eq!(SVMCell::AtomCell(Float(1.5)).cell_type(), CellType::Float);
+    /// assert_eq!(SVMCell::from(vec![1, 2]).cell_type(), CellType::Bytes);
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="typeinfo", issue = "94") )]
+    pub fn cell_type(&self) -> CellType {
+        match *self {
+            AtomCell(atom) => atom.cell_type(),
+            ListCell(_) | DottedCell(_) => CellType::List,
+            InstCell(_) => CellType::Inst,
+            BytesCell(_) => CellType::Bytes
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bytes", issue = "94") )]
+impl From<Vec<u8>> for SVMCell {
+    fn from(bytes: Vec<u8>) -> SVMCell { BytesCell(bytes) }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bytes", issue = "94") )]
+impl<'a> From<&'a [u8]> for SVMCell {
+    fn from(bytes: &'a [u8]) -> SVMCell { BytesCell(bytes.to_vec()) }
+}
+
+/// Writes a byte string as a hex literal, e.g. `#x"deadbeef"`.
+fn fmt_bytes(bytes: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "#x\"")?;
+    for byte in bytes {
+        write!(f, "{:02x}", byte)?;
+    }
+    write!(f, "\"")
 }
 
 #[cfg_attr(feature = "unstable",
     stable(feature="vm_core", since="0.1.0") )]
 impl fmt::Display for SVMCell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self)
+        match *self {
+            AtomCell(atom) => write!(f, "{}", atom),
+            ListCell(ref list) => write!(f, "{}", list),
+            InstCell(inst) => write!(f, "{:?}", inst),
+            BytesCell(ref bytes) => fmt_bytes(bytes, f),
+            DottedCell(ref dotted) => write!(f, "{}", dotted)
+        }
     }
 }
 
@@ -51,10 +322,12 @@ impl fmt::Debug for SVMCell {
     #[cfg_attr(feature = "unstable",
         stable(feature="vm_core", since="0.1.0") )]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &AtomCell(atom) => write!(f, "{:?}", atom),
-            &ListCell(ref list) => write!(f, "{:?}", list),
-            &InstCell(inst) => write!(f, "{:?}", inst)
+        match *self {
+            AtomCell(atom) => write!(f, "{:?}", atom),
+            ListCell(ref list) => write!(f, "{:?}", list),
+            InstCell(inst) => write!(f, "{:?}", inst),
+            BytesCell(ref bytes) => fmt_bytes(bytes, f),
+            DottedCell(ref dotted) => write!(f, "{:?}", dotted)
         }
     }
 }
@@ -83,6 +356,90 @@ pub enum Atom {
         stable(feature="vm_core", since="0.1.0") )]
     Char(char)
 }
+
+impl Atom {
+    /// Returns the type of this atom.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="typeinfo", issue = "94") )]
+    pub fn cell_type(&self) -> CellType {
+        match *self {
+            UInt(_)  => CellType::UInt,
+            SInt(_)  => CellType::SInt,
+            Char(_)  => CellType::Char,
+            Float(_) => CellType::Float
+        }
+    }
+
+    /// Converts an integer atom to a float atom (`ITOF`).
+    ///
+    /// Returns `None` if this atom is not an integer.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="typeinfo", issue = "94") )]
+    pub fn int_to_float(self) -> Option<Atom> {
+        match self {
+            UInt(a) => Some(Float(a as f64)),
+            SInt(a) => Some(Float(a as f64)),
+            _       => None
+        }
+    }
+
+    /// Converts a float atom to a signed integer atom (`FTOI`).
+    ///
+    /// The fractional part is truncated. Returns `None` if this atom
+    /// is not a float, or if it is NaN or out of the range of a sint.
+    ///
+    /// # Examples
+    /// ```
+    /// # use seax_util::cell::Atom::*;
+    /// assert_eq!(Float(-2.75).float_to_int(), Some(SInt(-2)));
+    /// assert_eq!(Float(1e300).float_to_int(), None);
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="typeinfo", issue = "94") )]
+    pub fn float_to_int(self) -> Option<Atom> {
+        match self {
+            // 2^63 is the first float too large to fit in an i64,
+            // and NaN is never contained in a range
+            Float(a) if (-9223372036854775808.0..9223372036854775808.0)
+                            .contains(&a) => Some(SInt(a as i64)),
+            _ => None
+        }
+    }
+
+    /// Converts a char atom to a uint atom holding its code point (`CTOI`).
+    ///
+    /// Returns `None` if this atom is not a char.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="typeinfo", issue = "94") )]
+    pub fn char_to_int(self) -> Option<Atom> {
+        match self {
+            Char(c) => Some(UInt(c as u64)),
+            _       => None
+        }
+    }
+
+    /// Converts an integer atom to the char with that code point (`ITOC`).
+    ///
+    /// Returns `None` if this atom is not an integer, or if it is not a
+    /// valid Unicode scalar value.
+    ///
+    /// # Examples
+    /// ```
+    /// # use seax_util::cell::Atom::*;
+    /// assert_eq!(UInt(97).int_to_char(), Some(Char('a')));
+    /// assert_eq!(SInt(-1).int_to_char(), None);
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature="typeinfo", issue = "94") )]
+    pub fn int_to_char(self) -> Option<Atom> {
+        let code = match self {
+            UInt(a) if a <= u32::MAX as u64 => a as u32,
+            SInt(a) if a >= 0 && a <= u32::MAX as i64 => a as u32,
+            _ => return None
+        };
+        char::from_u32(code).map(Char)
+    }
+}
 #[cfg_attr(feature = "unstable",
     stable(feature="vm_core", since="0.1.0") )]
 impl fmt::Display for Atom {
@@ -206,6 +563,117 @@ impl ops::Rem for Atom {
 
 }
 
+macro_rules! impl_bitwise {
+    ($name:ident, $symbol:tt) => {
+        fn $name(self, other: Atom) -> Option<Atom> {
+            match (self, other) {
+                // same type:  no coercion
+                (SInt(a), SInt(b))      => Some(SInt(e!(a $symbol b))),
+                (UInt(a), UInt(b))      => Some(UInt(e!(a $symbol b))),
+                // uint & sint: coerce to sint
+                (UInt(a), SInt(b))      => Some(SInt(e!(a as i64 $symbol b))),
+                (SInt(a), UInt(b))      => Some(SInt(e!(a $symbol b as i64))),
+                // bitwise operations are only defined on integers
+                _                       => None
+            }
+        }
+    }
+}
+
+/// Converts an integer atom to a shift amount, if it is a valid one.
+fn shift_amount(atom: Atom) -> Option<u32> {
+    match atom {
+        UInt(n) if n < 64 => Some(n as u32),
+        SInt(n) if (0..64).contains(&n) => Some(n as u32),
+        _ => None
+    }
+}
+
+/// Bitwise AND of two integer atoms.
+///
+/// Unlike the arithmetic operators, the bitwise operators are only
+/// defined on integers, so the result is `None` if either operand is
+/// a float or a character. Mixing signed and unsigned integers
+/// produces a signed integer.
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bitwise", issue = "94") )]
+impl ops::BitAnd for Atom {
+    type Output = Option<Atom>;
+    impl_bitwise!(bitand, &);
+}
+
+/// Bitwise OR of two integer atoms.
+///
+/// The result is `None` if either operand is not an integer.
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bitwise", issue = "94") )]
+impl ops::BitOr for Atom {
+    type Output = Option<Atom>;
+    impl_bitwise!(bitor, |);
+}
+
+/// Bitwise exclusive OR of two integer atoms.
+///
+/// The result is `None` if either operand is not an integer.
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bitwise", issue = "94") )]
+impl ops::BitXor for Atom {
+    type Output = Option<Atom>;
+    impl_bitwise!(bitxor, ^);
+}
+
+/// Bitwise complement of an integer atom.
+///
+/// The result is `None` if the operand is not an integer.
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bitwise", issue = "94") )]
+impl ops::Not for Atom {
+    type Output = Option<Atom>;
+    fn not(self) -> Option<Atom> {
+        match self {
+            UInt(a) => Some(UInt(!a)),
+            SInt(a) => Some(SInt(!a)),
+            _       => None
+        }
+    }
+}
+
+/// Left shift of an integer atom by another integer atom.
+///
+/// The result is `None` if either operand is not an integer, or if
+/// the shift amount is negative or not less than 64.
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bitwise", issue = "94") )]
+impl ops::Shl for Atom {
+    type Output = Option<Atom>;
+    fn shl(self, other: Atom) -> Option<Atom> {
+        shift_amount(other).and_then(|n| match self {
+            UInt(a) => Some(UInt(a << n)),
+            SInt(a) => Some(SInt(a << n)),
+            _       => None
+        })
+    }
+}
+
+/// Right shift of an integer atom by another integer atom.
+///
+/// Unsigned integers are shifted logically, while signed integers are
+/// shifted arithmetically (preserving the sign). The result is `None`
+/// if either operand is not an integer, or if the shift amount is
+/// negative or not less than 64.
+#[cfg_attr(feature = "unstable",
+    unstable(feature="bitwise", issue = "94") )]
+impl ops::Shr for Atom {
+    type Output = Option<Atom>;
+    fn shr(self, other: Atom) -> Option<Atom> {
+        shift_amount(other).and_then(|n| match self {
+            UInt(a) => Some(UInt(a >> n)),
+            SInt(a) => Some(SInt(a >> n)),
+            _       => None
+        })
+    }
+}
+
 /// SVM instruction types.
 ///
 /// Each SVM instruction will be described using operational
@@ -463,4 +931,242 @@ pub enum Inst {
     #[cfg_attr(feature = "unstable",
         unstable(feature = "callcc", issue = "69"))]
     APCC = 0x04,
+    /// `and`: bitwise `and`
+    ///
+    /// Pops two integers off of the stack and pushes their bitwise AND.
+    ///
+    /// __Operational semantics__: `(a.b.s, e, AND.c, d) → ((b & a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "bitwise", issue = "94"))]
+    AND = 0x1E,
+    /// `or`: bitwise `or`
+    ///
+    /// Pops two integers off of the stack and pushes their bitwise OR.
+    ///
+    /// __Operational semantics__: `(a.b.s, e, OR.c, d) → ((b | a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "bitwise", issue = "94"))]
+    OR = 0x1F,
+    /// `xor`: bitwise e`x`clusive `or`
+    ///
+    /// Pops two integers off of the stack and pushes their bitwise XOR.
+    ///
+    /// __Operational semantics__: `(a.b.s, e, XOR.c, d) → ((b ^ a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "bitwise", issue = "94"))]
+    XOR = 0x20,
+    /// `not`: bitwise `not`
+    ///
+    /// Pops an integer off of the stack and pushes its bitwise complement.
+    ///
+    /// __Operational semantics__: `(a.s, e, NOT.c, d) → ((!a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "bitwise", issue = "94"))]
+    NOT = 0x21,
+    /// `shl`: `sh`ift `l`eft
+    ///
+    /// Pops a shift amount and then an integer off of the stack, and pushes
+    /// the integer shifted left by that many bits. The shift amount must be
+    /// between 0 and 63, inclusive.
+    ///
+    /// __Operational semantics__: `(n.a.s, e, SHL.c, d) → ((a << n).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "bitwise", issue = "94"))]
+    SHL = 0x22,
+    /// `shr`: `sh`ift `r`ight
+    ///
+    /// Pops a shift amount and then an integer off of the stack, and pushes
+    /// the integer shifted right by that many bits. Unsigned integers are
+    /// shifted logically and signed integers arithmetically. The shift
+    /// amount must be between 0 and 63, inclusive.
+    ///
+    /// __Operational semantics__: `(n.a.s, e, SHR.c, d) → ((a >> n).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "bitwise", issue = "94"))]
+    SHR = 0x23,
+    /// `type`: push `type` tag
+    ///
+    /// Pops an item from the stack and pushes a uint atom identifying its
+    /// type. The values of the type tags are given by `CellType`.
+    ///
+    /// __Operational semantics__: `(a.s, e, TYPE.c, d) → ((type a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    TYPE = 0x24,
+    /// `isuint`: test if unsigned integer
+    ///
+    /// Pops an item from the stack and returns true if it is a uint atom,
+    /// false otherwise.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ISUINT = 0x25,
+    /// `issint`: test if signed integer
+    ///
+    /// Pops an item from the stack and returns true if it is a sint atom,
+    /// false otherwise.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ISSINT = 0x26,
+    /// `isfloat`: test if float
+    ///
+    /// Pops an item from the stack and returns true if it is a float atom,
+    /// false otherwise.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ISFLOAT = 0x27,
+    /// `ischar`: test if char
+    ///
+    /// Pops an item from the stack and returns true if it is a char atom,
+    /// false otherwise.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ISCHAR = 0x28,
+    /// `islist`: test if list
+    ///
+    /// Pops an item from the stack and returns true if it is a list
+    /// (including `nil`), false otherwise.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ISLIST = 0x29,
+    /// `isclos`: test if closure
+    ///
+    /// Pops an item from the stack and returns true if it is a closure
+    /// constructed by `LDF`, false otherwise.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ISCLOS = 0x2A,
+    /// `isbytes`: test if byte string
+    ///
+    /// Pops an item from the stack and returns true if it is a byte string,
+    /// false otherwise.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ISBYTES = 0x2B,
+    /// `itof`: `i`nteger `to` `f`loat
+    ///
+    /// Pops an integer from the stack and pushes it converted to a float.
+    ///
+    /// __Operational semantics__: `(a.s, e, ITOF.c, d) → ((float a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ITOF = 0x2C,
+    /// `ftoi`: `f`loat `to` `i`nteger
+    ///
+    /// Pops a float from the stack and pushes it converted to a sint,
+    /// truncating any fractional part.
+    ///
+    /// __Operational semantics__: `(a.s, e, FTOI.c, d) → ((sint a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    FTOI = 0x2D,
+    /// `ctoi`: `c`haracter `to` `i`nteger
+    ///
+    /// Pops a char from the stack and pushes its code point as a uint.
+    ///
+    /// __Operational semantics__: `(a.s, e, CTOI.c, d) → ((uint a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    CTOI = 0x2E,
+    /// `itoc`: `i`nteger `to` `c`haracter
+    ///
+    /// Pops an integer from the stack and pushes the char with that code
+    /// point. The integer must be a valid Unicode scalar value.
+    ///
+    /// __Operational semantics__: `(a.s, e, ITOC.c, d) → ((char a).s, e, c, d)`
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "typeinfo", issue = "94"))]
+    ITOC = 0x2F,
+}
+
+/// Generates random cells for property tests.
+///
+/// Generated lists are never empty and generated floats are never NaN, so
+/// every generated cell is equal to itself. The final cdr of a generated
+/// improper list is an atom, a byte string or an instruction. Lists are
+/// nested less deeply as the generator's size shrinks.
+#[cfg(any(test, feature = "arbitrary"))]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arbitrary", issue = "94") )]
+impl Arbitrary for SVMCell {
+    fn arbitrary(g: &mut Gen) -> SVMCell {
+        match u8::arbitrary(g) % 9 {
+            0..=2 => InstCell(Inst::arbitrary(g)),
+            3..=5 => AtomCell(Atom::arbitrary(g)),
+            6 => BytesCell(Vec::arbitrary(g)),
+            7 if g.size() > 1 => {
+                let mut smaller = Gen::new(g.size() / 2);
+                let list = List::arbitrary(&mut smaller);
+                ListCell(Box::new(
+                    List::Cons(SVMCell::arbitrary(&mut smaller),
+                               Box::new(list))))
+            },
+            8 if g.size() > 1 => {
+                let mut smaller = Gen::new(g.size() / 2);
+                let list = List::arbitrary(&mut smaller);
+                let last = match u8::arbitrary(g) % 3 {
+                    0 => AtomCell(Atom::arbitrary(g)),
+                    1 => BytesCell(Vec::arbitrary(g)),
+                    _ => InstCell(Inst::arbitrary(g))
+                };
+                SVMCell::dotted(list.push(SVMCell::arbitrary(&mut smaller)), last)
+            },
+            _ => AtomCell(Atom::arbitrary(g))
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = SVMCell>> {
+        match *self {
+            AtomCell(ref atom) => Box::new(atom.shrink().map(AtomCell)),
+            BytesCell(ref bytes) => Box::new(bytes.shrink().map(BytesCell)),
+            ListCell(ref list) => Box::new(
+                list.shrink()
+                    .filter(|list| !list.is_empty())
+                    .map(ListCell)),
+            DottedCell(ref dotted) => Box::new(
+                ::std::iter::once(dotted.car().clone())),
+            InstCell(_) => Box::new(::std::iter::empty())
+        }
+    }
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arbitrary", issue = "94") )]
+impl Arbitrary for Atom {
+    fn arbitrary(g: &mut Gen) -> Atom {
+        match u8::arbitrary(g) % 4 {
+            0 => UInt(u64::arbitrary(g)),
+            1 => SInt(i64::arbitrary(g)),
+            2 => Char(char::arbitrary(g)),
+            _ => match f64::arbitrary(g) {
+                f if f.is_nan() => Float(0.0),
+                f => Float(f)
+            }
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Atom>> {
+        match *self {
+            UInt(n) => Box::new(n.shrink().map(UInt)),
+            SInt(n) => Box::new(n.shrink().map(SInt)),
+            Char(c) => Box::new(c.shrink().map(Char)),
+            Float(f) => Box::new(f.shrink().filter(|f| !f.is_nan()).map(Float))
+        }
+    }
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arbitrary", issue = "94") )]
+impl Arbitrary for Inst {
+    fn arbitrary(g: &mut Gen) -> Inst {
+        use self::Inst::*;
+        *g.choose(&[
+            NIL, LDC, LD, LDF, JOIN, AP, RET, DUM, RAP, SEL, ADD, SUB, MUL,
+            DIV, FDIV, MOD, EQ, GT, GTE, LT, LTE, ATOM, CAR, CDR, CONS, NULL,
+            STOP, READC, WRITEC, APCC, AND, OR, XOR, NOT, SHL, SHR, TYPE,
+            ISUINT, ISSINT, ISFLOAT, ISCHAR, ISLIST, ISCLOS, ISBYTES, ITOF,
+            FTOI, CTOI, ITOC
+        ]).unwrap()
+    }
 }
@@ -1,5 +1,10 @@
-use super::Atom;
+use super::{Atom,SVMCell,CellType,Interner};
 use super::Atom::*;
+use super::SVMCell::*;
+use super::Inst::*;
+use ::list::List::{Cons,Nil};
+use ::list::DottedList;
+use std::collections::HashSet;
 #[test]
 fn test_atom_show () {
     let mut a: Atom;
@@ -23,3 +28,217 @@ fn test_atom_show () {
     assert_eq!(format!("{}", a), "1");
 
 }
+
+#[test]
+fn test_bytes_show () {
+    let cell = BytesCell(vec![0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(format!("{}", cell), "#x\"deadbeef\"");
+    assert_eq!(format!("{:?}", cell), "#x\"deadbeef\"");
+
+    let empty = BytesCell(vec![]);
+    assert_eq!(format!("{}", empty), "#x\"\"");
+}
+
+#[test]
+fn test_cell_show () {
+    let cell = list_cell![InstCell(LDC), AtomCell(UInt(1)), BytesCell(vec![0x0A])];
+    assert_eq!(format!("{}", cell), "(LDC, 1, #x\"0a\")");
+    assert_eq!(format!("{}", list_cell![]), "()");
+}
+
+#[test]
+fn test_bytes_from_list () {
+    let cell = list_cell![AtomCell(UInt(0x0A)), AtomCell(UInt(0xFF))];
+    assert_eq!(cell.into_bytes(), Ok(vec![0x0A, 0xFF]));
+
+    let cell = list_cell![AtomCell(UInt(0x100))];
+    assert_eq!(cell.clone().into_bytes(), Err(cell));
+
+    assert_eq!(SVMCell::from(&b"hi"[..]), BytesCell(vec![b'h', b'i']));
+}
+
+#[test]
+fn test_atom_bitwise () {
+    assert_eq!(UInt(0b1100) & UInt(0b1010), Some(UInt(0b1000)));
+    assert_eq!(UInt(0b1100) | UInt(0b1010), Some(UInt(0b1110)));
+    assert_eq!(UInt(0b1100) ^ UInt(0b1010), Some(UInt(0b0110)));
+    assert_eq!(SInt(-1) & UInt(0xFF), Some(SInt(0xFF)));
+    assert_eq!(!UInt(0), Some(UInt(u64::MAX)));
+    assert_eq!(!SInt(0), Some(SInt(-1)));
+}
+
+#[test]
+fn test_atom_bitwise_non_integer () {
+    assert_eq!(Float(1.0) & UInt(1), None);
+    assert_eq!(UInt(1) | Char('a'), None);
+    assert_eq!(!Float(1.0), None);
+    assert_eq!(!Char('a'), None);
+}
+
+#[test]
+fn test_atom_shift () {
+    assert_eq!(UInt(1) << UInt(4), Some(UInt(16)));
+    assert_eq!(SInt(1) << SInt(63), Some(SInt(i64::MIN)));
+    assert_eq!(UInt(u64::MAX) >> UInt(60), Some(UInt(0xF)));
+    assert_eq!(SInt(-16) >> UInt(2), Some(SInt(-4)));
+    assert_eq!(UInt(1) << UInt(64), None);
+    assert_eq!(UInt(1) >> SInt(-1), None);
+    assert_eq!(Float(1.0) << UInt(1), None);
+    assert_eq!(UInt(1) << Float(1.0), None);
+}
+
+#[test]
+fn test_cell_type () {
+    assert_eq!(AtomCell(UInt(1)).cell_type(), CellType::UInt);
+    assert_eq!(AtomCell(SInt(1)).cell_type(), CellType::SInt);
+    assert_eq!(AtomCell(Char('a')).cell_type(), CellType::Char);
+    assert_eq!(AtomCell(Float(1.0)).cell_type(), CellType::Float);
+    assert_eq!(list_cell![].cell_type(), CellType::List);
+    assert_eq!(InstCell(LDC).cell_type(), CellType::Inst);
+    assert_eq!(BytesCell(vec![]).cell_type(), CellType::Bytes);
+    assert_eq!(CellType::Float as u8, 0x04);
+}
+
+#[test]
+fn test_atom_conversions () {
+    assert_eq!(UInt(3).int_to_float(), Some(Float(3.0)));
+    assert_eq!(SInt(-3).int_to_float(), Some(Float(-3.0)));
+    assert_eq!(Char('a').int_to_float(), None);
+
+    assert_eq!(Float(3.9).float_to_int(), Some(SInt(3)));
+    assert_eq!(Float(f64::NAN).float_to_int(), None);
+    assert_eq!(UInt(3).float_to_int(), None);
+
+    assert_eq!(Char('λ').char_to_int(), Some(UInt(0x3BB)));
+    assert_eq!(UInt(0x3BB).int_to_char(), Some(Char('λ')));
+    assert_eq!(UInt(0xD800).int_to_char(), None);
+    assert_eq!(Float(97.0).int_to_char(), None);
+}
+
+#[test]
+fn test_cons_cells () {
+    let pair = SVMCell::cons(AtomCell(UInt(1)), AtomCell(UInt(3)));
+    assert_eq!(pair.cell_type(), CellType::List);
+    assert_eq!(format!("{}", pair), "(1 . 3)");
+    assert_eq!(format!("{:?}", pair), "(1u . 3u)");
+
+    let improper = SVMCell::cons(AtomCell(UInt(0)), pair.clone());
+    assert_eq!(format!("{}", improper), "(0, 1 . 3)");
+    assert_eq!(improper.clone().uncons(), Some((AtomCell(UInt(0)), pair.clone())));
+    assert_eq!(pair.clone().uncons(), Some((AtomCell(UInt(1)), AtomCell(UInt(3)))));
+
+    // a list cdr makes a proper list
+    let list = SVMCell::cons(AtomCell(UInt(0)), list_cell![AtomCell(UInt(1))]);
+    assert_eq!(list, list_cell![AtomCell(UInt(0)), AtomCell(UInt(1))]);
+    assert_eq!(list.uncons(), Some((AtomCell(UInt(0)), list_cell![AtomCell(UInt(1))])));
+    assert_eq!(list_cell![].uncons(), None);
+    assert_eq!(AtomCell(UInt(0)).uncons(), None);
+}
+
+#[test]
+fn test_dotted_cells () {
+    let improper = SVMCell::dotted(
+        list!(AtomCell(UInt(0))),
+        SVMCell::cons(AtomCell(UInt(1)), AtomCell(UInt(2))));
+    assert_eq!(improper, SVMCell::dotted(
+        list!(AtomCell(UInt(0)), AtomCell(UInt(1))), AtomCell(UInt(2))));
+    assert_eq!(SVMCell::dotted(Nil, AtomCell(UInt(2))), AtomCell(UInt(2)));
+    assert_eq!(SVMCell::dotted(list!(AtomCell(UInt(0))), list_cell![]),
+               list_cell![AtomCell(UInt(0))]);
+    assert_eq!(SVMCell::dotted(list!(AtomCell(UInt(0))), InstCell(NIL)),
+               list_cell![AtomCell(UInt(0))]);
+    assert_eq!(SVMCell::dotted(Nil, InstCell(NIL)), InstCell(NIL));
+    // a directly built dotted cdr ending in a list is flattened too
+    let nested = DottedCell(Box::new(DottedList::pair(
+        AtomCell(UInt(1)), list_cell![AtomCell(UInt(2))])));
+    assert_eq!(SVMCell::dotted(list!(AtomCell(UInt(0))), nested),
+               list_cell![AtomCell(UInt(0)), AtomCell(UInt(1)), AtomCell(UInt(2))]);
+}
+
+#[test]
+fn test_intern_equal_cells () {
+    let mut interner = Interner::new();
+    let cell = list_cell![InstCell(LDC), list_cell![AtomCell(SInt(1)), AtomCell(SInt(2))]];
+    let a = interner.intern(&cell);
+    let b = interner.intern(&cell.clone());
+    assert_eq!(a, b);
+    assert!(a != interner.intern(&list_cell![InstCell(LDC)]));
+    assert!(interner.intern(&AtomCell(UInt(1))) != interner.intern(&AtomCell(SInt(1))));
+
+    let set: HashSet<_> = vec![a.clone(), b, interner.intern(&list_cell![])]
+        .into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_intern_shares_structure () {
+    let mut interner = Interner::new();
+    let a = interner.intern_list(&list!(InstCell(LDC), AtomCell(SInt(1)), InstCell(NIL)));
+    assert_eq!(interner.len(), 7);
+    let b = interner.intern_list(&list!(InstCell(LDF), AtomCell(SInt(1)), InstCell(NIL)));
+    // only `LDF` and the `cons` cell holding it are new
+    assert_eq!(interner.len(), 9);
+    assert_eq!(a.cdr(), b.cdr());
+
+    let nested = interner.intern(&list_cell![
+        list_cell![InstCell(NIL)], list_cell![InstCell(NIL)]]);
+    assert_eq!(nested.car(), nested.cdr().unwrap().car());
+}
+
+#[test]
+fn test_intern_round_trip () {
+    let mut interner = Interner::new();
+    let cells = vec![
+        AtomCell(UInt(1)),
+        AtomCell(Float(1.5)),
+        AtomCell(Char('a')),
+        InstCell(ADD),
+        BytesCell(vec![1, 2, 3]),
+        list_cell![],
+        list_cell![AtomCell(SInt(-1)), list_cell![], BytesCell(vec![])],
+        SVMCell::cons(AtomCell(UInt(1)), AtomCell(UInt(2))),
+        SVMCell::dotted(list!(list_cell![InstCell(NIL)]), BytesCell(vec![0]))
+    ];
+    for cell in cells {
+        assert_eq!(interner.intern(&cell).to_cell(), cell);
+    }
+    let list = list!(AtomCell(UInt(1)), AtomCell(UInt(2)));
+    assert_eq!(interner.intern_list(&list).to_list(), Some(list));
+    let pair = interner.intern(&SVMCell::cons(AtomCell(UInt(1)), AtomCell(UInt(2))));
+    assert_eq!(pair.to_list(), None);
+    assert_eq!(format!("{}", pair), "(1 . 2)");
+}
+
+#[test]
+fn test_intern_floats () {
+    let mut interner = Interner::new();
+    assert!(interner.atom(Float(0.0)) != interner.atom(Float(-0.0)));
+    assert_eq!(interner.atom(Float(::std::f64::NAN)),
+               interner.atom(Float(::std::f64::NAN)));
+}
+
+#[test]
+fn test_intern_purge () {
+    let mut interner = Interner::new();
+    let kept = interner.intern(&list_cell![InstCell(NIL)]);
+    interner.intern(&list_cell![InstCell(LDC), list_cell![AtomCell(UInt(1))]]);
+    assert_eq!(interner.len(), 8);
+    // everything but `kept`, `NIL` and `nil` is unused
+    assert_eq!(interner.purge(), 5);
+    assert_eq!(interner.len(), 3);
+    assert_eq!(interner.intern(&list_cell![InstCell(NIL)]), kept);
+    drop(kept);
+    assert_eq!(interner.purge(), 3);
+    assert!(interner.is_empty());
+}
+
+#[test]
+fn test_intern_drop_long () {
+    let list: ::list::List<SVMCell> = (0..1000000).map(|i| AtomCell(UInt(i))).collect();
+    let mut interner = Interner::new();
+    let interned = interner.intern_list(&list);
+    drop(list);
+    drop(interner);
+    assert_eq!(interned.car().unwrap().to_cell(), AtomCell(UInt(0)));
+    drop(interned);
+}
@@ -0,0 +1,486 @@
+//! Garbage-collected cell heaps
+//! ============================
+//!
+//! `List<T>` and `SVMCell` are tree-shaped: every list owns its cells, so
+//! they can't express the cyclic structures built by `DUM` and `RAP`, in
+//! which a recursive closure's environment contains the closure itself.
+//!
+//! A `Heap` instead holds `cons` cells in a `ConsArena`, addressed by
+//! `CellHandle`s, and frees them with a mark-and-sweep collector. The car
+//! and cdr of a heap cell are both `Value`s, which are either immediate
+//! atoms, instructions, and byte strings, or handles to other heap cells,
+//! so any cell may refer to any other, including itself. The VM's `s`,
+//! `e`, `c` and `d` registers are the heap's roots: `collect()` frees
+//! every cell which can't be reached from one of them, however many
+//! cycles it is part of.
+//!
+//! A cell whose cdr is neither `nil` nor another cell ends an improper
+//! list, such as the `(level . index)` pairs loaded by `LD`.
+
+use std::collections::HashSet;
+use std::{fmt, mem};
+
+use ::cell::{Atom, Inst, SVMCell};
+use ::cell::SVMCell::*;
+use ::list::{ConsArena, CellHandle, ListBuilder};
+use ::list::List::Nil;
+
+#[cfg(test)]
+mod tests;
+
+/// A value held in a heap cell or a heap register.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+pub enum Value {
+    /// An atom.
+    Atom(Atom),
+    /// An instruction.
+    Inst(Inst),
+    /// A byte string.
+    Bytes(Vec<u8>),
+    /// The empty list.
+    Nil,
+    /// A non-empty list, starting at the given cell.
+    List(CellHandle)
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl Value {
+    /// Returns the handle of the list's first cell, if this is a
+    /// non-empty list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn handle(&self) -> Option<CellHandle> {
+        match *self {
+            Value::List(cell) => Some(cell),
+            _ => None
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl From<Atom> for Value {
+    fn from(atom: Atom) -> Value { Value::Atom(atom) }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl From<Inst> for Value {
+    fn from(inst: Inst) -> Value { Value::Inst(inst) }
+}
+
+/// The contents of a heap cell.
+///
+/// The arena's own cdr links are only able to refer to other cells, so
+/// they are left unused, and both halves of the cell are kept here.
+#[derive(Debug)]
+struct Pair {
+    car: Value,
+    cdr: Value
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl From<Option<CellHandle>> for Value {
+    fn from(cell: Option<CellHandle>) -> Value {
+        cell.map_or(Value::Nil, Value::List)
+    }
+}
+
+/// The VM registers which are the roots of a heap.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+pub enum Register {
+    /// The stack register.
+    S = 0,
+    /// The environment register.
+    E = 1,
+    /// The control register.
+    C = 2,
+    /// The dump register.
+    D = 3
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl Register {
+    /// Every register, in order.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub const ALL: [Register; 4] = [Register::S, Register::E, Register::C, Register::D];
+}
+
+/// Statistics about a heap's allocations and collections.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+pub struct HeapStats {
+    /// The number of cells currently allocated.
+    pub live: usize,
+    /// The number of slots in the heap, both allocated and free.
+    pub slots: usize,
+    /// The total number of cells ever allocated.
+    pub allocated: usize,
+    /// The total number of cells freed by collections.
+    pub freed: usize,
+    /// The number of collections run.
+    pub collections: usize,
+    /// The number of cells freed by the most recent collection.
+    pub last_freed: usize
+}
+
+/// A garbage-collected heap of `cons` cells.
+///
+/// # Examples
+///
+/// ```
+/// # use seax_util::heap::{Heap, Register, Value};
+/// # use seax_util::cell::Atom::SInt;
+/// let mut heap = Heap::new();
+/// // a list which contains itself
+/// let cell = heap.cons(Value::Atom(SInt(1)), Value::Nil);
+/// heap.set_car(cell, Value::List(cell));
+/// heap.set_root(Register::S, Value::List(cell));
+///
+/// assert_eq!(heap.collect(), 0);
+/// heap.set_root(Register::S, Value::Nil);
+/// assert_eq!(heap.collect(), 1);
+/// assert!(heap.is_empty());
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+pub struct Heap {
+    cells: ConsArena<Pair>,
+    roots: [Value; 4],
+    stats: HeapStats
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl Heap {
+
+    /// Creates an empty heap, whose registers are all `nil`.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn new() -> Heap {
+        Heap::with_capacity(0)
+    }
+
+    /// Creates an empty heap with room for `capacity` cells.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn with_capacity(capacity: usize) -> Heap {
+        Heap {
+            cells: ConsArena::with_capacity(capacity),
+            roots: [Value::Nil, Value::Nil, Value::Nil, Value::Nil],
+            stats: HeapStats::default()
+        }
+    }
+
+    /// Allocates a cell, returning its handle.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn cons(&mut self, car: Value, cdr: Value) -> CellHandle {
+        self.stats.allocated += 1;
+        self.cells.alloc(Pair { car, cdr }, None)
+    }
+
+    /// Allocates a proper list of the given items.
+    ///
+    /// Returns `Value::Nil` if there are no items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn list<I>(&mut self, items: I) -> Value
+    where I: IntoIterator<Item = Value>,
+          I::IntoIter: DoubleEndedIterator {
+        self.list_ending(items, Value::Nil)
+    }
+
+    /// Allocates a list of the given items, whose last cdr is `cdr`.
+    ///
+    /// Returns `cdr` if there are no items.
+    fn list_ending<I>(&mut self, items: I, cdr: Value) -> Value
+    where I: IntoIterator<Item = Value>,
+          I::IntoIter: DoubleEndedIterator {
+        items.into_iter().rev()
+            .fold(cdr, |cdr, car| Value::List(self.cons(car, cdr)))
+    }
+
+    /// Borrows the car of a cell.
+    ///
+    /// # Panics
+    ///
+    /// If the cell has been freed.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn car(&self, cell: CellHandle) -> &Value { &self.cells.car(cell).car }
+
+    /// Borrows the cdr of a cell.
+    ///
+    /// # Panics
+    ///
+    /// If the cell has been freed.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn cdr(&self, cell: CellHandle) -> &Value { &self.cells.car(cell).cdr }
+
+    /// Replaces the car of a cell, returning the old car.
+    ///
+    /// This is how `RAP` ties the knot of a recursive environment.
+    ///
+    /// # Panics
+    ///
+    /// If the cell has been freed.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn set_car(&mut self, cell: CellHandle, car: Value) -> Value {
+        mem::replace(&mut self.cells.car_mut(cell).car, car)
+    }
+
+    /// Replaces the cdr of a cell, returning the old cdr.
+    ///
+    /// # Panics
+    ///
+    /// If the cell has been freed.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn set_cdr(&mut self, cell: CellHandle, cdr: Value) -> Value {
+        mem::replace(&mut self.cells.car_mut(cell).cdr, cdr)
+    }
+
+    /// Returns true if `cell` refers to an allocated cell.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn contains(&self, cell: CellHandle) -> bool {
+        self.cells.contains(cell)
+    }
+
+    /// Borrows the value of a register.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn root(&self, register: Register) -> &Value {
+        &self.roots[register as usize]
+    }
+
+    /// Sets the value of a register, returning its old value.
+    ///
+    /// Cells reachable from the old value are freed by the next
+    /// collection, unless they can still be reached from another register.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn set_root(&mut self, register: Register, value: Value) -> Value {
+        mem::replace(&mut self.roots[register as usize], value)
+    }
+
+    /// Returns the number of cells currently allocated.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn len(&self) -> usize { self.cells.len() }
+
+    /// Returns true if no cells are allocated.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn is_empty(&self) -> bool { self.cells.is_empty() }
+
+    /// Returns statistics about this heap.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn stats(&self) -> HeapStats {
+        HeapStats {
+            live: self.cells.len(),
+            slots: self.cells.num_slots(),
+            ..self.stats
+        }
+    }
+
+    /// Frees every cell which can't be reached from a register, returning
+    /// the number of cells freed.
+    ///
+    /// Handles to the freed cells must not be used afterwards; their
+    /// slots will be reused by later allocations.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn collect(&mut self) -> usize {
+        let mut marked = vec![false; self.cells.num_slots()];
+        let mut pending: Vec<CellHandle> = self.roots.iter()
+            .filter_map(Value::handle)
+            .collect();
+        while let Some(cell) = pending.pop() {
+            if marked[cell.index()] {
+                continue;
+            }
+            marked[cell.index()] = true;
+            let pair = self.cells.car(cell);
+            pending.extend(pair.car.handle());
+            pending.extend(pair.cdr.handle());
+        }
+
+        let garbage: Vec<CellHandle> = self.cells.cells()
+            .filter(|cell| !marked[cell.index()])
+            .collect();
+        for &cell in &garbage {
+            self.cells.free(cell);
+        }
+
+        debug!("[heap] collected {} cells, {} live",
+            garbage.len(), self.cells.len());
+        self.stats.collections += 1;
+        self.stats.freed += garbage.len();
+        self.stats.last_freed = garbage.len();
+        garbage.len()
+    }
+
+    /// Copies a cell into the heap.
+    ///
+    /// Lists are copied iteratively, so the depth to which they are nested
+    /// is not limited by the native stack.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn store(&mut self, cell: &SVMCell) -> Value {
+        // the lists being copied, innermost last: the cells in each which
+        // are still to be copied, last first; the values of those copied
+        // so far; and whether the last cell is the list's final cdr
+        let mut lists: Vec<(Vec<&SVMCell>, Vec<Value>, bool)> = Vec::new();
+        let mut cell = cell;
+        loop {
+            let mut value = match *cell {
+                AtomCell(atom) => Some(Value::Atom(atom)),
+                InstCell(inst) => Some(Value::Inst(inst)),
+                BytesCell(ref bytes) => Some(Value::Bytes(bytes.clone())),
+                ListCell(ref list) => {
+                    let mut cells: Vec<&SVMCell> = list.iter().collect();
+                    cells.reverse();
+                    lists.push((cells, Vec::new(), false));
+                    None
+                },
+                DottedCell(ref list) => {
+                    let mut cells: Vec<&SVMCell> = list.items().iter().collect();
+                    cells.push(list.last_cdr());
+                    cells.reverse();
+                    lists.push((cells, Vec::new(), true));
+                    None
+                }
+            };
+            // add the value to the innermost list, and allocate any lists
+            // which have been copied completely
+            loop {
+                match lists.last_mut() {
+                    Some(&mut (ref mut cells, ref mut values, _)) => {
+                        values.extend(value.take());
+                        if let Some(next) = cells.pop() {
+                            cell = next;
+                            break;
+                        }
+                    },
+                    None => return value.unwrap()
+                }
+                let (_, mut values, dotted) = lists.pop().unwrap();
+                let cdr = if dotted { values.pop().unwrap() } else { Value::Nil };
+                value = Some(self.list_ending(values, cdr));
+            }
+        }
+    }
+
+    /// Copies a value out of the heap, as a cell.
+    ///
+    /// Lists which share cells in the heap are copied separately. Lists
+    /// are copied iteratively, so the depth to which they are nested is
+    /// not limited by the native stack.
+    ///
+    /// # Return Value
+    ///
+    /// The cell, or an error if the value contains a cycle, which can't be
+    /// represented by an `SVMCell`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "heap", issue = "94") )]
+    pub fn load(&self, value: &Value) -> Result<SVMCell, String> {
+        // the cells of every list which is currently being copied
+        let mut path = HashSet::new();
+        // the lists being copied, innermost last: the items copied so far,
+        // the cells visited, and the cell whose car is being copied
+        let mut lists: Vec<(ListBuilder<SVMCell>, Vec<CellHandle>, CellHandle)> =
+            Vec::new();
+        let mut value = value;
+        loop {
+            let mut cell = match *value {
+                Value::List(head) => {
+                    if !path.insert(head) {
+                        return Err(format!("cell {} is part of a cycle", head.index()));
+                    }
+                    lists.push((ListBuilder::new(), vec![head], head));
+                    value = self.car(head);
+                    continue;
+                },
+                ref value => load_immediate(value)
+            };
+            // add the cell to the innermost list, and finish any lists
+            // which end after it
+            loop {
+                let cdr = match lists.last_mut() {
+                    Some(&mut (ref mut items, ref mut visited, ref mut current)) => {
+                        items.push_back(cell);
+                        match *self.cdr(*current) {
+                            Value::List(next) => {
+                                if !path.insert(next) {
+                                    return Err(format!("cell {} is part of a cycle",
+                                                       next.index()));
+                                }
+                                visited.push(next);
+                                *current = next;
+                                value = self.car(next);
+                                break;
+                            },
+                            ref cdr => cdr
+                        }
+                    },
+                    None => return Ok(cell)
+                };
+                let (items, visited, _) = lists.pop().unwrap();
+                for visited in visited {
+                    path.remove(&visited);
+                }
+                cell = SVMCell::dotted(items.build(), load_immediate(cdr));
+            }
+        }
+    }
+}
+
+/// Copies a value which is not a non-empty list out of the heap.
+fn load_immediate(value: &Value) -> SVMCell {
+    match *value {
+        Value::Atom(atom) => AtomCell(atom),
+        Value::Inst(inst) => InstCell(inst),
+        Value::Bytes(ref bytes) => BytesCell(bytes.clone()),
+        Value::Nil => ListCell(Box::new(Nil)),
+        Value::List(_) => unreachable!()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl Default for Heap {
+    fn default() -> Heap { Heap::new() }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+impl fmt::Debug for Heap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Heap")
+            .field("roots", &self.roots)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
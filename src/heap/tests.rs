@@ -0,0 +1,267 @@
+use super::{Heap, HeapStats, Register, Value};
+use ::cell::Atom::*;
+use ::cell::SVMCell;
+use ::cell::SVMCell::*;
+use ::Inst::*;
+use ::list::{DottedList, List};
+use ::list::List::{Cons, Nil};
+
+/// `LDF`: makes a closure `[code env]` of the given code and the current
+/// environment, and pushes it onto the stack.
+fn ldf(heap: &mut Heap, code: &SVMCell) {
+    let code = heap.store(code);
+    let env = heap.root(Register::E).clone();
+    let closure = heap.list(vec![code, env]);
+    let stack = heap.root(Register::S).clone();
+    let stack = heap.cons(closure, stack);
+    heap.set_root(Register::S, Value::List(stack));
+}
+
+/// `DUM`: pushes an empty dummy frame onto the environment.
+fn dum(heap: &mut Heap) {
+    let env = heap.root(Register::E).clone();
+    let frame = heap.cons(Value::Nil, env);
+    heap.set_root(Register::E, Value::List(frame));
+}
+
+/// `RAP`: replaces the dummy frame with the list of closures on the
+/// stack, saving the caller's stack, environment and control on the dump.
+fn rap(heap: &mut Heap, caller_env: Value) {
+    let closures = heap.set_root(Register::S, Value::Nil);
+    let frame = heap.root(Register::E).handle().unwrap();
+    heap.set_car(frame, closures);
+    let control = heap.root(Register::C).clone();
+    let saved = heap.list(vec![Value::Nil, caller_env, control]);
+    let dump = heap.root(Register::D).clone();
+    let dump = heap.cons(saved, dump);
+    heap.set_root(Register::D, Value::List(dump));
+}
+
+/// `RET`: restores the caller's environment from the dump.
+fn ret(heap: &mut Heap) {
+    let dump = heap.root(Register::D).handle().unwrap();
+    let saved = heap.car(dump).handle().unwrap();
+    let env_cell = heap.cdr(saved).handle().unwrap();
+    let env = heap.car(env_cell).clone();
+    let rest = heap.cdr(dump).clone();
+    heap.set_root(Register::E, env);
+    heap.set_root(Register::D, rest);
+}
+
+/// Builds a recursive environment of two mutually recursive closures on
+/// top of a one-frame environment, returning that outer environment.
+fn letrec(heap: &mut Heap) -> Value {
+    let outer = heap.store(&list_cell![list_cell![AtomCell(SInt(1))]]);
+    heap.set_root(Register::E, outer.clone());
+    dum(heap);
+    ldf(heap, &list_cell![InstCell(LD),
+        list_cell![AtomCell(UInt(1)), AtomCell(UInt(2))], InstCell(AP), InstCell(RET)]);
+    ldf(heap, &list_cell![InstCell(LD),
+        list_cell![AtomCell(UInt(1)), AtomCell(UInt(1))], InstCell(AP), InstCell(RET)]);
+    rap(heap, outer.clone());
+    outer
+}
+
+#[test]
+fn test_collect_unreachable() {
+    let mut heap = Heap::new();
+    let kept = heap.list(vec![Value::Atom(UInt(1)), Value::Atom(UInt(2))]);
+    heap.list(vec![Value::Atom(UInt(3)), Value::Inst(ADD)]);
+    heap.set_root(Register::S, kept.clone());
+    assert_eq!(heap.len(), 4);
+    assert_eq!(heap.collect(), 2);
+    assert_eq!(heap.len(), 2);
+    assert_eq!(heap.load(&kept), Ok(list_cell![AtomCell(UInt(1)), AtomCell(UInt(2))]));
+}
+
+#[test]
+fn test_every_register_is_a_root() {
+    for &register in Register::ALL.iter() {
+        let mut heap = Heap::new();
+        let value = heap.list(vec![Value::Atom(SInt(-1))]);
+        heap.set_root(register, value.clone());
+        assert_eq!(heap.collect(), 0);
+        assert_eq!(heap.root(register), &value);
+        heap.set_root(register, Value::Nil);
+        assert_eq!(heap.collect(), 1);
+    }
+}
+
+#[test]
+fn test_shared_cells_are_kept() {
+    let mut heap = Heap::new();
+    let shared = heap.list(vec![Value::Atom(UInt(1))]);
+    let a = heap.cons(shared.clone(), Value::Nil);
+    let b = heap.cons(shared.clone(), Value::Nil);
+    heap.set_root(Register::S, Value::List(a));
+    heap.set_root(Register::D, Value::List(b));
+    assert_eq!(heap.collect(), 0);
+    heap.set_root(Register::S, Value::Nil);
+    assert_eq!(heap.collect(), 1);
+    assert!(!heap.contains(a));
+    assert!(heap.contains(b));
+    assert!(heap.contains(shared.handle().unwrap()));
+}
+
+#[test]
+fn test_recursive_environment() {
+    let mut heap = Heap::new();
+    let outer = letrec(&mut heap);
+    let live = heap.len();
+
+    // the closures' environment contains the closures themselves
+    let frame = heap.root(Register::E).handle().unwrap();
+    let closures = heap.car(frame).handle().unwrap();
+    let closure = heap.car(closures).handle().unwrap();
+    let env = heap.cdr(closure).handle().unwrap();
+    assert_eq!(heap.car(env), &Value::List(frame));
+
+    assert_eq!(heap.collect(), 0);
+    assert_eq!(heap.len(), live);
+    assert!(heap.load(heap.root(Register::E)).unwrap_err().contains("cycle"));
+
+    ret(&mut heap);
+    assert_eq!(heap.root(Register::E), &outer);
+    assert_eq!(heap.collect(), live - 2);
+    assert_eq!(heap.len(), 2);
+    assert!(!heap.contains(frame));
+    assert_eq!(heap.load(&outer), Ok(list_cell![list_cell![AtomCell(SInt(1))]]));
+}
+
+#[test]
+fn test_nested_recursive_environments() {
+    let mut heap = Heap::new();
+    letrec(&mut heap);
+    let inner_frame = heap.root(Register::E).handle().unwrap();
+    dum(&mut heap);
+    ldf(&mut heap, &list_cell![InstCell(RET)]);
+    let caller = Value::List(inner_frame);
+    rap(&mut heap, caller);
+    assert_eq!(heap.collect(), 0);
+
+    // returning from the inner `letrec` frees only its closures
+    let before = heap.len();
+    ret(&mut heap);
+    assert_eq!(heap.root(Register::E), &Value::List(inner_frame));
+    let freed = heap.collect();
+    assert!(freed > 0);
+    assert!(heap.contains(inner_frame));
+    assert_eq!(heap.len(), before - freed);
+
+    ret(&mut heap);
+    heap.collect();
+    assert_eq!(heap.len(), 2);
+}
+
+#[test]
+fn test_heap_stats() {
+    let mut heap = Heap::new();
+    assert_eq!(heap.stats(), HeapStats::default());
+    let kept = heap.list(vec![Value::Atom(UInt(1))]);
+    heap.list(vec![Value::Atom(UInt(2)), Value::Atom(UInt(3))]);
+    heap.set_root(Register::C, kept);
+    heap.collect();
+    heap.list(vec![Value::Atom(UInt(4))]);
+    assert_eq!(heap.stats(), HeapStats {
+        live: 2,
+        slots: 3,
+        allocated: 4,
+        freed: 2,
+        collections: 1,
+        last_freed: 2
+    });
+    heap.collect();
+    let stats = heap.stats();
+    assert_eq!(stats.collections, 2);
+    assert_eq!(stats.freed, 3);
+    assert_eq!(stats.last_freed, 1);
+    assert_eq!(stats.live, 1);
+}
+
+#[test]
+fn test_collection_reuses_slots() {
+    let mut heap = Heap::new();
+    for i in 0..100 {
+        let value = heap.list(vec![Value::Atom(UInt(i)); 10]);
+        heap.set_root(Register::S, value);
+        heap.collect();
+    }
+    assert_eq!(heap.len(), 10);
+    assert!(heap.stats().slots <= 20);
+}
+
+#[test]
+fn test_store_load() {
+    let mut heap = Heap::new();
+    let cell = list_cell![
+        AtomCell(SInt(1)),
+        list_cell![InstCell(LDC), AtomCell(Float(1.5))],
+        BytesCell(vec![1, 2, 3]),
+        ListCell(Box::new(List::new()))
+    ];
+    let value = heap.store(&cell);
+    assert_eq!(heap.len(), 6);
+    assert_eq!(heap.load(&value), Ok(cell));
+    assert_eq!(heap.store(&InstCell(ADD)), Value::Inst(ADD));
+    assert_eq!(heap.load(&Value::Nil), Ok(ListCell(Box::new(List::new()))));
+}
+
+#[test]
+fn test_store_improper_list() {
+    let mut heap = Heap::new();
+    // `(level . index)`
+    let pair = DottedCell(Box::new(
+        DottedList::pair(AtomCell(UInt(1)), AtomCell(UInt(2)))));
+    let value = heap.store(&pair);
+    let cell = value.handle().unwrap();
+    assert_eq!(heap.car(cell), &Value::Atom(UInt(1)));
+    assert_eq!(heap.cdr(cell), &Value::Atom(UInt(2)));
+    assert_eq!(heap.load(&value), Ok(pair.clone()));
+
+    let code = list_cell![InstCell(LD), pair, InstCell(RET)];
+    let value = heap.store(&code);
+    assert_eq!(heap.len(), 5);
+    assert_eq!(heap.load(&value), Ok(code));
+
+    let cell = heap.cons(Value::Inst(ADD), Value::Bytes(vec![1]));
+    assert_eq!(heap.set_cdr(cell, Value::Nil), Value::Bytes(vec![1]));
+    assert_eq!(heap.load(&Value::List(cell)), Ok(list_cell![InstCell(ADD)]));
+}
+
+#[test]
+fn test_store_load_deeply_nested() {
+    const DEPTH: usize = 100000;
+    // dismantles a nested list one level at a time, since dropping it
+    // whole would overflow the stack
+    fn unnest(mut cell: SVMCell) -> usize {
+        let mut depth = 0;
+        while let ListCell(list) = cell {
+            depth += 1;
+            cell = list.into_iter().next().unwrap();
+        }
+        depth
+    }
+    let mut cell = AtomCell(UInt(0));
+    for _ in 0..DEPTH {
+        cell = list_cell![cell];
+    }
+    let mut heap = Heap::new();
+    let value = heap.store(&cell);
+    assert_eq!(unnest(cell), DEPTH);
+    assert_eq!(heap.len(), DEPTH);
+    assert_eq!(unnest(heap.load(&value).unwrap()), DEPTH);
+}
+
+#[test]
+fn test_load_cdr_cycle() {
+    let mut heap = Heap::new();
+    let a = heap.cons(Value::Atom(UInt(1)), Value::Nil);
+    let b = heap.cons(Value::Atom(UInt(2)), Value::List(a));
+    heap.set_cdr(a, Value::List(b));
+    assert!(heap.load(&Value::List(a)).is_err());
+    heap.set_root(Register::S, Value::List(b));
+    assert_eq!(heap.collect(), 0);
+    heap.set_cdr(a, Value::Nil);
+    assert_eq!(heap.load(&Value::List(b)),
+        Ok(list_cell![AtomCell(UInt(2)), AtomCell(UInt(1))]));
+}
@@ -16,20 +16,32 @@
 //! + `list`: Contains the singly-linked list and stack implementations used
 //!   by the Seax VM internally.
 //! + `bytecode`: Contains functions for encoding and decoding Seax VM cells
-//!   to and from Seax bytecode
+//!   to and from Seax bytecode, and for linking separately compiled modules
+//! + `heap`: Contains a garbage-collected heap of cells, which can hold the
+//!   cyclic environments built by recursive closures.
 //! + `compiler_tools`: Contains reusable code for implementing compilers
 //!   targeting the Seax platform, including traits for abstract syntax trees
 //!   and symbol tables.
 
 #[macro_use] extern crate log;
-#[cfg(test)] extern crate quickcheck;
+#[cfg(any(test, feature = "arbitrary"))] extern crate quickcheck;
 extern crate byteorder;
+#[cfg(feature = "signing")] extern crate ed25519_dalek;
+#[cfg(feature = "compression")] extern crate flate2;
 
 /// Contains singly-linked list and stack implementations.
 ///
 /// `List<T>` is a singly-linked `cons` list.
 /// `Stack<T>` is a trait providing stack operations(`push()`, `pop()`, and
-/// `peek()`), and an implementation for `List`.
+/// `peek()`), and an implementation for `List`. `StackMut<T>` provides the
+/// same operations in place, for any `Stack<T>`. `StackPeek<T>` provides
+/// `peek_n()` and `depth()`, for stacks which can look below their top item.
+/// `RcList<T>` and `ArcList<T>` are persistent lists which share their
+/// tails, and also implement `Stack<T>`.
+/// `ListBuilder<T>` builds a `List<T>` from front to back in O(_n_).
+/// `DottedList<T>` is an improper list, such as the dotted pair `(a . b)`.
+/// `ArenaList<T>` is a list whose cells are allocated in a `ConsArena<T>`
+/// and addressed by handle.
 #[cfg_attr(feature = "unstable",
     stable(feature = "list", since="0.1.0") )]
 #[macro_use] pub mod list;
@@ -37,8 +49,9 @@ extern crate byteorder;
 /// Seax VM cell and instruction types.
 ///
 /// A cell in the VM can be either an atom (single item, either unsigned
-/// int, signed int, float, or string), a pointer to a list cell, or an
-/// instruction.
+/// int, signed int, float, or string), a pointer to a list cell, an
+/// instruction, or a byte string. An `Interner` maps structurally equal
+/// cells to a single shared `Interned` cell.
 #[cfg_attr(feature = "unstable",
     stable(feature = "cell", since = "0.1.0") )]
 #[macro_use] pub mod cell;
@@ -48,12 +61,21 @@ extern crate byteorder;
     unstable(feature = "bytecode", issue = "94") )]
 pub mod bytecode;
 
+/// A garbage-collected heap of Seax VM cells.
+///
+/// `Heap` holds `cons` cells by handle, so that they may form cycles, and
+/// frees those which can't be reached from the VM's registers.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "heap", issue = "94") )]
+pub mod heap;
+
 /// General-purpose code for compilers targeting the Seax platform.
 #[cfg_attr(feature = "unstable",
     stable(feature = "compile", since = "0.0.1") )]
 pub mod compiler_tools;
 
 // Reexports
-pub use self::list::{List, Stack};
+pub use self::list::{List, Stack, StackMut, StackPeek};
 pub use self::list::List::{Cons,Nil};
 pub use self::cell::{SVMCell,Atom,Inst};
+pub use self::bytecode::link;
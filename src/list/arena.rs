@@ -0,0 +1,897 @@
+//! Lists whose cells are allocated in an arena.
+//!
+//! Every cell of a `List<T>` is a separate `Box`, so list-heavy programs
+//! spend much of their time in the allocator. A `ConsArena<T>` instead
+//! keeps `cons` cells in a slab, addressed by `CellHandle`s rather than
+//! pointers, and reuses the slots of freed cells. Since cells refer to
+//! each other by handle, a garbage collector can find every cell in the
+//! arena, and every link between them, without following pointers.
+//!
+//! `ArenaList<T>` is a list built on a `ConsArena<T>`, with the same
+//! operations as `List<T>`. Pushing and popping reuse freed slots, so a
+//! stack which grows and shrinks repeatedly stops allocating once it has
+//! reached its largest size. It also keeps a handle to its last cell, so
+//! appending to it is O(1).
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{self, FromIterator};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Index;
+use std::slice;
+
+use super::{List, Stack, StackPeek};
+
+/// A handle to a `cons` cell in a `ConsArena`.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash,PartialOrd,Ord)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+pub struct CellHandle(usize);
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl CellHandle {
+    /// Returns the index of the cell's slot in its arena.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn index(&self) -> usize { self.0 }
+}
+
+enum Slot<T> {
+    /// An allocated cell, holding its car and the handle of its cdr.
+    Cons(T, Option<CellHandle>),
+    /// A free slot, holding the index of the next free slot.
+    Free(Option<usize>)
+}
+
+/// A slab of `cons` cells, addressed by handle.
+///
+/// The cdr of each cell is either the handle of another cell in the same
+/// arena, or `None`, for `nil`. Freed cells' slots are reused by later
+/// allocations, so a handle must not be used after its cell is freed:
+/// doing so panics if the slot is still free, and otherwise refers to
+/// whichever cell was allocated in its place.
+///
+/// # Examples
+///
+/// ```
+/// # use seax_util::list::ConsArena;
+/// let mut arena = ConsArena::new();
+/// let tail = arena.alloc(2, None);
+/// let head = arena.alloc(1, Some(tail));
+/// assert_eq!(arena.car(head), &1);
+/// assert_eq!(arena.cdr(head), Some(tail));
+/// assert_eq!(arena.len(), 2);
+///
+/// assert_eq!(arena.free(head), (1, Some(tail)));
+/// assert_eq!(arena.len(), 1);
+/// // the freed slot is reused
+/// assert_eq!(arena.alloc(3, None), head);
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+pub struct ConsArena<T> {
+    slots: Vec<Slot<T>>,
+    /// The index of the first free slot.
+    free: Option<usize>,
+    len: usize
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> ConsArena<T> {
+    /// Creates an empty arena.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn new() -> ConsArena<T> {
+        ConsArena::with_capacity(0)
+    }
+
+    /// Creates an empty arena with room for `capacity` cells.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn with_capacity(capacity: usize) -> ConsArena<T> {
+        ConsArena { slots: Vec::with_capacity(capacity), free: None, len: 0 }
+    }
+
+    /// Allocates a cell, returning its handle.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn alloc(&mut self, car: T, cdr: Option<CellHandle>) -> CellHandle {
+        let slot = Slot::Cons(car, cdr);
+        self.len += 1;
+        match self.free {
+            Some(index) => {
+                if let Slot::Free(next) = mem::replace(&mut self.slots[index], slot) {
+                    self.free = next;
+                }
+                CellHandle(index)
+            },
+            None => {
+                self.slots.push(slot);
+                CellHandle(self.slots.len() - 1)
+            }
+        }
+    }
+
+    /// Frees a cell, returning its car and cdr.
+    ///
+    /// # Panics
+    ///
+    /// If the cell has already been freed.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn free(&mut self, cell: CellHandle) -> (T, Option<CellHandle>) {
+        if !self.contains(cell) {
+            panic!("cell {} is not allocated", cell.0);
+        }
+        match mem::replace(&mut self.slots[cell.0], Slot::Free(self.free)) {
+            Slot::Cons(car, cdr) => {
+                self.free = Some(cell.0);
+                self.len -= 1;
+                (car, cdr)
+            },
+            Slot::Free(_) => unreachable!()
+        }
+    }
+
+    /// Follows `n` cdrs from `cell`, returning the cell reached, if any.
+    fn nth_cell(&self, cell: Option<CellHandle>, n: usize) -> Option<CellHandle> {
+        let mut cell = cell;
+        for _ in 0..n {
+            cell = self.cdr(cell?);
+        }
+        cell
+    }
+
+    /// Returns true if `cell` refers to an allocated cell.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn contains(&self, cell: CellHandle) -> bool {
+        matches!(self.slots.get(cell.0), Some(&Slot::Cons(..)))
+    }
+
+    /// Borrows the car of a cell.
+    ///
+    /// # Panics
+    ///
+    /// If the cell is not allocated.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn car(&self, cell: CellHandle) -> &T {
+        match self.slots.get(cell.0) {
+            Some(Slot::Cons(car, _)) => car,
+            _ => panic!("cell {} is not allocated", cell.0)
+        }
+    }
+
+    /// Mutably borrows the car of a cell.
+    ///
+    /// # Panics
+    ///
+    /// If the cell is not allocated.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn car_mut(&mut self, cell: CellHandle) -> &mut T {
+        match self.slots.get_mut(cell.0) {
+            Some(Slot::Cons(car, _)) => car,
+            _ => panic!("cell {} is not allocated", cell.0)
+        }
+    }
+
+    /// Returns the cdr of a cell.
+    ///
+    /// # Panics
+    ///
+    /// If the cell is not allocated.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn cdr(&self, cell: CellHandle) -> Option<CellHandle> {
+        match self.slots.get(cell.0) {
+            Some(&Slot::Cons(_, cdr)) => cdr,
+            _ => panic!("cell {} is not allocated", cell.0)
+        }
+    }
+
+    /// Replaces the cdr of a cell.
+    ///
+    /// # Panics
+    ///
+    /// If the cell is not allocated.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn set_cdr(&mut self, cell: CellHandle, cdr: Option<CellHandle>) {
+        match self.slots.get_mut(cell.0) {
+            Some(&mut Slot::Cons(_, ref mut old)) => *old = cdr,
+            _ => panic!("cell {} is not allocated", cell.0)
+        }
+    }
+
+    /// Returns the number of allocated cells.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn len(&self) -> usize { self.len }
+
+    /// Returns true if no cells are allocated.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Returns the number of slots in the arena, both allocated and free.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn num_slots(&self) -> usize { self.slots.len() }
+
+    /// Provide an iterator over the handles of every allocated cell, in
+    /// order of their slots.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn cells<'a>(&'a self) -> ArenaCells<'a, T> {
+        ArenaCells { slots: self.slots.iter().enumerate() }
+    }
+
+    /// Frees every cell in the arena.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free = None;
+        self.len = 0;
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Default for ConsArena<T> {
+    fn default() -> ConsArena<T> { ConsArena::new() }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> fmt::Debug for ConsArena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ConsArena of {} cells in {} slots",
+            self.len, self.slots.len())
+    }
+}
+
+/// A singly-linked `cons` list whose cells are allocated in an arena.
+///
+/// This has the same operations as `List<T>`, except for those which
+/// borrow a list's tail as a list, since the tail of an `ArenaList` is
+/// not a separate list. Appending is O(1), as is finding the length of
+/// the list.
+///
+/// # Examples
+///
+/// ```
+/// # use seax_util::list::{ArenaList, Stack};
+/// let mut list: ArenaList<isize> = vec![2, 3].into_iter().collect();
+/// list = list.push(1);
+/// list.append(4);
+/// assert_eq!(list.length(), 4);
+/// assert_eq!(list.to_string(), "(1, 2, 3, 4)");
+///
+/// let (top, list) = list.pop().unwrap();
+/// assert_eq!(top, 1);
+/// assert_eq!(list[0], 2);
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+pub struct ArenaList<T> {
+    cells: ConsArena<T>,
+    head: Option<CellHandle>,
+    last: Option<CellHandle>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> ArenaList<T> {
+    /// Creates a new empty list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn new() -> ArenaList<T> {
+        ArenaList::with_capacity(0)
+    }
+
+    /// Creates a new empty list with room for `capacity` items.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn with_capacity(capacity: usize) -> ArenaList<T> {
+        ArenaList {
+            cells: ConsArena::with_capacity(capacity),
+            head: None,
+            last: None
+        }
+    }
+
+    /// Prepends the given item to the list, returning the list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn prepend(self, it: T) -> ArenaList<T> { self.push(it) }
+
+    /// Appends an item to the end of the list.
+    ///
+    /// This is an O(1) operation.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn append(&mut self, it: T) {
+        let cell = self.cells.alloc(it, None);
+        match self.last {
+            Some(last) => self.cells.set_cdr(last, Some(cell)),
+            None => self.head = Some(cell)
+        }
+        self.last = Some(cell);
+    }
+
+    /// Returns the length of the list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn length(&self) -> usize { self.cells.len() }
+
+    /// Returns true if the list is empty, false otherwise.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn is_empty(&self) -> bool { self.head.is_none() }
+
+    /// Provide a forward iterator.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn iter<'a>(&'a self) -> ArenaListIter<'a, T> {
+        ArenaListIter { cells: &self.cells, next: self.head, remaining: self.length() }
+    }
+
+    /// Provide a forward iterator over mutable references to the
+    /// list's items.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn iter_mut<'a>(&'a mut self) -> ArenaListIterMut<'a, T> {
+        ArenaListIterMut {
+            slots: self.cells.slots.as_mut_ptr(),
+            next: self.head,
+            remaining: self.cells.len(),
+            marker: PhantomData
+        }
+    }
+
+    /// Returns the last element of the list.
+    ///
+    /// # Panics
+    ///
+    /// If the list is empty.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn last(&self) -> &T {
+        match self.last {
+            Some(last) => self.cells.car(last),
+            None => panic!("Last called on empty list")
+        }
+    }
+
+    /// Optionally index the list, returning `None` if the index is out of
+    /// bounds.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    /// Returns a new list containing the result of applying `f` to each
+    /// item of this list.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn map<U, F>(&self, f: F) -> ArenaList<U>
+    where F: FnMut(&T) -> U {
+        self.iter().map(f).collect()
+    }
+
+    /// Folds every item of the list into an accumulator, from front to
+    /// back.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn fold<B, F>(&self, init: B, f: F) -> B
+    where F: FnMut(B, &T) -> B {
+        self.iter().fold(init, f)
+    }
+
+    /// Consumes the list, returning it in reverse order.
+    ///
+    /// This relinks the list's cells in place, so it doesn't allocate.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn rev(mut self) -> ArenaList<T> {
+        let mut reversed = None;
+        let mut next = self.head;
+        while let Some(cell) = next {
+            next = self.cells.cdr(cell);
+            self.cells.set_cdr(cell, reversed);
+            reversed = Some(cell);
+        }
+        self.last = self.head;
+        self.head = reversed;
+        self
+    }
+
+    /// Consumes the list, returning a list of only those items for which
+    /// `predicate` returns true.
+    ///
+    /// The cells of the other items are freed, and the rest relinked in
+    /// place, so this doesn't allocate.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn filter<P>(mut self, mut predicate: P) -> ArenaList<T>
+    where P: FnMut(&T) -> bool {
+        let mut kept = None;
+        let mut next = self.head;
+        while let Some(cell) = next {
+            next = self.cells.cdr(cell);
+            if predicate(self.cells.car(cell)) {
+                match kept {
+                    Some(prev) => self.cells.set_cdr(prev, Some(cell)),
+                    None => self.head = Some(cell)
+                }
+                kept = Some(cell);
+            } else {
+                self.cells.free(cell);
+            }
+        }
+        match kept {
+            Some(last) => self.cells.set_cdr(last, None),
+            None => self.head = None
+        }
+        self.last = kept;
+        self
+    }
+
+    /// Moves all of the items of `other` onto the end of this list.
+    ///
+    /// The items are moved into this list's arena, so this is O(_n_) in
+    /// the length of `other`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn append_list(&mut self, other: ArenaList<T>) {
+        self.extend(other);
+    }
+
+    /// Consumes two lists, returning a list of the items of this list
+    /// followed by the items of `other`.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn concat(mut self, other: ArenaList<T>) -> ArenaList<T> {
+        self.append_list(other);
+        self
+    }
+
+    /// Consumes two lists, returning a list of pairs of their items.
+    ///
+    /// The result is as long as the shorter of the two lists; any
+    /// remaining items of the longer list are dropped.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn zip<U>(self, other: ArenaList<U>) -> ArenaList<(T, U)> {
+        self.into_iter()
+            .zip(other)
+            .collect()
+    }
+
+    /// Consumes the list, splitting it into a list of its first `n` items
+    /// and a list of the rest.
+    ///
+    /// If the list has fewer than `n` items, the second list is empty. The
+    /// first list keeps this list's arena, and the rest of the items are
+    /// moved into a new one.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn split_at(mut self, n: usize) -> (ArenaList<T>, ArenaList<T>) {
+        let mut back = ArenaList::with_capacity(self.length().saturating_sub(n));
+        let mut next = match n {
+            0 => self.head.take(),
+            n => {
+                let end = match self.cells.nth_cell(self.head, n - 1) {
+                    Some(end) => end,
+                    None => return (self, back)
+                };
+                let next = self.cells.cdr(end);
+                self.cells.set_cdr(end, None);
+                self.last = Some(end);
+                next
+            }
+        };
+        if self.head.is_none() {
+            self.last = None;
+        }
+        while let Some(cell) = next {
+            let (item, cdr) = self.cells.free(cell);
+            back.append(item);
+            next = cdr;
+        }
+        (self, back)
+    }
+
+    /// Consumes the list, returning a list of its first `n` items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn take(self, n: usize) -> ArenaList<T> {
+        self.split_at(n).0
+    }
+
+    /// Consumes the list, returning a list of all but its first `n` items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn drop(self, n: usize) -> ArenaList<T> {
+        self.split_at(n).1
+    }
+
+    /// Borrows the arena holding the list's cells.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn arena(&self) -> &ConsArena<T> { &self.cells }
+
+    /// Returns the handle of the list's first cell, or `None` if the list
+    /// is empty.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn head_cell(&self) -> Option<CellHandle> { self.head }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> ArenaList<ArenaList<T>> {
+    /// Consumes a list of lists, returning a list of all of their items
+    /// in order.
+    ///
+    /// The items are moved into a single new arena, so this is O(_n_) in
+    /// the total number of items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "arena", issue = "94") )]
+    pub fn flatten(self) -> ArenaList<T> {
+        let len = self.iter().map(ArenaList::length).sum();
+        let mut result = ArenaList::with_capacity(len);
+        for list in self {
+            result.append_list(list);
+        }
+        result
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Stack<T> for ArenaList<T> {
+    #[inline]
+    fn push(mut self, item: T) -> ArenaList<T> {
+        let cell = self.cells.alloc(item, self.head);
+        if self.head.is_none() {
+            self.last = Some(cell);
+        }
+        self.head = Some(cell);
+        self
+    }
+
+    #[inline]
+    fn pop(mut self) -> Option<(T, ArenaList<T>)> {
+        let head = self.head?;
+        let (item, next) = self.cells.free(head);
+        self.head = next;
+        if next.is_none() {
+            self.last = None;
+        }
+        Some((item, self))
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        self.head.map(|head| self.cells.car(head))
+    }
+
+    #[inline]
+    fn empty() -> ArenaList<T> { ArenaList::new() }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> StackPeek<T> for ArenaList<T> {
+    #[inline]
+    fn peek_n(&self, n: usize) -> Option<&T> { self.get(n) }
+
+    #[inline]
+    fn depth(&self) -> usize { self.length() }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Default for ArenaList<T> {
+    fn default() -> ArenaList<T> { ArenaList::new() }
+}
+
+/// Copies a list into a new arena, without any free slots.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Clone for ArenaList<T>
+where T: Clone {
+    fn clone(&self) -> ArenaList<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> PartialEq for ArenaList<T>
+where T: PartialEq {
+    fn eq(&self, other: &ArenaList<T>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Eq for ArenaList<T> where T: Eq {}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Hash for ArenaList<T>
+where T: Hash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.length().hash(state);
+        for item in self.iter() {
+            item.hash(state);
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> fmt::Display for ArenaList<T>
+where T: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, item) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> fmt::Debug for ArenaList<T>
+where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for item in self.iter() {
+            write!(f, "({:?} . ", item)?;
+        }
+        write!(f, "nil")?;
+        for _ in self.iter() {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> FromIterator<T> for ArenaList<T> {
+    fn from_iter<I>(iterable: I) -> ArenaList<T>
+    where I: IntoIterator<Item = T> {
+        let iter = iterable.into_iter();
+        let mut list = ArenaList::with_capacity(iter.size_hint().0);
+        list.extend(iter);
+        list
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Extend<T> for ArenaList<T> {
+    fn extend<I>(&mut self, iterable: I) where I: IntoIterator<Item = T> {
+        for item in iterable {
+            self.append(item);
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> From<List<T>> for ArenaList<T> {
+    fn from(list: List<T>) -> ArenaList<T> {
+        list.into_iter().collect()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> From<ArenaList<T>> for List<T> {
+    fn from(list: ArenaList<T>) -> List<T> {
+        list.into_iter().collect()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Index<usize> for ArenaList<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index)
+            .unwrap_or_else(|| panic!("list index {} out of range", index))
+    }
+}
+
+/// Iterator over the handles of the allocated cells in a `ConsArena<T>`.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+pub struct ArenaCells<'a, T: 'a> {
+    slots: iter::Enumerate<slice::Iter<'a, Slot<T>>>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<'a, T> Iterator for ArenaCells<'a, T> {
+    type Item = CellHandle;
+
+    fn next(&mut self) -> Option<CellHandle> {
+        self.slots.by_ref()
+            .find(|&(_, slot)| matches!(*slot, Slot::Cons(..)))
+            .map(|(index, _)| CellHandle(index))
+    }
+}
+
+/// Iterator over the items of an `ArenaList<T>`.
+#[derive(Clone)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+pub struct ArenaListIter<'a, T: 'a> {
+    cells: &'a ConsArena<T>,
+    next: Option<CellHandle>,
+    remaining: usize
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<'a, T> Iterator for ArenaListIter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|cell| {
+            self.next = self.cells.cdr(cell);
+            self.remaining -= 1;
+            self.cells.car(cell)
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<'a, T> ExactSizeIterator for ArenaListIter<'a, T> {}
+
+/// Iterator over mutable references to the items of an `ArenaList<T>`.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+pub struct ArenaListIterMut<'a, T: 'a> {
+    slots: *mut Slot<T>,
+    next: Option<CellHandle>,
+    remaining: usize,
+    marker: PhantomData<&'a mut T>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<'a, T> Iterator for ArenaListIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        let cell = self.next?;
+        // The iterator mutably borrows the list, and each of the list's
+        // cells is in a different slot and is visited once, so the
+        // references it returns never alias.
+        match unsafe { &mut *self.slots.add(cell.0) } {
+            Slot::Cons(car, cdr) => {
+                self.next = *cdr;
+                self.remaining -= 1;
+                Some(car)
+            },
+            Slot::Free(_) => None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<'a, T> ExactSizeIterator for ArenaListIterMut<'a, T> {}
+
+/// Owning iterator over the items of an `ArenaList<T>`.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+pub struct ArenaListIntoIter<T> { list: ArenaList<T> }
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> Iterator for ArenaListIntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        mem::take(&mut self.list)
+            .pop()
+            .map(|(item, rest)| { self.list = rest; item })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.length(), Some(self.list.length()))
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> ExactSizeIterator for ArenaListIntoIter<T> {}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<T> IntoIterator for ArenaList<T> {
+    type Item = T;
+    type IntoIter = ArenaListIntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> ArenaListIntoIter<T> {
+        ArenaListIntoIter { list: self }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<'a, T> IntoIterator for &'a ArenaList<T> {
+    type Item = &'a T;
+    type IntoIter = ArenaListIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> ArenaListIter<'a, T> { self.iter() }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arena", issue = "94") )]
+impl<'a, T> IntoIterator for &'a mut ArenaList<T> {
+    type Item = &'a mut T;
+    type IntoIter = ArenaListIterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> ArenaListIterMut<'a, T> { self.iter_mut() }
+}
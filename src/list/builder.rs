@@ -0,0 +1,201 @@
+//! A builder for `List`s which appends items in constant time.
+//!
+//! `List::append()` has to walk the whole list to find its end. A
+//! `ListBuilder<T>` instead keeps a pointer to the `Nil` at the end of the
+//! list it is building, so that each item may be appended in O(1). This
+//! makes building a list from front to back, as `FromIterator` and the
+//! bytecode decoder do, O(_n_) rather than O(_n_^2).
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::ptr::NonNull;
+
+use super::List;
+use super::List::{Cons, Nil};
+
+/// Builds a `List<T>` by appending items to its end.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::{List, ListBuilder};
+/// # use seax_util::list::List::{Cons, Nil};
+/// # fn main() {
+/// let mut builder = ListBuilder::new();
+/// builder.push_back(1);
+/// builder.push_back(2);
+/// builder.push_back(3);
+/// assert_eq!(builder.len(), 3);
+/// assert_eq!(builder.build(), list!(1, 2, 3));
+/// # }
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+pub struct ListBuilder<T> {
+    // The list being built, in a `Box` owned by the builder. This is held
+    // as a raw pointer, since moving a `Box` would invalidate `tail` when
+    // it points into the same allocation.
+    head: NonNull<List<T>>,
+    // The `Nil` at the end of `head`, or `None` if `head` is itself `Nil`.
+    // When present, this always points into a `Box` owned by `head`, so it
+    // stays valid when the builder is moved.
+    tail: Option<NonNull<List<T>>>,
+    len: usize
+}
+
+// `ListBuilder` owns everything `head` and `tail` point to, so it is as
+// thread-safe as the list it is building.
+unsafe impl<T: Send> Send for ListBuilder<T> {}
+unsafe impl<T: Sync> Sync for ListBuilder<T> {}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+impl<T> ListBuilder<T> {
+
+    /// Creates a new builder for an empty list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_builder", issue = "94") )]
+    pub fn new() -> ListBuilder<T> {
+        ListBuilder::starting_with(Nil)
+    }
+
+    /// Creates a builder whose list starts out as `list`, without finding
+    /// its end.
+    fn starting_with(list: List<T>) -> ListBuilder<T> {
+        let head = NonNull::from(Box::leak(Box::new(list)));
+        ListBuilder { head, tail: None, len: 0 }
+    }
+
+    /// Appends an item to the end of the list being built.
+    ///
+    /// This is an O(1) operation.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_builder", issue = "94") )]
+    pub fn push_back(&mut self, item: T) {
+        let slot = match self.tail {
+            Some(mut tail) => unsafe { tail.as_mut() },
+            None => unsafe { self.head.as_mut() }
+        };
+        *slot = Cons(item, Box::new(Nil));
+        // the new `Nil` must be borrowed through the list once the `Box`
+        // holding it has been moved into place, as moving a `Box`
+        // invalidates any pointers taken into it before
+        self.tail = match *slot {
+            Cons(_, ref mut next) => Some(NonNull::from(&mut **next)),
+            Nil => unreachable!()
+        };
+        self.len += 1;
+    }
+
+    /// Returns the number of items appended so far.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_builder", issue = "94") )]
+    pub fn len(&self) -> usize { self.len }
+
+    /// Returns true if no items have been appended yet.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_builder", issue = "94") )]
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Borrows the list built so far.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_builder", issue = "94") )]
+    pub fn as_list(&self) -> &List<T> { unsafe { self.head.as_ref() } }
+
+    /// Finishes building, returning the list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_builder", issue = "94") )]
+    pub fn build(self) -> List<T> {
+        let head = unsafe { Box::from_raw(self.head.as_ptr()) };
+        mem::forget(self);
+        *head
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+impl<T> Drop for ListBuilder<T> {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.head.as_ptr()) });
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+impl<T> Default for ListBuilder<T> {
+    fn default() -> ListBuilder<T> { ListBuilder::new() }
+}
+
+/// Continues building an existing list.
+///
+/// The list is walked once to find its end, so this is O(_n_), but items
+/// may then be appended to it in O(1).
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::{List, ListBuilder};
+/// # use seax_util::list::List::{Cons, Nil};
+/// # fn main() {
+/// let mut builder = ListBuilder::from(list!(1, 2));
+/// builder.push_back(3);
+/// assert_eq!(builder.build(), list!(1, 2, 3));
+/// # }
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+impl<T> From<List<T>> for ListBuilder<T> {
+    fn from(list: List<T>) -> ListBuilder<T> {
+        let mut builder = ListBuilder::starting_with(list);
+        if let Cons(_, ref mut tail) = *unsafe { builder.head.as_mut() } {
+            let mut current: &mut List<T> = tail;
+            builder.len = 1;
+            while let Cons(_, ref mut next) = *current {
+                current = next;
+                builder.len += 1;
+            }
+            builder.tail = Some(NonNull::from(current));
+        }
+        builder
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+impl<T> Extend<T> for ListBuilder<T> {
+    fn extend<I>(&mut self, iterable: I) where I: IntoIterator<Item = T> {
+        for item in iterable {
+            self.push_back(item);
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+impl<T> FromIterator<T> for ListBuilder<T> {
+    fn from_iter<I>(iterable: I) -> ListBuilder<T>
+    where I: IntoIterator<Item = T> {
+        let mut builder = ListBuilder::new();
+        builder.extend(iterable);
+        builder
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_builder", issue = "94") )]
+impl<T> fmt::Debug for ListBuilder<T>
+where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ListBuilder")
+            .field(self.as_list())
+            .finish()
+    }
+}
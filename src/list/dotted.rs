@@ -0,0 +1,209 @@
+//! Improper lists, whose final cdr is not `nil`.
+//!
+//! A `List<T>` is always a proper list: the cdr of each of its `cons`
+//! cells is another list. A `DottedList<T>` is a chain of one or more
+//! `cons` cells whose final cdr is an arbitrary item instead, such as the
+//! dotted pair `(1 . 2)`, or the improper list `(1 2 . 3)`.
+
+use std::fmt;
+
+use super::{List, Stack};
+use super::List::{Cons, Nil};
+
+/// An improper list, such as `(a . b)` or `(a b . c)`.
+///
+/// This consists of a non-empty proper list of the cars of its cells,
+/// followed by the cdr of its last cell.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::DottedList;
+/// # use seax_util::list::List::{Cons, Nil};
+/// # fn main() {
+/// let pair = DottedList::pair(1, 2);
+/// assert_eq!(pair.to_string(), "(1 . 2)");
+///
+/// let improper = pair.cons(0);
+/// assert_eq!(improper.items(), &list!(0, 1));
+/// assert_eq!(improper.last_cdr(), &2);
+/// assert_eq!(improper.to_string(), "(0, 1 . 2)");
+/// # }
+/// ```
+#[derive(Clone,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "dotted", issue = "94") )]
+pub struct DottedList<T> {
+    items: List<T>,
+    last: T
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "dotted", issue = "94") )]
+impl<T> DottedList<T> {
+
+    /// Makes a dotted pair, `(car . cdr)`.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn pair(car: T, cdr: T) -> DottedList<T> {
+        DottedList { items: Cons(car, Box::new(Nil)), last: cdr }
+    }
+
+    /// Makes an improper list of the items of `items`, whose final cdr
+    /// is `last`.
+    ///
+    /// If `items` is empty, there are no `cons` cells to hold `last`, so
+    /// it is handed back in the `Err` case.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::{DottedList, List};
+    /// # use seax_util::list::List::{Cons, Nil};
+    /// # fn main() {
+    /// let improper = DottedList::new(list!(1, 2), 3).unwrap();
+    /// assert_eq!(improper.to_string(), "(1, 2 . 3)");
+    /// assert_eq!(DottedList::new(List::new(), 3), Err(3));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn new(items: List<T>, last: T) -> Result<DottedList<T>, T> {
+        if items.is_empty() {
+            Err(last)
+        } else {
+            Ok(DottedList { items, last })
+        }
+    }
+
+    /// Converts a proper list of at least two items into an improper list,
+    /// whose final cdr is the last item of the list.
+    ///
+    /// This is the inverse of `into_list()`. Lists which are too short are
+    /// handed back unchanged in the `Err` case.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::DottedList;
+    /// # use seax_util::list::List::{Cons, Nil};
+    /// # fn main() {
+    /// let improper = DottedList::from_list(list!(1, 2, 3)).unwrap();
+    /// assert_eq!(improper, DottedList::new(list!(1, 2), 3).unwrap());
+    /// assert_eq!(DottedList::from_list(list!(1)), Err(list!(1)));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn from_list(list: List<T>) -> Result<DottedList<T>, List<T>> {
+        let length = list.length();
+        if length < 2 {
+            return Err(list);
+        }
+        let (items, rest) = list.split_at(length - 1);
+        let last = rest.pop().map(|(last, _)| last).unwrap();
+        Ok(DottedList { items, last })
+    }
+
+    /// Converts this improper list into a proper list, whose last item is
+    /// this list's final cdr.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::DottedList;
+    /// # use seax_util::list::List::{Cons, Nil};
+    /// # fn main() {
+    /// assert_eq!(DottedList::pair(1, 2).into_list(), list!(1, 2));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn into_list(self) -> List<T> {
+        let DottedList { mut items, last } = self;
+        items.append(last);
+        items
+    }
+
+    /// Takes apart this improper list, returning the cars of its cells and
+    /// its final cdr.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn into_parts(self) -> (List<T>, T) {
+        (self.items, self.last)
+    }
+
+    /// Prepends an item to this improper list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn cons(self, item: T) -> DottedList<T> {
+        DottedList { items: self.items.push(item), last: self.last }
+    }
+
+    /// Returns the car of the first cell.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn car(&self) -> &T {
+        self.items.peek().unwrap()
+    }
+
+    /// Returns the cars of every cell, as a proper list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn items(&self) -> &List<T> { &self.items }
+
+    /// Returns the cdr of the last cell.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn last_cdr(&self) -> &T { &self.last }
+
+    /// Returns the number of `cons` cells in this list.
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "dotted", issue = "94") )]
+    pub fn length(&self) -> usize { self.items.length() }
+}
+
+/// Formats an improper list like a `List`, with the final cdr after a dot,
+/// e.g. `(1, 2 . 3)`.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "dotted", issue = "94") )]
+impl<T> fmt::Display for DottedList<T>
+where T: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut it = self.items.iter().peekable();
+        let mut s = String::from("(");
+        while let Some(item) = it.next() {
+            s.push_str(&format!("{}", item));
+            if it.peek().is_some() {
+                s.push_str(", ");
+            }
+        }
+        write!(f, "{} . {})", s, self.last)
+    }
+}
+
+/// Formats an improper list as nested pairs, like a `List`, e.g.
+/// `(1 . (2 . 3))`.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "dotted", issue = "94") )]
+impl<T> fmt::Debug for DottedList<T>
+where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for item in self.items.iter() {
+            write!(f, "({:?} . ", item)?;
+        }
+        write!(f, "{:?}", self.last)?;
+        for _ in self.items.iter() {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,17 @@
 pub use list::List::{Cons,Nil};
 
+use std::collections::VecDeque;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem::{self, ManuallyDrop};
 use std::ops::Index;
-use std::iter::{IntoIterator, FromIterator};
+use std::ptr;
+use std::iter::{IntoIterator, FromIterator, Extend};
 use std::default::Default;
 
+#[cfg(any(test, feature = "arbitrary"))]
+use quickcheck::{Arbitrary, Gen};
+
 /// Convenience macro for making lists.
 ///
 /// # Example:
@@ -29,6 +36,18 @@ macro_rules! list(
     () => ( Box::new(Nil) );
 );
 
+#[macro_use]
+mod shared;
+mod builder;
+mod dotted;
+mod arena;
+
+pub use self::shared::{RcList, ArcList, RcListIter, ArcListIter};
+pub use self::builder::ListBuilder;
+pub use self::dotted::DottedList;
+pub use self::arena::{ArenaList, ConsArena, CellHandle, ArenaCells,
+                      ArenaListIter, ArenaListIterMut, ArenaListIntoIter};
+
 #[cfg(test)]
 mod tests;
 
@@ -60,8 +79,302 @@ pub trait Stack<T>: Sized {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     fn empty()              -> Self;
+
+    /// Pop the top two items of the stack, returning them top first,
+    /// along with the rest of the stack.
+    ///
+    /// Returns `None` if the stack has fewer than two items.
+    ///
+    /// # Examples:
+    /// ```
+    /// # use seax_util::list::{List,Stack};
+    /// let s: List<isize> = List::new().push(2).push(1);
+    /// let (a, b, s) = s.pop2().unwrap();
+    /// assert_eq!((a, b), (1, 2));
+    /// assert_eq!(s.peek(), None);
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn pop2(self) -> Option<(T, T, Self)> {
+        self.pop()
+            .and_then(|(a, rest)| rest.pop()
+                .map(|(b, rest)| (a, b, rest)))
+    }
+
+    /// Pop the top `n` items of the stack, returning them top first,
+    /// along with the rest of the stack.
+    ///
+    /// Returns `None` if the stack has fewer than `n` items.
+    ///
+    /// # Examples:
+    /// ```
+    /// # use seax_util::list::{List,Stack};
+    /// let s: List<isize> = List::new().push(3).push(2).push(1);
+    /// let (items, s) = s.pop_n(2).unwrap();
+    /// assert_eq!(items, vec![1, 2]);
+    /// assert_eq!(s.peek(), Some(&3));
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn pop_n(self, n: usize) -> Option<(Vec<T>, Self)> {
+        let mut items = Vec::with_capacity(n);
+        let mut rest = self;
+        for _ in 0..n {
+            let (item, new_rest) = rest.pop()?;
+            items.push(item);
+            rest = new_rest;
+        }
+        Some((items, rest))
+    }
+
+    /// Push a copy of the top item of the stack.
+    ///
+    /// Returns `None` if the stack is empty.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn dup(self) -> Option<Self>
+    where T: Clone {
+        let top = self.peek()?.clone();
+        Some(self.push(top))
+    }
+
+    /// Exchange the top two items of the stack.
+    ///
+    /// Returns `None` if the stack has fewer than two items.
+    ///
+    /// # Examples:
+    /// ```
+    /// # use seax_util::list::{List,Stack};
+    /// let s: List<isize> = List::new().push(2).push(1);
+    /// assert_eq!(s.swap().unwrap().peek(), Some(&2));
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn swap(self) -> Option<Self> {
+        self.pop2().map(|(a, b, rest)| rest.push(a).push(b))
+    }
+
+    /// Rotate the top three items of the stack, bringing the third item
+    /// to the top.
+    ///
+    /// Returns `None` if the stack has fewer than three items.
+    ///
+    /// # Examples:
+    /// ```
+    /// # use seax_util::list::{List,Stack};
+    /// let s: List<isize> = List::new().push(3).push(2).push(1);
+    /// let (items, _) = s.rot().unwrap().pop_n(3).unwrap();
+    /// assert_eq!(items, vec![3, 1, 2]);
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn rot(self) -> Option<Self> {
+        self.pop_n(3).map(|(mut items, rest)| {
+            items.rotate_right(1);
+            items.into_iter().rev().fold(rest, Stack::push)
+        })
+    }
+
+    /// Pop every item of this stack and push it onto `other`, returning
+    /// `other`.
+    ///
+    /// The items end up in reverse order, with this stack's top item at
+    /// the bottom of those pushed onto `other`.
+    ///
+    /// # Examples:
+    /// ```
+    /// # use seax_util::list::{List,Stack};
+    /// let s: List<isize> = List::new().push(2).push(1);
+    /// let other: List<isize> = List::new().push(3);
+    /// let (items, _) = s.drain_to(other).pop_n(3).unwrap();
+    /// assert_eq!(items, vec![2, 1, 3]);
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn drain_to<S>(self, other: S) -> S
+    where S: Stack<T> {
+        let mut rest = self;
+        let mut other = other;
+        while let Some((item, new_rest)) = rest.pop() {
+            other = other.push(item);
+            rest = new_rest;
+        }
+        other
+    }
+}
+
+/// Stack operations which look below the top of a stack.
+///
+/// These can't be provided in terms of `Stack`'s methods without taking
+/// the stack apart, so they are a separate trait, implemented by stacks
+/// which can reach their lower items directly.
+///
+/// # Examples:
+/// ```
+/// # use seax_util::list::{List,Stack,StackPeek};
+/// let s: List<isize> = List::new().push(3).push(2).push(1);
+/// assert_eq!(s.peek_n(0), s.peek());
+/// assert_eq!(s.peek_n(2), Some(&3));
+/// assert_eq!(s.depth(), 3);
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "stack_ops", issue = "94") )]
+pub trait StackPeek<T>: Stack<T> {
+
+    /// Peek at the `n`th item from the top of the stack.
+    ///
+    /// The top item is item 0, so `peek_n(0)` is the same as `peek()`.
+    /// Returns `None` if the stack has `n` or fewer items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn peek_n(&self, n: usize) -> Option<&T>;
+
+    /// Returns the number of items on the stack.
+    ///
+    /// By default, this peeks at each item in turn; implementations
+    /// should override it if they can count their items more directly.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn depth(&self) -> usize {
+        (0..).take_while(|&n| self.peek_n(n).is_some()).count()
+    }
+}
+
+/// In-place operations for stacks, which take `&mut self` rather than
+/// consuming the stack.
+///
+/// This is implemented for every `Stack`, by swapping the stack out for an
+/// empty one and back again. Unlike the consuming operations, an operation
+/// which fails because there are too few items leaves the stack unchanged.
+///
+/// # Examples:
+/// ```
+/// # use seax_util::list::{List,Stack,StackMut};
+/// let mut s: List<isize> = Stack::empty();
+/// s.push_mut(1);
+/// s.push_mut(2);
+/// assert!(s.swap_mut());
+/// assert_eq!(s.pop_mut(), Some(1));
+/// assert_eq!(s.pop2_mut(), None);
+/// assert_eq!(s.peek(), Some(&2));
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "stack_ops", issue = "94") )]
+pub trait StackMut<T>: Stack<T> {
+
+    /// Push an item to the top of the stack.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn push_mut(&mut self, item: T) {
+        let stack = mem::replace(self, Self::empty());
+        *self = stack.push(item);
+    }
+
+    /// Pop the top item of the stack, or return `None` if it is empty.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn pop_mut(&mut self) -> Option<T> {
+        let (item, rest) = mem::replace(self, Self::empty()).pop()?;
+        *self = rest;
+        Some(item)
+    }
+
+    /// Pop the top two items of the stack, returning them top first.
+    ///
+    /// Returns `None` if the stack has fewer than two items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn pop2_mut(&mut self) -> Option<(T, T)> {
+        let a = self.pop_mut()?;
+        match self.pop_mut() {
+            Some(b) => Some((a, b)),
+            None => { self.push_mut(a); None }
+        }
+    }
+
+    /// Pop the top `n` items of the stack, returning them top first.
+    ///
+    /// Returns `None` if the stack has fewer than `n` items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn pop_n_mut(&mut self, n: usize) -> Option<Vec<T>> {
+        let mut items = Vec::with_capacity(n);
+        while items.len() < n {
+            match self.pop_mut() {
+                Some(item) => items.push(item),
+                None => {
+                    while let Some(item) = items.pop() {
+                        self.push_mut(item);
+                    }
+                    return None;
+                }
+            }
+        }
+        Some(items)
+    }
+
+    /// Push a copy of the top item of the stack.
+    ///
+    /// Returns false if the stack is empty.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn dup_mut(&mut self) -> bool
+    where T: Clone {
+        match self.peek().cloned() {
+            Some(top) => { self.push_mut(top); true },
+            None => false
+        }
+    }
+
+    /// Exchange the top two items of the stack.
+    ///
+    /// Returns false if the stack has fewer than two items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn swap_mut(&mut self) -> bool {
+        match self.pop2_mut() {
+            Some((a, b)) => { self.push_mut(a); self.push_mut(b); true },
+            None => false
+        }
+    }
+
+    /// Rotate the top three items of the stack, bringing the third item
+    /// to the top.
+    ///
+    /// Returns false if the stack has fewer than three items.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn rot_mut(&mut self) -> bool {
+        match self.pop_n_mut(3) {
+            Some(mut items) => {
+                items.rotate_right(1);
+                for item in items.into_iter().rev() {
+                    self.push_mut(item);
+                }
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Pop every item of this stack and push it onto `other`.
+    ///
+    /// The items end up in reverse order, with this stack's top item at
+    /// the bottom of those pushed onto `other`. This stack is left empty.
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn drain_to_mut<S>(&mut self, other: &mut S)
+    where S: StackMut<T> {
+        while let Some(item) = self.pop_mut() {
+            other.push_mut(item);
+        }
+    }
 }
 
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "stack_ops", issue = "94") )]
+impl<T, S> StackMut<T> for S where S: Stack<T> {}
+
 /// Stack implementation using a `cons` list
 impl<T> Stack<T> for List<T> {
 
@@ -106,10 +419,8 @@ impl<T> Stack<T> for List<T> {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     fn pop(self) -> Option<(T,List<T>)> {
-        match self {
-            Cons(item, new_self)    => Some((item, *new_self)),
-            Nil                     => None
-        }
+        self.into_parts()
+            .map(|(item, new_self)| (item, *new_self))
     }
 
     #[inline]
@@ -147,20 +458,45 @@ impl<T> Stack<T> for List<T> {
 
 }
 
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "stack_ops", issue = "94") )]
+impl<T> StackPeek<T> for List<T> {
+
+    /// Peek at the `n`th item from the top of the stack.
+    ///
+    /// # Examples:
+    /// ```
+    /// # use seax_util::list::{List,Stack,StackPeek};
+    /// let s: List<isize> = List::new().push(2).push(1);
+    /// assert_eq!(s.peek_n(1), Some(&2));
+    /// assert_eq!(s.peek_n(2), None);
+    /// assert_eq!(s.depth(), 2);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn peek_n(&self, n: usize) -> Option<&T> { self.iter().nth(n) }
+
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "stack_ops", issue = "94") )]
+    fn depth(&self) -> usize { self.length() }
+
+}
+
 /// Singly-linked `cons` list.
 ///
 /// This is used internally to represent list primitives in the
 /// Seax virtual machine.
 ///
-// TODO: potentially, a pointer to the last itemof the list could be
-// cached using a `RefCell` or something to speed up access for
-// appends/tail access. We could also check the length and decide whether
-// to link hop from the head or tail when indexing. It would be necessary
-// to investigate whether the added overhead of caching (both in terms of
-// space and in terms of time taken to update the cache) would be worth
-// the performance benefits --- my guess is that caching is worth the added
-// costs (as usual).
-#[derive(PartialEq,Eq,Hash,Clone)]
+/// No operation on a list recurses over its tail, so lists of any length
+/// may be created, cloned, compared, hashed, formatted and dropped without
+/// overflowing the stack. Because `List` implements `Drop`, a list can't be
+/// destructured by value; use `Stack::pop()` to take a list apart.
+///
+/// Appending to a `List` is O(_n_); to build a long list from front to
+/// back, use a `ListBuilder`, which caches a pointer to the end of the
+/// list.
 #[cfg_attr(feature = "unstable",
     stable(feature = "list", since="0.1.0") )]
 pub enum List<T> {
@@ -243,11 +579,7 @@ impl<T> List<T> {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     pub fn append(&mut self, it: T) {
-        match *self {
-            Cons(_, ref mut tail) => (*tail).append(it),
-            Nil => *self = Cons(it, Box::new(Nil))
-        }
-
+        self.append_chain(it);
     }
 
     /// Appends an item to the end of the list.
@@ -286,11 +618,12 @@ impl<T> List<T> {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     pub fn append_chain(&mut self, it: T) -> &mut List<T> {
-        match *self {
-            Cons(_, ref mut tail) => (*tail).append_chain(it),
-            Nil => { *self = Cons(it, Box::new(Nil)); self }
+        let mut current = self;
+        while let Cons(_, ref mut tail) = *current {
+            current = tail;
         }
-
+        *current = Cons(it, Box::new(Nil));
+        current
     }
 
     /// Returns the length of the list.
@@ -309,10 +642,7 @@ impl<T> List<T> {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     pub fn length (&self) -> usize {
-        match *self {
-            Cons(_, ref tail) => 1 + tail.length(),
-            Nil => 0
-        }
+        self.iter().count()
     }
 
     /// Returns true if the list is empty, false otherwise.
@@ -376,6 +706,29 @@ impl<T> List<T> {
         ListIterator{current: self}
     }
 
+    /// Provide a forward iterator over mutable references to the
+    /// list's items.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let mut list = list!(1,2,3);
+    /// for item in list.iter_mut() {
+    ///     *item *= 10;
+    /// }
+    /// assert_eq!(list, list!(10,20,30))
+    /// # }
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_iter", issue = "94") )]
+    pub fn iter_mut<'a>(&'a mut self) -> ListIterMut<'a, T> {
+        ListIterMut{current: Some(self)}
+    }
+
     /// Returns the last element of the list
     ///
     /// # Examples
@@ -392,11 +745,9 @@ impl<T> List<T> {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     pub fn last(&self) -> &T {
-        match *self {
-            Cons(ref car, ref cdr) if cdr.is_empty() => &car,
-            Cons(_, ref cdr) => (*cdr).last(),
-            Nil => panic!("Last called on empty list")
-        }
+        self.iter()
+            .last()
+            .expect("Last called on empty list")
     }
 
 
@@ -437,6 +788,286 @@ impl<T> List<T> {
         }
 
     }
+
+    /// Takes apart the head cell of the list, returning its item and the
+    /// box holding its tail, or `None` if the list is empty.
+    fn into_parts(self) -> Option<(T, Box<List<T>>)> {
+        // `List` implements `Drop`, so its fields can't be moved out by
+        // matching on it; instead, they are read out of a list which will
+        // never be dropped.
+        let mut list = ManuallyDrop::new(self);
+        match *list {
+            Cons(ref mut item, ref mut tail) => unsafe {
+                Some((ptr::read(item), ptr::read(tail)))
+            },
+            Nil => None
+        }
+    }
+}
+
+/// Functional combinators for `List`.
+///
+/// These build their results directly as lists, without collecting into
+/// intermediate vectors, and never recurse over the list. Those which
+/// consume a list reuse its cells where they can.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_combinators", issue = "94") )]
+impl<T> List<T> {
+
+    /// Returns a new list containing the result of applying `f` to each
+    /// item of this list.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let a_list = list!(1,2,3);
+    /// assert_eq!(a_list.map(|x| x * 2), list!(2,4,6));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn map<U, F>(&self, f: F) -> List<U>
+    where F: FnMut(&T) -> U {
+        self.iter().map(f).collect()
+    }
+
+    /// Consumes the list, returning a list of only those items for which
+    /// `predicate` returns true.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let a_list = list!(1,2,3,4,5);
+    /// assert_eq!(a_list.filter(|x| x % 2 == 1), list!(1,3,5));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn filter<P>(self, mut predicate: P) -> List<T>
+    where P: FnMut(&T) -> bool {
+        self.into_iter()
+            .filter(|item| predicate(item))
+            .collect()
+    }
+
+    /// Folds every item of the list into an accumulator, from front to
+    /// back.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let a_list = list!(1,2,3,4);
+    /// assert_eq!(a_list.fold(0, |sum, x| sum + x), 10);
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn fold<B, F>(&self, init: B, f: F) -> B
+    where F: FnMut(B, &T) -> B {
+        self.iter().fold(init, f)
+    }
+
+    /// Consumes the list, returning it in reverse order.
+    ///
+    /// This relinks the list's cells in place, so it doesn't allocate.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let a_list = list!(1,2,3);
+    /// assert_eq!(a_list.rev(), list!(3,2,1));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn rev(self) -> List<T> {
+        let mut reversed = Nil;
+        let mut rest = self;
+        while let Some((item, mut cell)) = rest.into_parts() {
+            rest = mem::replace(&mut *cell, reversed);
+            reversed = Cons(item, cell);
+        }
+        reversed
+    }
+
+    /// Moves all of the items of `other` onto the end of this list.
+    ///
+    /// This is O(_n_) in the length of this list, and O(1) in the length
+    /// of `other`, whose cells are linked in as they are.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let mut a_list = list!(1,2);
+    /// a_list.append_list(list!(3,4));
+    /// assert_eq!(a_list, list!(1,2,3,4));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn append_list(&mut self, other: List<T>) {
+        let mut end = self;
+        while let Cons(_, ref mut tail) = *end {
+            end = tail;
+        }
+        *end = other;
+    }
+
+    /// Consumes two lists, returning a list of the items of this list
+    /// followed by the items of `other`.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// assert_eq!(list!(1,2).concat(list!(3)), list!(1,2,3));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn concat(mut self, other: List<T>) -> List<T> {
+        self.append_list(other);
+        self
+    }
+
+    /// Consumes two lists, returning a list of pairs of their items.
+    ///
+    /// The result is as long as the shorter of the two lists; any
+    /// remaining items of the longer list are dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let pairs = list!(1,2,3).zip(list!('a','b'));
+    /// assert_eq!(pairs, list!((1,'a'), (2,'b')));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn zip<U>(self, other: List<U>) -> List<(T, U)> {
+        self.into_iter()
+            .zip(other)
+            .collect()
+    }
+
+    /// Consumes the list, splitting it into a list of its first `n` items
+    /// and a list of the rest.
+    ///
+    /// If the list has fewer than `n` items, the second list is empty.
+    /// This relinks the list's cells in place, so it doesn't allocate.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let (front, back) = list!(1,2,3,4).split_at(1);
+    /// assert_eq!(front, list!(1));
+    /// assert_eq!(back, list!(2,3,4));
+    ///
+    /// let (front, back) = list!(1,2).split_at(5);
+    /// assert_eq!(front, list!(1,2));
+    /// assert_eq!(back, List::new());
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn split_at(mut self, n: usize) -> (List<T>, List<T>) {
+        let back = {
+            let mut end = &mut self;
+            for _ in 0..n {
+                end = match end {
+                    &mut Cons(_, ref mut tail) => tail,
+                    nil => { end = nil; break }
+                };
+            }
+            mem::replace(end, Nil)
+        };
+        (self, back)
+    }
+
+    /// Consumes the list, returning a list of its first `n` items.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// assert_eq!(list!(1,2,3).take(2), list!(1,2));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn take(self, n: usize) -> List<T> {
+        self.split_at(n).0
+    }
+
+    /// Consumes the list, returning a list of all but its first `n` items.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// assert_eq!(list!(1,2,3).drop(2), list!(3));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn drop(self, n: usize) -> List<T> {
+        self.split_at(n).1
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_combinators", issue = "94") )]
+impl<T> List<List<T>> {
+
+    /// Consumes a list of lists, returning a list of all of their items
+    /// in order.
+    ///
+    /// The cells of the inner lists are linked together as they are, so
+    /// this is O(_n_) in the total number of items, and doesn't allocate.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::List;
+    /// # use seax_util::list::List::{Cons,Nil};
+    /// # fn main() {
+    /// let lists = list!(list!(1,2), List::new(), list!(3));
+    /// assert_eq!(lists.flatten(), list!(1,2,3));
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "unstable",
+        unstable(feature = "list_combinators", issue = "94") )]
+    pub fn flatten(self) -> List<T> {
+        let mut result = Nil;
+        {
+            let mut end = &mut result;
+            for list in self {
+                *end = list;
+                while let Cons(_, ref mut tail) = *end {
+                    end = tail;
+                }
+            }
+        }
+        result
+    }
 }
 #[cfg_attr(feature = "unstable",
     stable(feature = "list", since="0.1.0") )]
@@ -446,7 +1077,11 @@ where T: fmt::Display {
         stable(feature = "list", since="0.1.0") )]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut it = self.iter();
-        write!(f, "({}{})", it.next().unwrap(), it.fold(
+        let first = match it.next() {
+            Some(first) => first,
+            None => return write!(f, "()")
+        };
+        write!(f, "({}{})", first, it.fold(
             String::new(),
             |mut a, i| { a.push_str(format!(", {}", i).as_ref()); a} )
         )
@@ -460,13 +1095,73 @@ where T: fmt::Debug {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Cons(ref head, ref tail) => write!(f, "({:?} . {:?})", head, tail),
-            Nil => write!(f,"nil")
+        for head in self.iter() {
+            write!(f, "({:?} . ", head)?;
         }
+        write!(f, "nil")?;
+        for _ in self.iter() {
+            write!(f, ")")?;
+        }
+        Ok(())
     }
 
 }
+/// Drops a list one cell at a time.
+///
+/// Each cell's tail is detached before the cell is dropped, so dropping a
+/// long list does not recurse once per cell.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_drop", issue = "94") )]
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut tail = match *self {
+            Cons(_, ref mut tail) => mem::replace(&mut **tail, Nil),
+            Nil => return
+        };
+        loop {
+            // dropping the old value of `tail` drops only its head
+            tail = match tail {
+                Cons(_, ref mut next) => mem::replace(&mut **next, Nil),
+                Nil => return
+            };
+        }
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    stable(feature = "list", since="0.1.0") )]
+impl<T> Clone for List<T>
+where T: Clone {
+    fn clone(&self) -> List<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    stable(feature = "list", since="0.1.0") )]
+impl<T> PartialEq for List<T>
+where T: PartialEq {
+    fn eq(&self, other: &List<T>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    stable(feature = "list", since="0.1.0") )]
+impl<T> Eq for List<T> where T: Eq {}
+
+#[cfg_attr(feature = "unstable",
+    stable(feature = "list", since="0.1.0") )]
+impl<T> Hash for List<T>
+where T: Hash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.length().hash(state);
+        for item in self.iter() {
+            item.hash(state);
+        }
+    }
+}
+
 #[cfg_attr(feature = "unstable",
     stable(feature = "list", since="0.1.2") )]
 impl<T> Default for List<T> {
@@ -481,8 +1176,8 @@ impl<T> Default for List<T> {
 impl<T> FromIterator<T> for List<T> {
     /// Build a `List<T>` from a structure implementing `IntoIterator<T>`.
     ///
-    /// This takes advantage of the `List.append_chain()` method under the
-    /// hood to provide roughly O(_n_) performance.
+    /// This uses a `ListBuilder` under the hood, so each item is appended
+    /// in O(1).
     ///
     /// # Examples
     ///
@@ -500,11 +1195,7 @@ impl<T> FromIterator<T> for List<T> {
     #[cfg_attr(feature = "unstable",
         stable(feature = "list", since="0.1.0") )]
     fn from_iter<I>(iterable: I) -> List<T> where I: IntoIterator<Item=T> {
-            let mut result  = List::new();
-            iterable
-                .into_iter()
-                .fold(&mut result, |l, it| l.append_chain(it));
-            result
+            ListBuilder::from_iter(iterable).build()
     }
 
 }
@@ -575,6 +1266,173 @@ impl<'a, T> ExactSizeIterator for ListIterator<'a, T> {
         self.current.length()
     }
 }
+
+/// Iterator over mutable references to the items of a `List<T>`.
+///
+/// This is returned by `List::iter_mut()`.
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+pub struct ListIterMut<'a, T:'a> { current: Option<&'a mut List<T>> }
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<'a, T> Iterator for ListIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        match self.current.take() {
+            Some(&mut Cons(ref mut head, ref mut tail)) => {
+                self.current = Some(&mut **tail);
+                Some(head)
+            },
+            _ => None
+        }
+    }
+}
+
+/// Owning iterator over the items of a `List<T>`.
+///
+/// This is returned by `List::into_iter()`, and yields each item by value,
+/// freeing the list's cells as it goes.
+///
+/// It may also be iterated from the back. Since a `List` can only be
+/// walked from the front, the first call to `next_back()` moves the
+/// remaining items into a buffer, which takes O(_n_) time and space.
+///
+/// # Examples:
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::List;
+/// # use seax_util::list::List::{Cons, Nil};
+/// # fn main () {
+/// let list = list!(String::from("a"), String::from("b"));
+/// let mut strings = Vec::new();
+/// for s in list {
+///     strings.push(s);
+/// }
+/// assert_eq!(strings, vec!["a", "b"]);
+/// # }
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+pub struct IntoIter<T> {
+    list: List<T>,
+    // Items moved out of `list` by `next_back()`, which follow any items
+    // still in `list`.
+    buffer: VecDeque<T>
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        mem::replace(&mut self.list, Nil)
+            .pop()
+            .map(|(item, rest)| { self.list = rest; item })
+            .or_else(|| self.buffer.pop_front())
+    }
+}
+
+/// # Examples:
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::List;
+/// # use seax_util::list::List::{Cons, Nil};
+/// # fn main () {
+/// let mut items = list!(1, 2, 3, 4).into_iter();
+/// assert_eq!(items.next(), Some(1));
+/// assert_eq!(items.next_back(), Some(4));
+/// assert_eq!(items.rev().collect::<Vec<_>>(), vec![3, 2]);
+/// # }
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        if !self.list.is_empty() {
+            let mut items = mem::replace(&mut self.list, Nil).into_iter();
+            items.buffer.append(&mut self.buffer);
+            self.buffer = items.collect();
+        }
+        self.buffer.pop_back()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter{list: self, buffer: VecDeque::new()}
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = ListIterator<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> ListIterator<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = ListIterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> ListIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Appends the items of an iterator to the end of a `List<T>`.
+///
+/// The list is walked once to find its end, so extending a list is
+/// O(_n_ + _m_) rather than O(_n_ * _m_).
+///
+/// # Examples:
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::List;
+/// # use seax_util::list::List::{Cons, Nil};
+/// # fn main () {
+/// let mut list = list!(1,2);
+/// list.extend(vec![3,4]);
+/// list.extend(&[5]);
+/// assert_eq!(list, list!(1,2,3,4,5));
+/// # }
+/// ```
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<T> Extend<T> for List<T> {
+    fn extend<I>(&mut self, iterable: I) where I: IntoIterator<Item=T> {
+        iterable
+            .into_iter()
+            .fold(self, |l, it| l.append_chain(it));
+    }
+}
+
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "list_iter", issue = "94") )]
+impl<'a, T> Extend<&'a T> for List<T>
+where T: Copy + 'a {
+    fn extend<I>(&mut self, iterable: I) where I: IntoIterator<Item=&'a T> {
+        self.extend(iterable.into_iter().cloned())
+    }
+}
 /// Implementation of indexing for `List<T>`.
 ///
 /// # Examples:
@@ -705,3 +1563,21 @@ impl<T> Index<isize> for List<T> {
         }
     }
 }
+
+/// Generates random lists for property tests.
+///
+/// Lists are generated and shrunk as though they were vectors.
+#[cfg(any(test, feature = "arbitrary"))]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "arbitrary", issue = "94") )]
+impl<T> Arbitrary for List<T>
+where T: Arbitrary {
+    fn arbitrary(g: &mut Gen) -> List<T> {
+        Vec::<T>::arbitrary(g).into_iter().collect()
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = List<T>>> {
+        let items: Vec<T> = self.iter().cloned().collect();
+        Box::new(items.shrink().map(|items| items.into_iter().collect()))
+    }
+}
@@ -0,0 +1,420 @@
+//! Persistent lists which share their tails.
+//!
+//! `RcList<T>` and `ArcList<T>` are immutable `cons` lists whose links are
+//! reference-counted pointers, rather than `Box`es. Pushing an item onto
+//! one of these lists, taking its tail, or cloning it is O(1), and never
+//! copies any items; the new list shares its tail with the old one. This
+//! makes them suitable for VM registers such as the environment and dump,
+//! which are frequently saved and restored.
+//!
+//! `RcList<T>` uses `Rc`, and `ArcList<T>` uses `Arc`, so that it may be
+//! shared between threads. Otherwise, they are identical.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::Index;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::{List, Stack, StackPeek};
+
+/// Convenience macro for making `RcList`s.
+///
+/// # Example:
+///
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::{RcList, Stack};
+/// # fn main () {
+/// let list = rc_list!(1, 2, 3);
+/// assert_eq!(list, RcList::empty().push(3).push(2).push(1));
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "shared_list", issue = "94") )]
+macro_rules! rc_list(
+    ( $($e:expr),* ) => (
+        vec![ $( $e ),* ].into_iter().collect::<$crate::list::RcList<_>>()
+    );
+);
+
+/// Convenience macro for making `ArcList`s.
+///
+/// # Example:
+///
+/// ```
+/// # #[macro_use] extern crate seax_util;
+/// # use seax_util::list::{ArcList, Stack};
+/// # fn main () {
+/// let list = arc_list!(1, 2, 3);
+/// assert_eq!(list, ArcList::empty().push(3).push(2).push(1));
+/// # }
+/// ```
+#[macro_export]
+#[cfg_attr(feature = "unstable",
+    unstable(feature = "shared_list", issue = "94") )]
+macro_rules! arc_list(
+    ( $($e:expr),* ) => (
+        vec![ $( $e ),* ].into_iter().collect::<$crate::list::ArcList<_>>()
+    );
+);
+
+/// Defines a persistent list type using the pointer type `$ptr`.
+macro_rules! shared_list {
+    ( $(#[$attr:meta])*
+      list $name:ident, node $node:ident, iter $iter:ident, ptr $ptr:ident ) => {
+
+        $(#[$attr])*
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        pub struct $name<T> {
+            head: Option<$ptr<$node<T>>>
+        }
+
+        struct $node<T> {
+            item: T,
+            next: Option<$ptr<$node<T>>>
+        }
+
+        /// An iterator over references to the items in a list.
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        pub struct $iter<'a, T: 'a> {
+            current: Option<&'a $node<T>>
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> $name<T> {
+            /// Creates a new empty list.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            #[inline]
+            pub fn new() -> $name<T> { $name { head: None } }
+
+            /// Returns a new list with `item` in front of this one.
+            ///
+            /// This list is not modified, and shares its items with the
+            /// new list. This is an O(1) operation.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            pub fn cons(&self, item: T) -> $name<T> {
+                $name {
+                    head: Some($ptr::new($node {
+                        item,
+                        next: self.head.clone()
+                    }))
+                }
+            }
+
+            /// Returns the first item in the list, or `None` if the list
+            /// is empty.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            #[inline]
+            pub fn head(&self) -> Option<&T> {
+                self.head.as_ref().map(|node| &node.item)
+            }
+
+            /// Returns the tail of the list, which is empty if the list is.
+            ///
+            /// The tail is shared with this list, rather than copied. This
+            /// is an O(1) operation.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            pub fn tail(&self) -> $name<T> {
+                $name {
+                    head: self.head.as_ref().and_then(|node| node.next.clone())
+                }
+            }
+
+            /// Returns the length of the list.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            pub fn length(&self) -> usize {
+                self.iter().count()
+            }
+
+            /// Returns true if the list is empty, false otherwise.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            #[inline]
+            pub fn is_empty(&self) -> bool { self.head.is_none() }
+
+            /// Provide a forward iterator.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            #[inline]
+            pub fn iter<'a>(&'a self) -> $iter<'a, T> {
+                $iter { current: self.head.as_ref().map(|node| &**node) }
+            }
+
+            /// Optionally index the list.
+            ///
+            /// Unlike list indexing syntax (`list[i]`), this returns `None`
+            /// if the index is out of bounds rather than panicking.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            pub fn get(&self, index: usize) -> Option<&T> {
+                self.iter().nth(index)
+            }
+
+            /// Returns true if both lists are the same list in memory.
+            ///
+            /// Two empty lists are always the same. Lists which are the
+            /// same are always equal, so this is a cheap way to compare
+            /// lists which are likely to share structure.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            pub fn ptr_eq(&self, other: &$name<T>) -> bool {
+                match (self.head.as_ref(), other.head.as_ref()) {
+                    (Some(a), Some(b)) => $ptr::ptr_eq(a, b),
+                    (None, None) => true,
+                    _ => false
+                }
+            }
+
+            /// Copies the list's items into a `List<T>`.
+            #[cfg_attr(feature = "unstable",
+                unstable(feature = "shared_list", issue = "94") )]
+            pub fn to_list(&self) -> List<T>
+            where T: Clone {
+                self.iter().cloned().collect()
+            }
+        }
+
+        /// Stack implementation using a persistent list.
+        ///
+        /// Popping an item which is not shared with another list moves it
+        /// out of the list; popping a shared item clones it.
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> Stack<T> for $name<T>
+        where T: Clone {
+            #[inline]
+            fn push(self, item: T) -> $name<T> { self.cons(item) }
+
+            fn pop(mut self) -> Option<(T, $name<T>)> {
+                self.head.take().map(|node| match $ptr::try_unwrap(node) {
+                    Ok(node) => (node.item, $name { head: node.next }),
+                    Err(node) => (node.item.clone(),
+                                  $name { head: node.next.clone() })
+                })
+            }
+
+            #[inline]
+            fn peek(&self) -> Option<&T> { self.head() }
+
+            #[inline]
+            fn empty() -> $name<T> { $name::new() }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "stack_ops", issue = "94") )]
+        impl<T> StackPeek<T> for $name<T>
+        where T: Clone {
+            #[inline]
+            fn peek_n(&self, n: usize) -> Option<&T> { self.get(n) }
+
+            #[inline]
+            fn depth(&self) -> usize { self.length() }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> Drop for $name<T> {
+            /// Drops the list's nodes one at a time, rather than
+            /// recursively, stopping at the first node which is shared
+            /// with another list.
+            ///
+            /// `into_inner()` hands a node to exactly one of the handles
+            /// dropping it, even if they are dropped concurrently, so the
+            /// last owner always continues the walk down the tail.
+            fn drop(&mut self) {
+                let mut head = self.head.take();
+                while let Some(node) = head {
+                    head = $ptr::into_inner(node)
+                        .and_then(|mut node| node.next.take());
+                }
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> Clone for $name<T> {
+            /// Returns a list which shares all of this list's items.
+            ///
+            /// This is an O(1) operation.
+            #[inline]
+            fn clone(&self) -> $name<T> { $name { head: self.head.clone() } }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> Default for $name<T> {
+            fn default() -> $name<T> { $name::new() }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> PartialEq for $name<T>
+        where T: PartialEq {
+            fn eq(&self, other: &$name<T>) -> bool {
+                self.ptr_eq(other) || self.iter().eq(other.iter())
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> Eq for $name<T> where T: Eq {}
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> Hash for $name<T>
+        where T: Hash {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.length().hash(state);
+                for item in self.iter() {
+                    item.hash(state);
+                }
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> fmt::Display for $name<T>
+        where T: fmt::Display {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut it = self.iter();
+                match it.next() {
+                    Some(first) => write!(f, "({}", first)?,
+                    None => return write!(f, "()")
+                }
+                for item in it {
+                    write!(f, ", {}", item)?;
+                }
+                write!(f, ")")
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> fmt::Debug for $name<T>
+        where T: fmt::Debug {
+            /// Formats the list in the same way as a `List<T>`.
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                for item in self.iter() {
+                    write!(f, "({:?} . ", item)?;
+                }
+                write!(f, "nil")?;
+                for _ in self.iter() {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> FromIterator<T> for $name<T> {
+            fn from_iter<I>(iterable: I) -> $name<T>
+            where I: IntoIterator<Item = T> {
+                let items: Vec<T> = iterable.into_iter().collect();
+                let mut head = None;
+                for item in items.into_iter().rev() {
+                    head = Some($ptr::new($node { item, next: head }));
+                }
+                $name { head }
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> From<List<T>> for $name<T> {
+            fn from(list: List<T>) -> $name<T> {
+                list.into_iter().collect()
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<T> Index<usize> for $name<T> {
+            type Output = T;
+
+            fn index(&self, index: usize) -> &T {
+                self.get(index)
+                    .unwrap_or_else(|| panic!("list index {} out of range", index))
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<'a, T> IntoIterator for &'a $name<T> {
+            type Item = &'a T;
+            type IntoIter = $iter<'a, T>;
+
+            fn into_iter(self) -> $iter<'a, T> { self.iter() }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<'a, T> Iterator for $iter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<&'a T> {
+                self.current.map(|node| {
+                    self.current = node.next.as_ref().map(|next| &**next);
+                    &node.item
+                })
+            }
+        }
+
+        #[cfg_attr(feature = "unstable",
+            unstable(feature = "shared_list", issue = "94") )]
+        impl<'a, T> Clone for $iter<'a, T> {
+            fn clone(&self) -> $iter<'a, T> { $iter { current: self.current } }
+        }
+    }
+}
+
+shared_list! {
+    /// A persistent `cons` list which shares its tail using `Rc`.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::{RcList, Stack};
+    /// # fn main () {
+    /// let env = rc_list!(2, 3);
+    /// // pushing onto a clone shares the original's items
+    /// let frame = env.clone().push(1);
+    /// assert_eq!(frame, rc_list!(1, 2, 3));
+    /// assert!(frame.tail().ptr_eq(&env));
+    /// assert_eq!(frame[2], 3);
+    /// # }
+    /// ```
+    list RcList, node RcNode, iter RcListIter, ptr Rc
+}
+
+shared_list! {
+    /// A persistent `cons` list which shares its tail using `Arc`.
+    ///
+    /// This may be sent between threads, if its items may.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate seax_util;
+    /// # use seax_util::list::{ArcList, Stack};
+    /// # use std::thread;
+    /// # fn main () {
+    /// let env = arc_list!(2, 3);
+    /// let shared = env.clone();
+    /// let frame = thread::spawn(move || shared.push(1)).join().unwrap();
+    /// assert_eq!(frame, arc_list!(1, 2, 3));
+    /// assert!(frame.tail().ptr_eq(&env));
+    /// # }
+    /// ```
+    list ArcList, node ArcNode, iter ArcListIter, ptr Arc
+}
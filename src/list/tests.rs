@@ -1,6 +1,12 @@
-use super::{List, Stack};
+use super::{List, Stack, StackMut, StackPeek, RcList, ArcList, ListBuilder, DottedList,
+            ArenaList, ConsArena};
 use super::List::{Cons,Nil};
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
 #[test]
 fn test_list_length() {
     let full_list: List<i32> = list!(1i32, 2i32, 3i32);
@@ -93,3 +99,678 @@ fn test_list_iter() {
     let slice: &str = string.as_ref(); // this is necessary because assert_eq! is weird
     assert_eq!(slice, "1, 2, 3, 4, 5, 6, ")
 }
+
+#[test]
+fn test_list_into_iter() {
+    let l: List<String> = list!(String::from("a"), String::from("b"));
+    let items: Vec<String> = l.into_iter().collect();
+    assert_eq!(items, vec!["a", "b"]);
+    assert_eq!(List::<i32>::new().into_iter().next(), None);
+}
+
+#[test]
+fn test_list_into_iter_rev() {
+    let l: List<i32> = list!(1, 2, 3, 4, 5);
+    assert_eq!(l.clone().into_iter().rev().collect::<Vec<_>>(), vec![5, 4, 3, 2, 1]);
+    let mut items = l.into_iter();
+    assert_eq!(items.next_back(), Some(5));
+    assert_eq!(items.next(), Some(1));
+    assert_eq!(items.next(), Some(2));
+    assert_eq!(items.next_back(), Some(4));
+    assert_eq!(items.next_back(), Some(3));
+    assert_eq!(items.next(), None);
+    assert_eq!(items.next_back(), None);
+    assert_eq!(List::<i32>::new().into_iter().next_back(), None);
+
+    let mut items = long_list().into_iter();
+    assert_eq!(items.next_back(), Some(999999));
+    assert_eq!(items.count(), 999999);
+}
+
+#[test]
+fn test_list_for_loops() {
+    let mut l: List<i32> = list!(1, 2, 3);
+    for item in &mut l {
+        *item += 1;
+    }
+    let mut sum = 0;
+    for item in &l {
+        sum += *item;
+    }
+    assert_eq!(sum, 9);
+    let mut owned = Vec::new();
+    for item in l {
+        owned.push(item);
+    }
+    assert_eq!(owned, vec![2, 3, 4]);
+}
+
+#[test]
+fn test_list_iter_mut_empty() {
+    let mut l: List<i32> = List::new();
+    assert!(l.iter_mut().next().is_none());
+}
+
+#[test]
+fn test_list_extend() {
+    let mut l: List<i32> = List::new();
+    l.extend(vec![1, 2]);
+    l.extend(&[3, 4]);
+    l.extend(Vec::<i32>::new());
+    assert_eq!(l, list!(1, 2, 3, 4));
+}
+
+#[test]
+fn test_list_into_iter_long() {
+    let mut expected = 0;
+    for item in long_list() {
+        assert_eq!(item, expected);
+        expected += 1;
+    }
+    assert_eq!(expected, 1000000);
+}
+
+#[test]
+fn test_list_extend_long() {
+    let mut l = long_list();
+    l.extend(0..1000000);
+    assert_eq!(l.length(), 2000000);
+}
+
+#[test]
+fn test_list_builder() {
+    let mut builder = ListBuilder::new();
+    assert!(builder.is_empty());
+    assert_eq!(builder.as_list(), &List::new());
+    builder.push_back(1);
+    builder.push_back(2);
+    assert_eq!(builder.len(), 2);
+    assert_eq!(builder.as_list(), &list!(1, 2));
+    builder.extend(vec![3, 4]);
+    assert_eq!(builder.build(), list!(1, 2, 3, 4));
+}
+
+#[test]
+fn test_list_builder_from_list() {
+    let mut builder = ListBuilder::from(list!(1, 2, 3));
+    assert_eq!(builder.len(), 3);
+    builder.push_back(4);
+    assert_eq!(builder.build(), list!(1, 2, 3, 4));
+
+    let mut builder = ListBuilder::from(List::new());
+    assert!(builder.is_empty());
+    builder.push_back(1);
+    assert_eq!(builder.build(), list!(1));
+}
+
+#[test]
+fn test_list_builder_moved() {
+    let mut builders = Vec::new();
+    for i in 0..4 {
+        let mut builder = ListBuilder::new();
+        builder.push_back(i);
+        builders.push(builder);
+    }
+    // the builders have been moved into the vector's buffer, and may have
+    // been moved again when it grew
+    for (i, builder) in builders.iter_mut().enumerate() {
+        builder.push_back(i * 10);
+    }
+    let lists: Vec<List<usize>> = builders.into_iter()
+        .map(ListBuilder::build)
+        .collect();
+    assert_eq!(lists[3], list!(3, 30));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_list_builder_long() {
+    let mut builder = ListBuilder::new();
+    for i in 0..1000000 {
+        builder.push_back(i);
+    }
+    assert_eq!(builder.len(), 1000000);
+    assert_eq!(builder.build(), long_list());
+}
+
+#[test]
+fn test_list_map_filter_fold() {
+    let l: List<i32> = list!(1, 2, 3, 4);
+    assert_eq!(l.map(|x| x.to_string()), list!(
+        String::from("1"), String::from("2"),
+        String::from("3"), String::from("4")));
+    assert_eq!(l.fold(1, |acc, x| acc * x), 24);
+    assert_eq!(l.filter(|x| *x > 2), list!(3, 4));
+    assert_eq!(List::<i32>::new().map(|x| x + 1), List::new());
+}
+
+#[test]
+fn test_list_rev() {
+    let l: List<i32> = list!(1, 2, 3);
+    assert_eq!(l.rev(), list!(3, 2, 1));
+    assert_eq!(List::<i32>::new().rev(), List::new());
+}
+
+#[test]
+fn test_list_concat() {
+    let mut l: List<i32> = List::new();
+    l.append_list(list!(1));
+    l.append_list(List::new());
+    assert_eq!(l.concat(list!(2, 3)), list!(1, 2, 3));
+}
+
+#[test]
+fn test_list_zip() {
+    let l: List<i32> = list!(1, 2);
+    assert_eq!(l.zip(list!("a", "b", "c")), list!((1, "a"), (2, "b")));
+}
+
+#[test]
+fn test_list_split_take_drop() {
+    let l: List<i32> = list!(1, 2, 3);
+    let (front, back) = l.clone().split_at(0);
+    assert_eq!(front, List::new());
+    assert_eq!(back, list!(1, 2, 3));
+    let (front, back) = l.clone().split_at(3);
+    assert_eq!(front, list!(1, 2, 3));
+    assert_eq!(back, List::new());
+    assert_eq!(l.clone().take(0), List::new());
+    assert_eq!(l.clone().take(10), list!(1, 2, 3));
+    assert_eq!(l.clone().drop(1), list!(2, 3));
+    assert_eq!(l.drop(10), List::new());
+}
+
+#[test]
+fn test_list_flatten() {
+    let l: List<List<i32>> = list!(List::new(), list!(1), List::new(), list!(2, 3));
+    assert_eq!(l.flatten(), list!(1, 2, 3));
+    assert_eq!(List::<List<i32>>::new().flatten(), List::new());
+}
+
+#[test]
+fn test_list_combinators_long() {
+    let l = long_list();
+    assert_eq!(l.fold(0, |acc, x| acc + x), 499999500000);
+    let doubled = l.map(|x| x * 2);
+    assert_eq!(*doubled.last(), 1999998);
+    let evens = doubled.filter(|x| x % 4 == 0);
+    assert_eq!(evens.length(), 500000);
+    let reversed = l.rev();
+    assert_eq!(reversed[0usize], 999999);
+    let (front, back) = reversed.split_at(500000);
+    let joined = back.concat(front);
+    assert_eq!(joined[0usize], 499999);
+    assert_eq!(joined.length(), 1000000);
+    let pairs = joined.zip(long_list());
+    assert_eq!(pairs.length(), 1000000);
+    let halves = list!(long_list().take(500000), long_list().drop(500000));
+    assert_eq!(halves.flatten(), long_list());
+}
+
+#[test]
+fn test_dotted_list_conversions() {
+    let improper = DottedList::from_list(list!(1, 2, 3)).unwrap();
+    assert_eq!(improper.items(), &list!(1, 2));
+    assert_eq!(improper.last_cdr(), &3);
+    assert_eq!(improper.car(), &1);
+    assert_eq!(improper.length(), 2);
+    assert_eq!(improper.clone().into_list(), list!(1, 2, 3));
+    assert_eq!(improper.cons(0).into_parts(), (list!(0, 1, 2), 3));
+    assert_eq!(DottedList::from_list(List::<i32>::new()), Err(List::new()));
+    assert_eq!(DottedList::new(list!(1), 2), Ok(DottedList::pair(1, 2)));
+}
+
+#[test]
+fn test_dotted_list_formatting() {
+    let pair = DottedList::pair(1, 2);
+    assert_eq!(pair.to_string(), "(1 . 2)");
+    assert_eq!(format!("{:?}", pair), "(1 . 2)");
+    let improper = pair.cons(0);
+    assert_eq!(improper.to_string(), "(0, 1 . 2)");
+    assert_eq!(format!("{:?}", improper), "(0 . (1 . 2))");
+}
+
+#[test]
+fn test_dotted_list_long() {
+    let improper = DottedList::new(long_list(), 0).unwrap();
+    assert_eq!(improper.clone(), improper);
+    assert_eq!(improper.into_list().length(), 1000001);
+}
+
+#[test]
+fn test_stack_ops() {
+    let s: List<i32> = list!(1, 2, 3);
+    assert_eq!(s.depth(), 3);
+    assert_eq!(s.peek_n(0), Some(&1));
+    assert_eq!(s.peek_n(2), Some(&3));
+    assert_eq!(s.peek_n(3), None);
+    assert_eq!(s.clone().pop2(), Some((1, 2, list!(3))));
+    assert_eq!(s.clone().pop_n(3), Some((vec![1, 2, 3], List::new())));
+    assert_eq!(s.clone().pop_n(0), Some((vec![], list!(1, 2, 3))));
+    assert_eq!(s.clone().pop_n(4), None);
+    assert_eq!(s.clone().dup(), Some(list!(1, 1, 2, 3)));
+    assert_eq!(s.clone().swap(), Some(list!(2, 1, 3)));
+    assert_eq!(s.clone().rot(), Some(list!(3, 1, 2)));
+    assert_eq!(s.clone().drain_to(list!(4)), list!(3, 2, 1, 4));
+    assert_eq!(list!(1).pop2(), None);
+    assert_eq!(List::<i32>::new().dup(), None);
+    assert_eq!(list!(1).swap(), None);
+    assert_eq!(list!(1, 2).rot(), None);
+}
+
+/// A stack implementing only `Stack`'s required methods, as a stack from
+/// outside this crate would.
+#[derive(Debug,PartialEq)]
+struct VecStack(Vec<i32>);
+
+impl Stack<i32> for VecStack {
+    fn push(mut self, item: i32) -> VecStack { self.0.push(item); self }
+    fn pop(mut self) -> Option<(i32, VecStack)> {
+        self.0.pop().map(|item| (item, self))
+    }
+    fn peek(&self) -> Option<&i32> { self.0.last() }
+    fn empty() -> VecStack { VecStack(Vec::new()) }
+}
+
+#[test]
+fn test_stack_ops_provided() {
+    let s = VecStack(vec![3, 2, 1]);
+    assert_eq!(s.swap(), Some(VecStack(vec![3, 1, 2])));
+    let (a, b, rest) = VecStack(vec![3, 2, 1]).pop2().unwrap();
+    assert_eq!((a, b), (1, 2));
+    assert_eq!(rest, VecStack(vec![3]));
+    let mut s = VecStack(vec![1]);
+    assert!(s.dup_mut());
+    assert_eq!(s, VecStack(vec![1, 1]));
+}
+
+#[test]
+fn test_stack_mut_ops() {
+    let mut s: List<i32> = List::new();
+    assert_eq!(s.pop_mut(), None);
+    assert!(!s.dup_mut());
+    s.push_mut(3);
+    s.push_mut(2);
+    assert!(!s.rot_mut());
+    assert_eq!(s, list!(2, 3));
+    s.push_mut(1);
+    assert!(s.rot_mut());
+    assert_eq!(s, list!(3, 1, 2));
+    assert!(s.swap_mut());
+    assert_eq!(s, list!(1, 3, 2));
+    assert!(s.dup_mut());
+    assert_eq!(s.pop2_mut(), Some((1, 1)));
+    assert_eq!(s.pop_n_mut(3), None);
+    assert_eq!(s, list!(3, 2));
+    let mut other = list!(4);
+    s.drain_to_mut(&mut other);
+    assert!(s.is_empty());
+    assert_eq!(other, list!(2, 3, 4));
+    assert_eq!(other.pop_n_mut(2), Some(vec![2, 3]));
+    assert_eq!(other.pop2_mut(), None);
+    assert_eq!(other, list!(4));
+}
+
+#[test]
+fn test_shared_stack_ops() {
+    let mut s: RcList<i32> = rc_list!(1, 2, 3);
+    assert_eq!(s.depth(), 3);
+    assert_eq!(s.peek_n(1), Some(&2));
+    assert!(s.rot_mut());
+    assert_eq!(s, rc_list!(3, 1, 2));
+    let a: ArcList<i32> = arc_list!(1, 2);
+    assert_eq!(a.swap(), Some(arc_list!(2, 1)));
+}
+
+fn hash_of<T: Hash>(it: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    it.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn long_list() -> List<usize> {
+    (0..1000000).collect()
+}
+
+#[test]
+fn test_list_drop_long() {
+    let l = long_list();
+    assert_eq!(l.length(), 1000000);
+    drop(l);
+}
+
+#[test]
+fn test_list_clone_long() {
+    let l = long_list();
+    let cloned = l.clone();
+    assert_eq!(cloned.length(), 1000000);
+    assert_eq!(*cloned.last(), 999999);
+}
+
+#[test]
+fn test_list_eq_long() {
+    let a = long_list();
+    let mut b = long_list();
+    assert_eq!(a, b);
+    b = b.push(1);
+    assert!(a != b);
+}
+
+#[test]
+fn test_list_hash_long() {
+    assert_eq!(hash_of(&long_list()), hash_of(&long_list()));
+    assert!(hash_of(&long_list()) != hash_of(&long_list().push(0)));
+}
+
+#[test]
+fn test_list_debug_long() {
+    let s = format!("{:?}", long_list());
+    assert!(s.starts_with("(0 . (1 . (2 . "));
+    let body = s.trim_end_matches(')');
+    assert!(body.ends_with("(999998 . (999999 . nil"));
+    assert_eq!(s.len() - body.len(), 1000000);
+}
+
+#[test]
+fn test_list_debug() {
+    let l: List<i32> = list!(1, 2);
+    assert_eq!(format!("{:?}", l), "(1 . (2 . nil))");
+    assert_eq!(format!("{:?}", List::<i32>::new()), "nil");
+}
+
+#[test]
+fn test_list_pop_long() {
+    let mut l = long_list();
+    for i in 0..1000000 {
+        let (item, rest) = l.pop().unwrap();
+        assert_eq!(item, i);
+        l = rest;
+    }
+    assert!(l.is_empty());
+}
+
+#[test]
+fn test_rc_list_stack() {
+    let mut s: RcList<i32> = Stack::empty();
+    assert_eq!(s.peek(), None);
+    s = s.push(1);
+    s = s.push(6);
+    assert_eq!(s.peek(), Some(&6));
+    let (top, rest) = s.pop().unwrap();
+    assert_eq!(top, 6);
+    assert_eq!(rest.peek(), Some(&1));
+    assert_eq!(rest.pop().map(|(top, rest)| (top, rest.is_empty())),
+               Some((1, true)));
+}
+
+#[test]
+fn test_rc_list_shares_tails() {
+    let env = rc_list!(String::from("b"), String::from("c"));
+    let frame = env.clone().push(String::from("a"));
+    assert!(frame.tail().ptr_eq(&env));
+    assert!(env.clone().ptr_eq(&env));
+    assert!(!rc_list!(1).ptr_eq(&rc_list!(1)));
+    assert!(RcList::<i32>::new().ptr_eq(&RcList::new()));
+    // popping a shared item copies it, leaving the original intact
+    let (top, rest) = frame.clone().pop().unwrap();
+    assert_eq!(top, "a");
+    assert!(rest.ptr_eq(&env));
+    assert_eq!(frame.length(), 3);
+    // popping the last reference moves the item out
+    let (top, _) = frame.pop().unwrap();
+    assert_eq!(top, "a");
+    assert_eq!(env, rc_list!(String::from("b"), String::from("c")));
+}
+
+#[test]
+fn test_rc_list_indexing() {
+    let l = rc_list!(1, 2, 3, 4, 5, 6);
+    for i in 0..6 {
+        assert_eq!(l[i], i + 1);
+    }
+    assert_eq!(l.get(6), None);
+}
+
+#[test]
+#[should_panic(expected = "list index 3 out of range")]
+fn test_rc_list_index_out_of_range() {
+    let l = rc_list!(1, 2, 3);
+    l[3];
+}
+
+#[test]
+fn test_rc_list_conversions() {
+    let list: List<i32> = list!(1, 2, 3);
+    let shared = RcList::from(list.clone());
+    assert_eq!(shared, rc_list!(1, 2, 3));
+    assert_eq!(shared.to_list(), list);
+    assert_eq!(shared.iter().cloned().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(rc_list!(), RcList::<i32>::new());
+}
+
+#[test]
+fn test_rc_list_formatting() {
+    let l = rc_list!(1, 2, 3);
+    assert_eq!(l.to_string(), "(1, 2, 3)");
+    assert_eq!(format!("{:?}", l), format!("{:?}", list!(1, 2, 3)));
+    assert_eq!(RcList::<i32>::new().to_string(), "()");
+    assert_eq!(format!("{:?}", RcList::<i32>::new()), "nil");
+}
+
+#[test]
+fn test_rc_list_hash() {
+    let a = rc_list!(1, 2, 3);
+    let b = rc_list!(1, 2, 3);
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    assert!(a != rc_list!(1, 2));
+}
+
+#[test]
+fn test_rc_list_drop_long() {
+    let l: RcList<usize> = (0..1000000).collect();
+    let shared = l.tail();
+    drop(l);
+    assert_eq!(shared.length(), 999999);
+}
+
+#[test]
+fn test_arc_list_across_threads() {
+    let env: ArcList<i32> = arc_list!(2, 3);
+    let shared = env.clone();
+    let frame = thread::spawn(move || shared.push(1)).join().unwrap();
+    assert_eq!(frame, arc_list!(1, 2, 3));
+    assert!(frame.tail().ptr_eq(&env));
+    assert_eq!(frame[0], 1);
+    let (top, rest) = frame.pop().unwrap();
+    assert_eq!(top, 1);
+    assert!(rest.ptr_eq(&env));
+}
+
+#[test]
+fn test_cons_arena_reuses_slots() {
+    let mut arena = ConsArena::new();
+    let a = arena.alloc(1, None);
+    let b = arena.alloc(2, Some(a));
+    assert_eq!(arena.len(), 2);
+    assert_eq!(arena.cdr(b), Some(a));
+    *arena.car_mut(a) = 3;
+    assert_eq!(arena.free(a), (3, None));
+    assert!(!arena.contains(a));
+    arena.set_cdr(b, None);
+    let c = arena.alloc(4, None);
+    assert_eq!(c, a);
+    assert_eq!(arena.len(), 2);
+    assert_eq!(arena.num_slots(), 2);
+    arena.clear();
+    assert!(arena.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "not allocated")]
+fn test_cons_arena_double_free() {
+    let mut arena = ConsArena::new();
+    let a = arena.alloc(1, None);
+    arena.free(a);
+    arena.free(a);
+}
+
+#[test]
+fn test_arena_list_stack() {
+    let s: ArenaList<i32> = ArenaList::new().push(3).push(2).push(1);
+    assert_eq!(s.peek(), Some(&1));
+    assert_eq!(s.peek_n(2), Some(&3));
+    assert_eq!(s.depth(), 3);
+    let (top, s) = s.pop().unwrap();
+    assert_eq!(top, 1);
+    let (a, b, s) = s.pop2().unwrap();
+    assert_eq!((a, b), (2, 3));
+    assert!(s.is_empty());
+    assert!(s.pop().is_none());
+}
+
+#[test]
+fn test_arena_list_push_reuses_cells() {
+    let mut s: ArenaList<usize> = (0..100).collect();
+    for i in 0..100 {
+        s = s.pop().unwrap().1.push(i);
+    }
+    assert_eq!(s.length(), 100);
+    assert_eq!(s.arena().num_slots(), 100);
+}
+
+#[test]
+fn test_arena_list_append() {
+    let mut l: ArenaList<i32> = ArenaList::new();
+    l.append(1);
+    l = l.pop().unwrap().1;
+    l.append(2);
+    l = l.push(1);
+    l.append(3);
+    assert_eq!(l.to_string(), "(1, 2, 3)");
+    assert_eq!(l.last(), &3);
+    assert_eq!(l[1], 2);
+    assert_eq!(l.get(3), None);
+}
+
+#[test]
+fn test_arena_list_formatting() {
+    let l: ArenaList<i32> = vec![1, 2, 3].into_iter().collect();
+    assert_eq!(l.to_string(), "(1, 2, 3)");
+    assert_eq!(format!("{:?}", l), format!("{:?}", list!(1, 2, 3)));
+    assert_eq!(ArenaList::<i32>::new().to_string(), "()");
+    assert_eq!(format!("{:?}", ArenaList::<i32>::new()), "nil");
+}
+
+#[test]
+fn test_arena_list_iteration() {
+    let mut l: ArenaList<i32> = vec![1, 2, 3].into_iter().collect();
+    for item in &mut l {
+        *item *= 10;
+    }
+    assert_eq!(l.iter().len(), 3);
+    assert_eq!(l.iter().cloned().collect::<Vec<_>>(), vec![10, 20, 30]);
+    assert_eq!(l.into_iter().collect::<Vec<_>>(), vec![10, 20, 30]);
+}
+
+#[test]
+fn test_arena_list_combinators() {
+    let l: ArenaList<i32> = vec![1, 2, 3].into_iter().collect();
+    assert_eq!(l.map(|x| x * 2).to_string(), "(2, 4, 6)");
+    assert_eq!(l.fold(0, |acc, x| acc + x), 6);
+    let mut r = l.clone().rev();
+    assert_eq!(r.to_string(), "(3, 2, 1)");
+    assert_eq!(r.last(), &1);
+    r.append(0);
+    assert_eq!(r.to_string(), "(3, 2, 1, 0)");
+    assert_eq!(l.rev().rev(), vec![1, 2, 3].into_iter().collect());
+}
+
+#[test]
+fn test_arena_list_more_combinators() {
+    fn arena(items: Vec<i32>) -> ArenaList<i32> { items.into_iter().collect() }
+    let l = arena(vec![1, 2, 3, 4, 5]);
+
+    let mut odd = l.clone().filter(|x| x % 2 == 1);
+    assert_eq!(odd, arena(vec![1, 3, 5]));
+    assert_eq!(odd.arena().len(), 3);
+    odd.append(7);
+    assert_eq!(odd, arena(vec![1, 3, 5, 7]));
+    assert!(l.clone().filter(|_| false).is_empty());
+
+    let mut a = arena(vec![1, 2]);
+    a.append_list(arena(vec![3]));
+    a.append(4);
+    assert_eq!(a, arena(vec![1, 2, 3, 4]));
+    assert_eq!(a.concat(ArenaList::new()).concat(arena(vec![5])), l);
+
+    let pairs = l.clone().zip(arena(vec![6, 7]));
+    assert_eq!(pairs.iter().cloned().collect::<Vec<_>>(), vec![(1, 6), (2, 7)]);
+
+    let (mut front, mut back) = l.clone().split_at(2);
+    assert_eq!(front, arena(vec![1, 2]));
+    assert_eq!(back, arena(vec![3, 4, 5]));
+    assert_eq!(front.arena().len(), 2);
+    front.append(0);
+    back.append(0);
+    assert_eq!(front, arena(vec![1, 2, 0]));
+    assert_eq!(back, arena(vec![3, 4, 5, 0]));
+    let (mut front, back) = l.clone().split_at(0);
+    assert!(front.is_empty() && back == l);
+    front.append(1);
+    assert_eq!(front, arena(vec![1]));
+    let (front, back) = l.clone().split_at(10);
+    assert!(front == l && back.is_empty());
+    assert_eq!(l.clone().take(3), arena(vec![1, 2, 3]));
+    assert_eq!(l.clone().drop(3), arena(vec![4, 5]));
+
+    let lists: ArenaList<ArenaList<i32>> =
+        vec![arena(vec![1, 2]), ArenaList::new(), arena(vec![3, 4, 5])]
+            .into_iter().collect();
+    assert_eq!(lists.flatten(), l);
+}
+
+#[test]
+fn test_arena_list_eq_hash() {
+    let a: ArenaList<i32> = vec![1, 2, 3].into_iter().collect();
+    // the same items, in cells allocated in a different order
+    let b = ArenaList::new().push(3).push(2).push(1);
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    assert!(a != b.pop().unwrap().1);
+}
+
+#[test]
+fn test_arena_list_to_from_list() {
+    let l: ArenaList<i32> = ArenaList::from(list!(1, 2, 3));
+    assert_eq!(l.to_string(), "(1, 2, 3)");
+    assert_eq!(List::from(l), list!(1, 2, 3));
+}
+
+#[test]
+fn test_arena_list_long() {
+    let l: ArenaList<usize> = (0..1000000).collect();
+    assert_eq!(l.length(), 1000000);
+    assert_eq!(l.fold(0, |acc, x| acc + x), 499999500000);
+    let l = l.rev();
+    assert_eq!(l.peek(), Some(&999999));
+    assert_eq!(List::from(l.clone()).length(), 1000000);
+    drop(l);
+}
+
+#[test]
+fn test_arc_list_concurrent_drop_long() {
+    for _ in 0..20 {
+        let l: ArcList<usize> = (0..100000).collect();
+        let barrier = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = vec![l.clone(), l].into_iter()
+            .map(|l| {
+                let barrier = barrier.clone();
+                thread::spawn(move || { barrier.wait(); drop(l); })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}